@@ -1,7 +1,27 @@
 use chrono::{Duration, NaiveDate};
+use thiserror::Error;
 
 use crate::{human_date, todotxt};
 
+/// Errors that may happen while evaluating a date expression.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DateExprError {
+    #[error("Empty expression")]
+    Empty,
+    #[error("Failed to parse base date")]
+    InvalidBaseDate,
+    #[error("Incomplete expression: '{0}'")]
+    Incomplete(String),
+    #[error("Invalid character '{0}'")]
+    InvalidCharacter(char),
+    #[error("Invalid duration: '{0}'")]
+    InvalidDuration(String),
+    #[error("Invalid date [{0}]: {1}")]
+    InvalidDate(String, String), // date, reason
+    #[error("Recursion stack overflow")]
+    TooDeep,
+}
+
 #[derive(Debug)]
 struct ExprItem<'a> {
     pub sign: char,
@@ -213,10 +233,7 @@ fn parse_single_day(s: &str) -> Option<&str> {
 
 // Special date: tue, today, tomorrow etc
 fn parse_special(s: &str) -> Option<&str> {
-    let c = match s.chars().next() {
-        None => return None,
-        Some(cc) => cc,
-    };
+    let c = s.chars().next()?;
     if !c.is_ascii_lowercase() && !c.is_ascii_uppercase() {
         return None;
     }
@@ -235,37 +252,20 @@ fn parse_special(s: &str) -> Option<&str> {
 
 // Duration: ##D (1-2 digits and duration type DWMY)
 fn parse_duration(s: &str) -> Option<&str> {
-    let c = match s.chars().next() {
-        None => return None,
-        Some(cc) => cc,
-    };
+    let c = s.chars().next()?;
     let durs = ['d', 'D', 'w', 'W', 'm', 'M', 'y', 'Y'];
     if c.is_ascii_digit() {
         let idxl = match s.find(|c: char| !c.is_ascii_digit()) {
             None => return Some(s),
             Some(i) => i,
         };
-        let rest = &s[idxl..];
-        if rest.starts_with('-') || rest.starts_with('+') {
-            Some(&s[..idxl])
-        } else if let Some(cc) = rest.chars().next() {
-            if durs.contains(&cc) {
-                if s.len() == idxl + 1 {
-                    Some(s)
-                } else {
-                    let rest = &s[idxl + 1..];
-                    if rest.starts_with('-') || rest.starts_with('+') {
-                        Some(&s[..idxl + 1])
-                    } else {
-                        None
-                    }
-                }
-            } else {
-                None
-            }
-        } else {
-            None
+        let end = s[idxl..].find(['-', '+']).map_or(s.len(), |i| idxl + i);
+        let dur = &s[..end];
+        if end == idxl || (end == idxl + 1 && durs.contains(&s[idxl..].chars().next()?)) {
+            return Some(dur);
         }
+        // business days and months, and weeks with a weekday: `2b`, `1bm`, `1w@mon`
+        todotxt::Recurrence::parse(dur).ok().map(|_| dur)
     } else if durs.contains(&c) {
         match s.find(|c: char| !c.is_ascii_lowercase() && !c.is_ascii_uppercase()) {
             None => {
@@ -289,7 +289,7 @@ fn parse_duration(s: &str) -> Option<&str> {
     }
 }
 
-fn parse_base_date(s: &str) -> Result<ExprItem, String> {
+fn parse_base_date(s: &str) -> Result<ExprItem<'_>, DateExprError> {
     if let Some(st) = parse_special(s) {
         return Ok(ExprItem { sign: '+', val: st });
     }
@@ -302,10 +302,10 @@ fn parse_base_date(s: &str) -> Result<ExprItem, String> {
     if let Some(st) = parse_single_day(s) {
         return Ok(ExprItem { sign: '+', val: st });
     }
-    Err("Failed to parse base date".to_string())
+    Err(DateExprError::InvalidBaseDate)
 }
 
-fn parse_expression(s: &str) -> Result<Vec<ExprItem>, String> {
+fn parse_expression(s: &str) -> Result<Vec<ExprItem<'_>>, DateExprError> {
     let mut items = Vec::new();
     let mut st = match parse_base_date(s) {
         Err(e) => return Err(e),
@@ -317,18 +317,18 @@ fn parse_expression(s: &str) -> Result<Vec<ExprItem>, String> {
     };
     while !st.is_empty() {
         if st.len() < 2 {
-            return Err(format!("Incomplete expression: '{s}'"));
+            return Err(DateExprError::Incomplete(s.to_string()));
         }
         let c = match st.chars().next() {
             Some(cc) => cc,
-            None => return Err("Internal error".to_string()),
+            None => return Err(DateExprError::Incomplete(s.to_string())),
         };
         if c != '-' && c != '+' {
-            return Err(format!("Invalid character '{0}'", c));
+            return Err(DateExprError::InvalidCharacter(c));
         }
         st = &st[1..];
         match parse_duration(st) {
-            None => return Err(format!("Invalid duration: '{st}'")),
+            None => return Err(DateExprError::InvalidDuration(st.to_string())),
             Some(v) => {
                 let ei = ExprItem { sign: c, val: v };
                 st = &st[ei.val.len()..];
@@ -339,17 +339,17 @@ fn parse_expression(s: &str) -> Result<Vec<ExprItem>, String> {
     Ok(items)
 }
 
-fn parse_abs_date(base: NaiveDate, s: &str, soon_days: u8) -> Result<NaiveDate, String> {
+fn parse_abs_date(base: NaiveDate, s: &str, soon_days: u8) -> Result<NaiveDate, DateExprError> {
     match human_date::human_to_date(base, s, soon_days) {
         Ok(d) => Ok(d),
         Err(e) => {
             if e == human_date::NO_CHANGE {
                 match NaiveDate::parse_from_str(s, "%Y-%m-%d") {
                     Ok(d) => Ok(d),
-                    Err(e) => Err(format!("Invalid date [{s}]: {e}")),
+                    Err(e) => Err(DateExprError::InvalidDate(s.to_string(), e.to_string())),
                 }
             } else {
-                Err(e)
+                Err(DateExprError::InvalidDate(s.to_string(), e))
             }
         }
     }
@@ -361,12 +361,17 @@ fn calc_base(
     tags: &mut TaskTagList,
     soon_days: u8,
     counter: usize,
-) -> Result<NaiveDate, String> {
+) -> Result<NaiveDate, DateExprError> {
     let mut dt = base;
     if s.find(|c: char| !c.is_ascii_lowercase() && !c.is_ascii_uppercase()).is_none() {
         // Special date case
-        let spec = s.to_lowercase();
-        let tval = tags.tag_value(spec.as_str());
+        let mut spec = s.to_lowercase();
+        let mut tval = tags.tag_value(spec.as_str());
+        if spec == "thr" && tval == TagValueType::None {
+            // `thr` is an alias of the threshold tag `t` unless the todo has its own `thr:` tag
+            spec = "t".to_string();
+            tval = tags.tag_value(spec.as_str());
+        }
         match tval {
             TagValueType::None => {
                 dt = parse_abs_date(dt, s, soon_days)?;
@@ -393,14 +398,17 @@ fn calc_expr(
     tags: &mut TaskTagList,
     soon_days: u8,
     counter: usize,
-) -> Result<NaiveDate, String> {
+) -> Result<NaiveDate, DateExprError> {
     if counter > 10 {
-        return Err("Recursion stack overflow".to_string());
+        return Err(DateExprError::TooDeep);
+    }
+    if s.is_empty() {
+        return Err(DateExprError::Empty);
     }
 
     let items = parse_expression(s)?;
     if items.is_empty() {
-        return Err("Empty expression".to_string());
+        return Err(DateExprError::Empty);
     }
 
     let mut dt = base;
@@ -417,8 +425,8 @@ fn calc_expr(
                 };
                 let rc = match todotxt::Recurrence::parse(&rec_str) {
                     Ok(r) => r,
-                    Err(e) => {
-                        return Err(format!("Invalid duration '{0}': {e}", item.val));
+                    Err(_) => {
+                        return Err(DateExprError::InvalidDuration(item.val.to_string()));
                     }
                 };
                 match rc.period {
@@ -444,7 +452,9 @@ fn calc_expr(
                     todotxt::Period::Year => {
                         dt = human_date::add_years(dt, rc.count.into(), item.sign == '-');
                     }
-                    _ => {}
+                    todotxt::Period::BusinessDay | todotxt::Period::BusinessMonth | todotxt::Period::WeekDay(_) => {
+                        dt = if item.sign == '-' { rc.prev_date(dt) } else { rc.next_date(dt) };
+                    }
                 }
             }
         }
@@ -461,14 +471,36 @@ fn calc_expr(
 ///         like `today` or `sun`
 ///     operation is either `+` or `-`
 ///     single-range is the same format as recurrence #R (a number followed by a range type DWMY),
-///         if range type is missing, it defaults to `d`=days
+///         if range type is missing, it defaults to `d`=days. Business days `b`, business
+///         months `bm`, and weeks with a weekday `w@mon` are supported as well
 /// Examples:
 ///     `due+2` ==> 2 days after due date
 ///     `due+2d` ==> the same as above: 2 days after due date
-///     `t-2w` ==> 2 weeks before threshold date
+///     `t-2w` ==> 2 weeks before threshold date, `thr-2w` is the same
 ///     `due+1w+2d` ==> 1 week and 2 days after due date
+///     `eom-2d` ==> 2 days before the end of the current month
+/// Besides tag names, the base date can be one of special words: `today`, `soon`,
+/// weekday names (`mon`, `monday`), `bom` (beginning of the month), `eom` (end of
+/// the month), `eoy` (end of the year), etc.
 pub fn calculate_expr(base: NaiveDate, s: &str, tags: &mut TaskTagList, soon_days: u8) -> Result<NaiveDate, String> {
-    calc_expr(base, s, tags, soon_days, 1)
+    calc_expr(base, s, tags, soon_days, 1).map_err(|e| e.to_string())
+}
+
+/// Evaluates a date expression for a todo without modifying the todo.
+/// Useful to preview the result of an expression before applying it.
+///
+/// * `base` - the date used for relative dates like `today` or `tue`
+/// * `expr` - the expression in the format described in `calculate_expr`,
+///   e.g. `due+1w` or `t-2d`
+/// * `task` - the todo which dates (`due`, `t` or `thr`, `created`) and tags can be
+///   used in the expression
+/// * `soon_days` - the number of days for the special date `soon`
+///
+/// Example: for a todo with `due:2023-01-31`, the expression `due+1m-1d`
+/// evaluates to `2023-02-27`.
+pub fn eval(base: NaiveDate, expr: &str, task: &todotxt::Task, soon_days: u8) -> Result<NaiveDate, DateExprError> {
+    let mut tags = TaskTagList::from_task(task);
+    calc_expr(base, expr, &mut tags, soon_days, 1)
 }
 
 /// Calculates values of the main todo.txt tags.
@@ -539,13 +571,13 @@ mod date_expr_test {
             match r {
                 Some(rr) => {
                     if test.err {
-                        assert!(false, "Test [{0}] must fail", test.txt);
+                        panic!("Test [{0}] must fail", test.txt);
                     }
                     assert_eq!(test.res, rr, "Failed [{0}]: {:?}", test.txt);
                 }
                 None => {
                     if !test.err {
-                        assert!(false, "Test [{0}] must pass", test.txt);
+                        panic!("Test [{0}] must pass", test.txt);
                     }
                 }
             }
@@ -568,13 +600,13 @@ mod date_expr_test {
             match r {
                 Some(rr) => {
                     if test.err {
-                        assert!(false, "Test [{0}] must fail", test.txt);
+                        panic!("Test [{0}] must fail", test.txt);
                     }
                     assert_eq!(test.res, rr, "Failed [{0}]: {:?}", test.txt);
                 }
                 None => {
                     if !test.err {
-                        assert!(false, "Test [{0}] must pass", test.txt);
+                        panic!("Test [{0}] must pass", test.txt);
                     }
                 }
             }
@@ -603,13 +635,13 @@ mod date_expr_test {
             match r {
                 Some(rr) => {
                     if test.err {
-                        assert!(false, "Test [{0}] must fail", test.txt);
+                        panic!("Test [{0}] must fail", test.txt);
                     }
                     assert_eq!(test.res, rr, "Failed [{0}]: {:?}", test.txt);
                 }
                 None => {
                     if !test.err {
-                        assert!(false, "Test [{0}] must pass", test.txt);
+                        panic!("Test [{0}] must pass", test.txt);
                     }
                 }
             }
@@ -630,19 +662,23 @@ mod date_expr_test {
             Test { txt: "20days", err: true, res: "" },
             Test { txt: "20/4", err: true, res: "" },
             Test { txt: "20w/4", err: true, res: "" },
+            Test { txt: "2b+1", err: false, res: "2b" },
+            Test { txt: "1bm", err: false, res: "1bm" },
+            Test { txt: "1w@mon-2d", err: false, res: "1w@mon" },
+            Test { txt: "1w@xyz", err: true, res: "" },
         ];
         for test in tests.iter() {
             let r = parse_duration(test.txt);
             match r {
                 Some(rr) => {
                     if test.err {
-                        assert!(false, "Test [{0}] must fail", test.txt);
+                        panic!("Test [{0}] must fail", test.txt);
                     }
                     assert_eq!(test.res, rr, "Failed [{0}]: {:?}", test.txt);
                 }
                 None => {
                     if !test.err {
-                        assert!(false, "Test [{0}] must pass", test.txt);
+                        panic!("Test [{0}] must pass", test.txt);
                     }
                 }
             }
@@ -673,7 +709,7 @@ mod date_expr_test {
             match r {
                 Ok(rr) => {
                     if test.err {
-                        assert!(false, "Test [{0}] must fail", test.txt);
+                        panic!("Test [{0}] must fail", test.txt);
                     }
                     assert_eq!(test.l, rr.len(), "{0} expected {1} items, got {2}", test.txt, test.l, rr.len());
                     assert_eq!(
@@ -687,7 +723,7 @@ mod date_expr_test {
                 }
                 Err(e) => {
                     if !test.err {
-                        assert!(false, "Test [{0}] must pass: {e:?}", test.txt);
+                        panic!("Test [{0}] must pass: {e:?}", test.txt);
                     }
                 }
             }
@@ -706,13 +742,18 @@ mod date_expr_test {
             ETest { txt: "2021-05-07+10d", err: false, res: NaiveDate::from_ymd_opt(2021, 5, 17).unwrap() },
             ETest { txt: "2021-05-07+2w", err: false, res: NaiveDate::from_ymd_opt(2021, 5, 21).unwrap() },
             ETest { txt: "2021-05-07-7d", err: false, res: NaiveDate::from_ymd_opt(2021, 4, 30).unwrap() },
-            ETest { txt: "2021-05-07-2m", err: false, res: NaiveDate::from_ymd_opt(2021, 3, 07).unwrap() },
-            ETest { txt: "2021-05-07+1y", err: false, res: NaiveDate::from_ymd_opt(2022, 5, 07).unwrap() },
+            ETest { txt: "2021-05-07-2m", err: false, res: NaiveDate::from_ymd_opt(2021, 3, 7).unwrap() },
+            ETest { txt: "2021-05-07+1y", err: false, res: NaiveDate::from_ymd_opt(2022, 5, 7).unwrap() },
             ETest { txt: "2021-05-07+12d-2d", err: false, res: NaiveDate::from_ymd_opt(2021, 5, 17).unwrap() },
             ETest { txt: "2021-05-07+12d-1w", err: false, res: NaiveDate::from_ymd_opt(2021, 5, 12).unwrap() },
             ETest { txt: "today", err: false, res: NaiveDate::from_ymd_opt(2020, 3, 15).unwrap() },
             ETest { txt: "yesterday+2d", err: false, res: NaiveDate::from_ymd_opt(2020, 3, 16).unwrap() },
             ETest { txt: "first+1w", err: false, res: NaiveDate::from_ymd_opt(2020, 4, 8).unwrap() },
+            ETest { txt: "eom", err: false, res: NaiveDate::from_ymd_opt(2020, 3, 31).unwrap() },
+            ETest { txt: "eom-2d", err: false, res: NaiveDate::from_ymd_opt(2020, 3, 29).unwrap() },
+            ETest { txt: "bom+1m", err: false, res: NaiveDate::from_ymd_opt(2020, 4, 1).unwrap() },
+            ETest { txt: "EOY", err: false, res: NaiveDate::from_ymd_opt(2020, 12, 31).unwrap() },
+            ETest { txt: "monday+1w", err: false, res: NaiveDate::from_ymd_opt(2020, 3, 23).unwrap() },
             ETest { txt: "due+1d", err: false, res: NaiveDate::from_ymd_opt(2020, 4, 9).unwrap() },
             ETest { txt: "t-1d", err: false, res: NaiveDate::from_ymd_opt(2020, 4, 3).unwrap() },
             ETest { txt: "extra+1w", err: false, res: NaiveDate::from_ymd_opt(2022, 9, 23).unwrap() },
@@ -728,13 +769,12 @@ mod date_expr_test {
             let d = calculate_expr(base, test.txt, &mut tlist, 8);
             if test.err {
                 if d.is_ok() {
-                    assert!(false, "Test {idx}.[{0}] must fail", test.txt);
+                    panic!("Test {idx}.[{0}] must fail", test.txt);
                 }
             } else {
-                if d.is_err() {
-                    assert!(false, "Test {idx}.[{0}] must pass: {1:?}", test.txt, d);
-                } else {
-                    assert_eq!(d.unwrap(), test.res, "Test {idx}.[{0}]", test.txt);
+                match d {
+                    Err(e) => panic!("Test {idx}.[{0}] must pass: {1:?}", test.txt, e),
+                    Ok(dt) => assert_eq!(dt, test.res, "Test {idx}.[{0}]", test.txt),
                 }
             }
         }
@@ -793,12 +833,12 @@ mod date_expr_test {
             match res {
                 Err(e) => {
                     if !test.is_err {
-                        assert!(false, "{idx}. The test must not fail. Got {0:?}", e);
+                        panic!("{idx}. The test must not fail. Got {0:?}", e);
                     }
                 }
                 Ok(d) => {
                     if test.is_err {
-                        assert!(false, "{idx}. The test must fail");
+                        panic!("{idx}. The test must fail");
                     } else {
                         let ds = todotxt::format_date(d);
                         assert_eq!(test.value, ds.as_str(), "Expected date: {0}, got {1}", test.value, d);
@@ -831,4 +871,39 @@ mod date_expr_test {
             assert_eq!(new_str.as_str(), test.val, "{idx}. Must be equal [{0}], got [{new_str}]", test.val);
         }
     }
+    #[test]
+    fn eval_test() {
+        let base = NaiveDate::from_ymd_opt(2023, 1, 10).unwrap();
+        let task = todotxt::Task::parse("pay rent due:2023-01-31 t:2023-01-25", base);
+        let orig = task.clone();
+
+        let d = eval(base, "due+1m-1d", &task, 7).unwrap();
+        assert_eq!(d, NaiveDate::from_ymd_opt(2023, 2, 27).unwrap());
+        let d = eval(base, "thr+1d", &task, 7).unwrap();
+        assert_eq!(d, NaiveDate::from_ymd_opt(2023, 1, 26).unwrap());
+        let d = eval(base, "t+1d", &task, 7).unwrap();
+        assert_eq!(d, NaiveDate::from_ymd_opt(2023, 1, 26).unwrap());
+        let d = eval(base, "today+1w", &task, 7).unwrap();
+        assert_eq!(d, NaiveDate::from_ymd_opt(2023, 1, 17).unwrap());
+        // 2023-01-31 is Tuesday
+        let d = eval(base, "due+1bm", &task, 7).unwrap();
+        assert_eq!(d, NaiveDate::from_ymd_opt(2023, 2, 28).unwrap());
+        let d = eval(base, "due-1bm", &task, 7).unwrap();
+        assert_eq!(d, NaiveDate::from_ymd_opt(2022, 12, 30).unwrap());
+        let d = eval(base, "due+1w@mon", &task, 7).unwrap();
+        assert_eq!(d, NaiveDate::from_ymd_opt(2023, 2, 6).unwrap());
+        let d = eval(base, "due-1w@mon", &task, 7).unwrap();
+        assert_eq!(d, NaiveDate::from_ymd_opt(2023, 1, 30).unwrap());
+        let d = eval(base, "due+4b", &task, 7).unwrap();
+        assert_eq!(d, NaiveDate::from_ymd_opt(2023, 2, 6).unwrap());
+        let d = eval(base, "due-2b", &task, 7).unwrap();
+        assert_eq!(d, NaiveDate::from_ymd_opt(2023, 1, 27).unwrap());
+        // the todo is not modified
+        assert_eq!(task, orig);
+
+        assert_eq!(eval(base, "", &task, 7), Err(DateExprError::Empty));
+        assert_eq!(eval(base, "due+", &task, 7), Err(DateExprError::Incomplete("due+".to_string())));
+        assert_eq!(eval(base, "due+1q", &task, 7), Err(DateExprError::InvalidDuration("1q".to_string())));
+        assert!(matches!(eval(base, "abc+1d", &task, 7), Err(DateExprError::InvalidDate(..))));
+    }
 }
@@ -291,6 +291,60 @@ fn abs_time_diff(base: NaiveDate, human: &str, back: bool) -> HumanResult {
     Ok(dt)
 }
 
+/// The first day of a week for week-based calculations, e.g. `end of week`
+/// phrase or the current week range. Default is `Monday`.
+///
+/// Business days and weekday-anchored recurrences (`rec:1w@mon`) do not
+/// depend on it: weekends are always Saturday and Sunday, and a recurrence
+/// always snaps to the given weekday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+impl WeekStart {
+    /// Returns the first day of a week.
+    pub fn first_weekday(self) -> Weekday {
+        match self {
+            WeekStart::Monday => Weekday::Mon,
+            WeekStart::Sunday => Weekday::Sun,
+        }
+    }
+
+    /// Returns the last day of a week.
+    pub fn last_weekday(self) -> Weekday {
+        self.first_weekday().pred()
+    }
+
+    /// Returns the number of days from the first day of a week to `date`.
+    fn days_from_start(self, date: NaiveDate) -> i64 {
+        match self {
+            WeekStart::Monday => date.weekday().num_days_from_monday() as i64,
+            WeekStart::Sunday => date.weekday().num_days_from_sunday() as i64,
+        }
+    }
+}
+
+/// Converts the old `first_sunday` flag of calendar functions:
+/// `true` is `Sunday`, `false` is `Monday`.
+impl From<bool> for WeekStart {
+    fn from(first_sunday: bool) -> Self {
+        if first_sunday {
+            WeekStart::Sunday
+        } else {
+            WeekStart::Monday
+        }
+    }
+}
+
+/// Returns the first and the last days of the week that contains `today`.
+pub fn this_week(today: NaiveDate, week_start: WeekStart) -> (NaiveDate, NaiveDate) {
+    let first = today - Duration::days(week_start.days_from_start(today));
+    (first, first + Duration::days(DAYS_PER_WEEK as i64 - 1))
+}
+
 /// Find the date of the next week day that is later than `base`.
 /// Examples:
 ///     Base date is Saturday: `dt` = `2024-11-16`
@@ -399,7 +453,20 @@ fn no_year_date(base: NaiveDate, human: &str) -> HumanResult {
 // Returns if a special day is always either in the future or in the past. E.g., `today` cannot be in
 // the past and `yesterday` cannot be in the future, so the function returns `true` for both.
 fn is_absolute(name: &str) -> bool {
-    matches!(name, "today" | "tomorrow" | "tmr" | "tm" | "yesterday" | "overdue")
+    matches!(name, "today" | "tomorrow" | "tmr" | "tm" | "yesterday" | "overdue" | "bom" | "eom" | "eoy")
+}
+
+fn weekday_by_name(name: &str) -> Option<Weekday> {
+    match name {
+        "monday" | "mon" | "mo" => Some(Weekday::Mon),
+        "tuesday" | "tue" | "tu" => Some(Weekday::Tue),
+        "wednesday" | "wed" | "we" => Some(Weekday::Wed),
+        "thursday" | "thu" | "th" => Some(Weekday::Thu),
+        "friday" | "fri" | "fr" => Some(Weekday::Fri),
+        "saturday" | "sat" | "sa" => Some(Weekday::Sat),
+        "sunday" | "sun" | "su" => Some(Weekday::Sun),
+        _ => None,
+    }
 }
 
 fn special_time_point(base: NaiveDate, human: &str, back: bool, soon_days: u8) -> HumanResult {
@@ -416,6 +483,12 @@ fn special_time_point(base: NaiveDate, human: &str, back: bool, soon_days: u8) -
             let dur = Duration::days(soon_days as i64);
             Ok(if back { base - dur } else { base + dur })
         }
+        "bom" => Ok(NaiveDate::from_ymd_opt(base.year(), base.month(), 1).unwrap_or(base)),
+        "eom" => {
+            let d = days_in_month(base.year(), base.month());
+            Ok(NaiveDate::from_ymd_opt(base.year(), base.month(), d).unwrap_or(base))
+        }
+        "eoy" => Ok(NaiveDate::from_ymd_opt(base.year(), 12, 31).unwrap_or(base)),
         "first" => {
             let mut y = base.year();
             let mut m = base.month();
@@ -460,56 +533,11 @@ fn special_time_point(base: NaiveDate, human: &str, back: bool, soon_days: u8) -
             d = days_in_month(y, m);
             Ok(NaiveDate::from_ymd_opt(y, m, d).unwrap_or(base))
         }
-        "monday" | "mon" | "mo" => {
-            if back {
-                Ok(prev_weekday(base, Weekday::Mon))
-            } else {
-                Ok(next_weekday(base, Weekday::Mon))
-            }
-        }
-        "tuesday" | "tue" | "tu" => {
-            if back {
-                Ok(prev_weekday(base, Weekday::Tue))
-            } else {
-                Ok(next_weekday(base, Weekday::Tue))
-            }
-        }
-        "wednesday" | "wed" | "we" => {
-            if back {
-                Ok(prev_weekday(base, Weekday::Wed))
-            } else {
-                Ok(next_weekday(base, Weekday::Wed))
-            }
-        }
-        "thursday" | "thu" | "th" => {
-            if back {
-                Ok(prev_weekday(base, Weekday::Thu))
-            } else {
-                Ok(next_weekday(base, Weekday::Thu))
-            }
-        }
-        "friday" | "fri" | "fr" => {
-            if back {
-                Ok(prev_weekday(base, Weekday::Fri))
-            } else {
-                Ok(next_weekday(base, Weekday::Fri))
-            }
-        }
-        "saturday" | "sat" | "sa" => {
-            if back {
-                Ok(prev_weekday(base, Weekday::Sat))
-            } else {
-                Ok(next_weekday(base, Weekday::Sat))
-            }
-        }
-        "sunday" | "sun" | "su" => {
-            if back {
-                Ok(prev_weekday(base, Weekday::Sun))
-            } else {
-                Ok(next_weekday(base, Weekday::Sun))
-            }
-        }
-        _ => Err(format!("invalid date '{human}'")),
+        _ => match weekday_by_name(&s) {
+            Some(wd) if back => Ok(prev_weekday(base, wd)),
+            Some(wd) => Ok(next_weekday(base, wd)),
+            None => Err(format!("invalid date '{human}'")),
+        },
     }
 }
 
@@ -548,6 +576,80 @@ pub fn human_to_date(base: NaiveDate, human: &str, soon_days: u8) -> HumanResult
     special_time_point(base, human, back, soon_days)
 }
 
+/// Converts a short English phrase to a date. The phrase is case-insensitive,
+/// and words can be separated with any number of spaces or tabs. Supported phrases:
+/// - everything `human_to_date` accepts: `today`, `tomorrow`, `yesterday`,
+///   `friday`, `3d`, `2w`, an absolute date `2024-11-17`, etc;
+/// - `next <weekday>`: the first given weekday after `base`, e.g. `next monday`;
+/// - `in <number> <unit>`: where unit is `day`, `week`, `month`, or `year`
+///   (in singular or plural form), e.g. `in 2 weeks`;
+/// - `end of week`, `end of month`, `end of year`. A week starts on Monday,
+///   use `parse_with_week_start` to change it.
+///
+/// Returns `None` if the phrase is not recognized.
+pub fn parse(s: &str, base: NaiveDate) -> Option<NaiveDate> {
+    parse_with_week_start(s, base, WeekStart::Monday)
+}
+
+/// Converts a short English phrase to a date like `parse` does, but week-based
+/// phrases, e.g. `end of week`, use `week_start` as the first day of a week.
+pub fn parse_with_week_start(s: &str, base: NaiveDate, week_start: WeekStart) -> Option<NaiveDate> {
+    let s = s.to_lowercase();
+    let words: Vec<&str> = s.split_whitespace().collect();
+    match words.as_slice() {
+        [word] => match human_to_date(base, word, 0) {
+            Ok(d) => Some(d),
+            Err(e) if e == NO_CHANGE => NaiveDate::parse_from_str(word, "%Y-%m-%d").ok(),
+            Err(_) => None,
+        },
+        ["next", day] => weekday_by_name(day).map(|wd| next_weekday(base, wd)),
+        ["in", num, unit] => {
+            let num: u32 = num.parse().ok()?;
+            let unit = match unit.strip_suffix('s').unwrap_or(unit) {
+                "day" => 'd',
+                "week" => 'w',
+                "month" => 'm',
+                "year" => 'y',
+                _ => return None,
+            };
+            if num == 0 {
+                return Some(base);
+            }
+            abs_time_diff(base, &format!("{num}{unit}"), false).ok()
+        }
+        ["end", "of", "week"] => Some(this_week(base, week_start).1),
+        ["end", "of", "month"] => {
+            NaiveDate::from_ymd_opt(base.year(), base.month(), days_in_month(base.year(), base.month()))
+        }
+        ["end", "of", "year"] => NaiveDate::from_ymd_opt(base.year(), 12, 31),
+        _ => None,
+    }
+}
+
+/// Returns a short human-readable description of `date` relative to `base`,
+/// e.g. `today`, `tomorrow`, `in 3 days`, `2 weeks ago`, `next month`.
+/// Close dates are described in days, dates within four weeks or within the
+/// same month in weeks, and farther dates in calendar months and years.
+pub fn humanize(date: NaiveDate, base: NaiveDate) -> String {
+    let days = (date - base).num_days();
+    let months = (date.year() * 12 + date.month() as i32) - (base.year() * 12 + base.month() as i32);
+    let (n, unit, next, last) = match days.abs() {
+        0 => return "today".to_string(),
+        1 if days > 0 => return "tomorrow".to_string(),
+        1 => return "yesterday".to_string(),
+        2..=13 => (days.abs(), "day", "", ""),
+        d if d < 28 || months == 0 => (d / 7, "week", "", ""),
+        _ if months.abs() < 12 => (months.abs() as i64, "month", "next month", "last month"),
+        _ => ((months.abs() / 12) as i64, "year", "next year", "last year"),
+    };
+    match n {
+        1 if days > 0 => next.to_string(),
+        1 => last.to_string(),
+        _ if days > 0 => format!("in {n} {unit}s"),
+        _ => format!("{n} {unit}s ago"),
+    }
+}
+
 /// Replace a special word in due date with a real date.
 /// E.g, "due:sat" ==> "due:2022-07-09" for today between 2022-07-03 and 2022-07-09
 pub fn fix_date(base: NaiveDate, orig: &str, look_for: &str, soon_days: u8) -> Option<String> {
@@ -690,14 +792,14 @@ pub fn human_to_range(base: NaiveDate, human: &str, soon_days: u8) -> Result<tfi
 }
 
 /// Returns the first date of a calendar depending on the calendar range `rng`.
-/// The value `first_sunday` defines the first day of a week:
-///     `true` => Weekday::Sunday
-///     `false` => Weekday::Monday
+/// The value `week_start` defines the first day of a week. For compatibility,
+/// it can be a `bool` as well: `true` is Sunday, and `false` is Monday.
 /// For day ranges like `5d` the date is exact.
 /// For week ranges the date is always the first day of a week
 /// For month ranges the date is always the first day of a month
 /// For year ranges the date is always the first day of a year
-pub fn calendar_first_day(today: NaiveDate, rng: &CalendarRange, first_sunday: bool) -> NaiveDate {
+pub fn calendar_first_day(today: NaiveDate, rng: &CalendarRange, week_start: impl Into<WeekStart>) -> NaiveDate {
+    let week_start = week_start.into();
     match rng.rng {
         CalendarRangeType::Days(n) => {
             if n >= 0 {
@@ -709,16 +811,7 @@ pub fn calendar_first_day(today: NaiveDate, rng: &CalendarRange, first_sunday: b
         }
         CalendarRangeType::DayRange(n, _) => today.checked_add_signed(Duration::days(n.into())).unwrap_or(today),
         CalendarRangeType::Weeks(n) => {
-            let is_first =
-                (today.weekday() == Weekday::Sun && first_sunday) || (today.weekday() == Weekday::Mon && !first_sunday);
-            let today = if rng.strict || is_first {
-                today
-            } else {
-                match first_sunday {
-                    true => prev_weekday(today, Weekday::Sun),
-                    false => prev_weekday(today, Weekday::Mon),
-                }
-            };
+            let today = if rng.strict { today } else { this_week(today, week_start).0 };
             if rng.strict || n >= -1 {
                 return today;
             }
@@ -793,14 +886,14 @@ pub fn calendar_first_day(today: NaiveDate, rng: &CalendarRange, first_sunday: b
 }
 
 /// Returns the last date of a calendar depending on the calendar range `rng`.
-/// The value `first_sunday` defines the first day of a week:
-///     `true` => Weekday::Sunday
-///     `false` => Weekday::Monday
+/// The value `week_start` defines the first day of a week. For compatibility,
+/// it can be a `bool` as well: `true` is Sunday, and `false` is Monday.
 /// For day ranges like `5d` the date is exact.
 /// For week ranges the date is always the last day of a week
 /// For month ranges the date is always the last day of a month
 /// For year ranges the date is always the last day of a year
-pub fn calendar_last_day(today: NaiveDate, rng: &CalendarRange, first_sunday: bool) -> NaiveDate {
+pub fn calendar_last_day(today: NaiveDate, rng: &CalendarRange, week_start: impl Into<WeekStart>) -> NaiveDate {
+    let week_start = week_start.into();
     match rng.rng {
         CalendarRangeType::Days(n) => {
             if n <= 0 {
@@ -820,10 +913,7 @@ pub fn calendar_last_day(today: NaiveDate, rng: &CalendarRange, first_sunday: bo
                     Some(d) => d.checked_add_signed(Duration::days(-1)).unwrap_or(d),
                 };
             }
-            let today = match first_sunday {
-                true => next_weekday(today, Weekday::Sat),
-                false => next_weekday(today, Weekday::Sun),
-            };
+            let today = next_weekday(today, week_start.last_weekday());
             if n <= 1 {
                 return today;
             }
@@ -1180,7 +1270,7 @@ mod humandate_test {
             if test.err {
                 assert!(res.is_err(), "{}", test.txt);
             } else {
-                assert!(!res.is_err(), "{}", test.txt);
+                assert!(res.is_ok(), "{}", test.txt);
                 assert_eq!(res.unwrap(), test.val.unwrap(), "{}", test.txt);
             }
         }
@@ -1195,192 +1285,192 @@ mod humandate_test {
         }
         let tests: Vec<TestCal> = vec![
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(-1) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(-1) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 06, 27).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 6, 27).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 04).unwrap(), // Monday
+                td: NaiveDate::from_ymd_opt(2022, 7, 4).unwrap(), // Monday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(-1) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 04).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 4).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(-1) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 04).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 4).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(1) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(1) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 06, 27).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 6, 27).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 04).unwrap(), // Monday
+                td: NaiveDate::from_ymd_opt(2022, 7, 4).unwrap(), // Monday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(1) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 04).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 4).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(1) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 04).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 4).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Weeks(1) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Weeks(1) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Weeks(2) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(2) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 06, 27).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 6, 27).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(2) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Days(15) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Days(15) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Days(15) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Days(15) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Days(-5) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 06, 29).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 6, 29).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Days(-5) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 06, 29).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 6, 29).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Days(-5) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 06, 29).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 6, 29).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Days(-5) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 06, 29).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 6, 29).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Months(2) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Months(2) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Months(2) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 01).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 1).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Months(2) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 01).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 1).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Months(-2) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 05, 04).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 5, 4).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Months(-2) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 05, 04).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 5, 4).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Months(-2) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 06, 01).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 6, 1).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Months(-2) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 06, 01).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 6, 1).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Years(-1) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 01, 01).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 1, 1).unwrap(),
             },
         ];
         for test in tests.iter() {
@@ -1398,183 +1488,183 @@ mod humandate_test {
         }
         let tests: Vec<TestCal> = vec![
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(-1) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 09).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 9).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(-1) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 10).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 10).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 04).unwrap(), // Monday
+                td: NaiveDate::from_ymd_opt(2022, 7, 4).unwrap(), // Monday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(-1) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 09).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 9).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 04).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 4).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(-1) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 10).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 10).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(1) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 09).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 9).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(1) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 10).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 10).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 04).unwrap(), // Monday
+                td: NaiveDate::from_ymd_opt(2022, 7, 4).unwrap(), // Monday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(1) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 09).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 9).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 04).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 4).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(1) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 10).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 10).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Weeks(1) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 09).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 9).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Weeks(1) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 09).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 9).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 05).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 5).unwrap(), // Sunday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Weeks(2) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 16).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 16).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Weeks(2) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 16).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 16).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Days(15) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 17).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 17).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Days(15) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 17).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 17).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Days(15) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 17).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 17).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Days(15) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 17).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 17).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Days(-5) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Days(-5) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Days(-5) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Days(-5) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Months(2) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 09, 02).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 9, 2).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Months(2) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 09, 02).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 9, 2).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Months(2) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 08, 31).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 8, 31).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Months(2) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 08, 31).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 8, 31).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Months(-2) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: true, rng: CalendarRangeType::Months(-2) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(), // Sunday
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(), // Sunday
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Months(-2) },
                 sunday: true,
-                res: NaiveDate::from_ymd_opt(2022, 07, 31).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 31).unwrap(),
             },
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Months(-2) },
                 sunday: false,
-                res: NaiveDate::from_ymd_opt(2022, 07, 31).unwrap(),
+                res: NaiveDate::from_ymd_opt(2022, 7, 31).unwrap(),
             },
             // ---
             TestCal {
-                td: NaiveDate::from_ymd_opt(2022, 07, 03).unwrap(),
+                td: NaiveDate::from_ymd_opt(2022, 7, 3).unwrap(),
                 rng: CalendarRange { strict: false, rng: CalendarRangeType::Years(-1) },
                 sunday: false,
                 res: NaiveDate::from_ymd_opt(2022, 12, 31).unwrap(),
@@ -1585,4 +1675,84 @@ mod humandate_test {
             assert_eq!(res, test.res, "{} - SUN: {}, RANGE: {:?}", test.td, test.sunday, test.rng);
         }
     }
+
+    #[test]
+    fn parse_phrase_test() {
+        let base = NaiveDate::from_ymd_opt(2020, 7, 9).unwrap(); // Thursday
+        let tests: Vec<(&str, Option<NaiveDate>)> = vec![
+            ("today", Some(base)),
+            ("  Tomorrow ", NaiveDate::from_ymd_opt(2020, 7, 10)),
+            ("YESTERDAY", NaiveDate::from_ymd_opt(2020, 7, 8)),
+            ("next monday", NaiveDate::from_ymd_opt(2020, 7, 13)),
+            ("next\tThursday", NaiveDate::from_ymd_opt(2020, 7, 16)),
+            ("next fri", NaiveDate::from_ymd_opt(2020, 7, 10)),
+            ("in 2 weeks", NaiveDate::from_ymd_opt(2020, 7, 23)),
+            ("In   1 day", NaiveDate::from_ymd_opt(2020, 7, 10)),
+            ("in 3 days", NaiveDate::from_ymd_opt(2020, 7, 12)),
+            ("in 1 month", NaiveDate::from_ymd_opt(2020, 8, 9)),
+            ("in 0 years", Some(base)),
+            ("end of week", NaiveDate::from_ymd_opt(2020, 7, 12)),
+            ("end of month", NaiveDate::from_ymd_opt(2020, 7, 31)),
+            ("End Of Year", NaiveDate::from_ymd_opt(2020, 12, 31)),
+            ("3d", NaiveDate::from_ymd_opt(2020, 7, 12)),
+            ("2021-01-02", NaiveDate::from_ymd_opt(2021, 1, 2)),
+            ("", None),
+            ("next", None),
+            ("next week", None),
+            ("in two days", None),
+            ("in 2 fortnights", None),
+            ("end of day", None),
+            ("someday", None),
+        ];
+        for (s, res) in tests {
+            assert_eq!(parse(s, base), res, "{s:?}");
+        }
+    }
+
+    #[test]
+    fn week_start_test() {
+        // Wednesday
+        let base = NaiveDate::from_ymd_opt(2020, 7, 15).unwrap();
+        let d = |m, d| NaiveDate::from_ymd_opt(2020, m, d).unwrap();
+        assert_eq!(this_week(base, WeekStart::Monday), (d(7, 13), d(7, 19)));
+        assert_eq!(this_week(base, WeekStart::Sunday), (d(7, 12), d(7, 18)));
+        assert_eq!(WeekStart::default(), WeekStart::Monday);
+
+        // Sunday belongs to different weeks
+        let sun = d(7, 19);
+        assert_eq!(this_week(sun, WeekStart::Monday), (d(7, 13), d(7, 19)));
+        assert_eq!(this_week(sun, WeekStart::Sunday), (d(7, 19), d(7, 25)));
+
+        assert_eq!(parse("end of week", base), Some(d(7, 19)));
+        assert_eq!(parse_with_week_start("end of week", base, WeekStart::Monday), Some(d(7, 19)));
+        assert_eq!(parse_with_week_start("end of week", base, WeekStart::Sunday), Some(d(7, 18)));
+        assert_eq!(parse_with_week_start("end of week", sun, WeekStart::Sunday), Some(d(7, 25)));
+    }
+
+    #[test]
+    fn humanize_test() {
+        let base = NaiveDate::from_ymd_opt(2020, 7, 30).unwrap();
+        let tests: Vec<(NaiveDate, &str)> = vec![
+            (base, "today"),
+            (NaiveDate::from_ymd_opt(2020, 7, 31).unwrap(), "tomorrow"),
+            (NaiveDate::from_ymd_opt(2020, 7, 29).unwrap(), "yesterday"),
+            (NaiveDate::from_ymd_opt(2020, 8, 2).unwrap(), "in 3 days"),
+            (NaiveDate::from_ymd_opt(2020, 7, 27).unwrap(), "3 days ago"),
+            (NaiveDate::from_ymd_opt(2020, 8, 12).unwrap(), "in 13 days"),
+            (NaiveDate::from_ymd_opt(2020, 8, 13).unwrap(), "in 2 weeks"),
+            (NaiveDate::from_ymd_opt(2020, 7, 16).unwrap(), "2 weeks ago"),
+            (NaiveDate::from_ymd_opt(2020, 7, 2).unwrap(), "4 weeks ago"),
+            (NaiveDate::from_ymd_opt(2020, 8, 27).unwrap(), "next month"),
+            (NaiveDate::from_ymd_opt(2020, 6, 30).unwrap(), "last month"),
+            (NaiveDate::from_ymd_opt(2020, 10, 1).unwrap(), "in 3 months"),
+            (NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(), "6 months ago"),
+            (NaiveDate::from_ymd_opt(2021, 6, 1).unwrap(), "in 11 months"),
+            (NaiveDate::from_ymd_opt(2021, 8, 1).unwrap(), "next year"),
+            (NaiveDate::from_ymd_opt(2019, 7, 30).unwrap(), "last year"),
+            (NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(), "in 2 years"),
+        ];
+        for (dt, res) in tests {
+            assert_eq!(humanize(dt, base), res, "{dt}");
+        }
+    }
 }
@@ -16,6 +16,7 @@
 
 pub mod date_expr;
 pub mod human_date;
+pub mod prelude;
 pub mod terr;
 pub mod tfilter;
 pub mod timer;
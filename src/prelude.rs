@@ -0,0 +1,21 @@
+//! Re-exports of the most used types and functions.
+//!
+//! ```
+//! use todo_lib::prelude::*;
+//! ```
+//!
+//! The modules `todo`, `tfilter`, and `tsort` have their own `Conf` types, so
+//! the prelude exports them as `TodoConf`, `FilterConf`, and `SortConf`.
+//! `tfilter::Recurrence` is not exported to avoid a collision with
+//! `todotxt::Recurrence`.
+
+pub use crate::terr::TodoError;
+pub use crate::tfilter::{
+    filter, Conf as FilterConf, DateRange, ItemRange, TagFilter, TodoStatus, ValueRange, ValueSpan,
+};
+pub use crate::todo::{
+    add, archive, done, edit, load, remove, save, undone, Action, ChangedVec, Conf as TodoConf, DateTagChange, IDVec,
+    ListTagChange, NewDateValue, PriorityTagChange, TaskSlice, TaskVec,
+};
+pub use crate::todotxt::{CompletionConfig, CompletionDateMode, CompletionMode, Period, Recurrence, Task};
+pub use crate::tsort::{sort, Conf as SortConf};
@@ -1,5 +1,11 @@
+use std::sync::RwLock;
+
 use thiserror::Error;
 
+type WarningHandler = Box<dyn Fn(&str) + Send + Sync>;
+
+static WARNING_HANDLER: RwLock<Option<WarningHandler>> = RwLock::new(None);
+
 #[derive(Error, Debug)]
 pub enum TodoError {
     #[error("invalid value {0} for {1}")]
@@ -17,3 +23,29 @@ pub enum TodoError {
     #[error("I/O Error: {0}")]
     IOError(String),
 }
+
+/// Sets a function that receives all warnings emitted by the library, e.g.
+/// when a date expression cannot be calculated. By default, warnings are
+/// printed to stderr.
+pub fn set_warning_handler(f: impl Fn(&str) + Send + Sync + 'static) {
+    if let Ok(mut h) = WARNING_HANDLER.write() {
+        *h = Some(Box::new(f));
+    }
+}
+
+/// Restores the default warning handler that prints warnings to stderr.
+pub fn reset_warning_handler() {
+    if let Ok(mut h) = WARNING_HANDLER.write() {
+        *h = None;
+    }
+}
+
+pub(crate) fn warn(msg: &str) {
+    if let Ok(h) = WARNING_HANDLER.read() {
+        if let Some(f) = h.as_ref() {
+            f(msg);
+            return;
+        }
+    }
+    eprintln!("{msg}");
+}
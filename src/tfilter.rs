@@ -1,8 +1,11 @@
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 use regex::Regex;
 
+use crate::terr;
 use crate::timer;
 use crate::todo;
 use crate::todotxt;
+use crate::tsort;
 
 /// Setting unused end of Lower/Higher ValueRange makes the filter to include
 /// todos that have a given date field undefined
@@ -22,6 +25,11 @@ pub enum ItemRange {
     /// Values can exceed the number of todos. All IDs greater than the number
     /// of todos are skipped
     Range(usize, usize),
+    /// half-open range of records `(from, to)`: `to` is not included.
+    /// `RangeExclusive(0, 10)` means the first ten records: 0 through 9.
+    /// Convenient for paging: page `n` of size `len` is
+    /// `RangeExclusive(n * len, (n + 1) * len)`. An empty range selects nothing
+    RangeExclusive(usize, usize),
     /// List of IDs
     List(Vec<usize>),
 }
@@ -29,14 +37,19 @@ pub enum ItemRange {
 /// Todo state range
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum TodoStatus {
-    /// Only todos that are incompleted yet
+    /// Only todos that are incompleted yet. Todos which threshold date is
+    /// after today are not actionable yet and are hidden, unless `Conf.thr`
+    /// is set (see `Task::has_future_threshold`). Todos without threshold
+    /// date are always shown
     Active,
-    /// All todos
+    /// All todos, including todos with threshold date in the future
     All,
     /// Only todos marked `done`
     Done,
     /// Only empty todos
     Empty,
+    /// Only incompleted todos that have recurrence
+    Recurring,
 }
 
 /// An arbitrary range of values for todo properties check. The range is inclusive
@@ -46,13 +59,20 @@ pub struct ValueRange {
     pub high: i64,
 }
 
+impl ValueRange {
+    /// Returns true if the value is within the inclusive range.
+    pub fn contains(&self, value: i64) -> bool {
+        value >= self.low && value <= self.high
+    }
+}
+
 /// A type of comparison for the property.
 ///
 /// Every property supports only a limited subset:
 /// * `project` and `context`: do not use `ValueSpan` because they always search for a given text;
-/// * `priority`: `None`, `Any`, `Equal`, `Lower`, and `Higher`;
+/// * `priority`: `None`, `Any`, `Equal`, `Lower`, and `Higher` (for a range of priorities, see `PriorityRange`);
 /// * `recurrence`: `None` and `Any`;
-/// * `due`: `None`, `Any`, `Lower`, and `Range`;
+/// * `due`, `threshold`, `created`, and `finished`: `None`, `Any`, `Lower`, `Higher`, `Range`, and `Dates`;
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ValueSpan {
     /// Do not check the property value
@@ -71,10 +91,14 @@ pub enum ValueSpan {
     Range, // from - to
     /// Timer is running
     Active,
+    /// Date property must be within the inclusive range of absolute dates
+    /// `(from, to)`. `None` for a range end means the range is open from
+    /// that side. `DateRange::between` creates a date range with this span
+    Dates(Option<NaiveDate>, Option<NaiveDate>),
 }
 
-/// For filtering by date range or value. `days` is inclusive range and
-/// is not used when `span` is `Any` or `None`
+/// For filtering by date range or value. `days` is inclusive range of day
+/// offsets from today and is not used when `span` is `Any`, `None`, or `Dates`
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DateRange {
     pub days: ValueRange,
@@ -86,6 +110,15 @@ impl Default for DateRange {
     }
 }
 
+impl DateRange {
+    /// Creates an inclusive range of absolute dates, e.g. to select todos due
+    /// within a calendar month. `None` for a range end means the range is open
+    /// from that side. Todos without the date never match the range.
+    pub fn between(from: Option<NaiveDate>, to: Option<NaiveDate>) -> DateRange {
+        DateRange { span: ValueSpan::Dates(from, to), days: Default::default() }
+    }
+}
+
 /// For filtering by recurrence. Only `Any` and `None` are supported
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Recurrence {
@@ -109,20 +142,42 @@ impl Default for Priority {
     }
 }
 
-/// For filtering by timer
+/// For filtering by a range of priorities. The range is inclusive. Values are
+/// priority numbers: `0` is `A`, `1` is `B` etc. If `low` is greater than
+/// `high`, the values are swapped, so `A..C` and `C..A` select the same todos.
+/// Todos without priority are selected only if `include_none` is `true`:
+/// they are treated as the lowest priority below the bottom end of any range,
+/// like `INCLUDE_NONE` adds todos without a date to a date range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriorityRange {
+    pub low: u8,
+    pub high: u8,
+    pub include_none: bool,
+}
+
+/// For filtering by timer. `span` can be:
+/// * `None` - the timer is not running;
+/// * `Active` - the timer is running;
+/// * `Higher` - the time spent, in seconds, is equal to or greater than `value`;
+/// * `Lower` - the time spent, in seconds, is equal to or less than `value`;
+/// * `Range` - the time spent, in seconds, is within inclusive `range`.
+///
+/// The time spent on a todo with running timer includes the time elapsed
+/// since the timer was started.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Timer {
     pub span: ValueSpan,
     pub value: usize,
+    pub range: ValueRange,
 }
 impl Default for Timer {
     fn default() -> Timer {
-        Timer { value: 0, span: ValueSpan::None }
+        Timer { value: 0, span: ValueSpan::None, range: Default::default() }
     }
 }
 
 /// Filter rules for special entities: projects, contexts, tags.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct TagFilter {
     /// List of all project that a todo must include. The search
     /// supports very limited pattern matching:
@@ -150,6 +205,10 @@ pub struct TagFilter {
     /// * `*foo` - finds all todos with tags that ends with `foo`
     /// * `*foo*` - finds all todos with tags that contains `foo`
     ///
+    /// A filter can include a value pattern after a colon: `id:PROJ-*` finds
+    /// all todos with tag `id` which value starts with `PROJ-`. The value
+    /// pattern supports the same matching rules.
+    ///
     /// Special values:
     /// * none - select todos with no tags
     /// * any - select todos that have at least one tag
@@ -164,6 +223,18 @@ pub struct TagFilter {
     /// * none - select todos with no hashtags
     /// * any - select todos that have at least one tag
     pub hashtags: Vec<String>,
+    /// How values of `projects` are matched:
+    /// * `false` - a todo matches the list if it matches any value of the list;
+    /// * `true` - a todo matches the list only if it matches all values of the list.
+    pub require_all_projects: bool,
+    /// How values of `contexts` are matched. See `require_all_projects`
+    pub require_all_contexts: bool,
+    /// How values of `tags` are matched. See `require_all_projects`
+    pub require_all_tags: bool,
+    /// How values of `hashtags` are matched. See `require_all_projects`.
+    /// E.g., `hashtags: ["home", "urgent"]` with `require_all_hashtags`
+    /// selects todos that have both `#home` and `#urgent`
+    pub require_all_hashtags: bool,
 }
 
 /// A rules for todo list filtering. Setting a field to None or empty vector
@@ -178,6 +249,18 @@ pub struct Conf {
     /// If it is `true`, `regex` is treated as regular expression. If `use_regex`
     /// is `false`, the value of `regex` is just a substring to search for
     pub use_regex: bool,
+    /// Todos which text contains this value are excluded from the list. It is
+    /// checked after `regex`, so if both are set, a todo must contain `regex`
+    /// and must not contain `exclude_regex`
+    pub exclude_regex: Option<String>,
+    /// If it is `true`, `exclude_regex` is treated as regular expression.
+    /// Otherwise, it is just a substring to search for
+    pub exclude_use_regex: bool,
+    /// Match `regex` and `exclude_regex` against the whole todo line, including
+    /// completion mark, priority, and dates, instead of the subject only. The
+    /// line is the text that `Display` writes for a todo, e.g. a completed todo
+    /// with priority is matched as `x (B) 2020-01-01 subject`
+    pub regex_full_line: bool,
 
     /// Todos must contain the following values to be included in the list.
     pub include: TagFilter,
@@ -189,94 +272,173 @@ pub struct Conf {
 
     /// Search for a due date: any, no due date, or withing range
     pub due: Option<DateRange>,
-    /// Search for a threshold date: any, no threshold date, or withing range
+    /// Search for a threshold date: any, no threshold date, or withing range.
+    /// If it is `None` and `all` is not `TodoStatus::All`, todos with threshold
+    /// date after today are skipped
     pub thr: Option<DateRange>,
     /// Search for recurrent todos
     pub rec: Option<Recurrence>,
+    /// Search for recurrent todos with the given cadence regardless of their
+    /// strictness, e.g. `rec:1m` selects both `rec:1m` and `rec:+1m` todos
+    pub rec_cadence: Option<todotxt::Recurrence>,
     /// Search for todos with priority or priority range
     pub pri: Option<Priority>,
+    /// Search for todos which priority is within the inclusive range, e.g. from `A` to `C`
+    pub pri_range: Option<PriorityRange>,
     /// Search for todos with timer related stuff: active, inactive, any time spent
     pub tmr: Option<Timer>,
+    /// Search for todos which timer has been running for at least the given
+    /// number of hours (the time spent before the timer was started is not
+    /// counted). Todos with stopped timers never match.
+    pub tmr_running_hours: Option<u32>,
+    /// Search for todos with the number of `key:value` tags within inclusive
+    /// range. Projects, contexts, and hashtags are not counted. E.g., `low: 2`
+    /// and `high: i64::MAX` selects todos with two or more tags
+    pub tag_count: Option<ValueRange>,
+    /// Search for todos which due date is at most the given number of days
+    /// after their creation date. Todos without any of the dates never match
+    pub due_within_days_of_creation: Option<u32>,
+    /// Search for todos modified on or after the given date: the date is taken
+    /// from the tag `modified:` (see `Task::touch`). Todos without the tag,
+    /// or with an invalid date in it, never match
+    pub modified_since: Option<NaiveDate>,
+    /// Search for todos that have at least the given number of projects
+    pub min_projects: Option<usize>,
+    /// Search for todos that have at least the given number of contexts
+    pub min_contexts: Option<usize>,
+    /// Search for todos that have both the project and the context of any
+    /// pair `(project, context)`. Names are compared case-insensitively,
+    /// without pattern matching. Empty list disables the check
+    pub pairs: Vec<(String, String)>,
     /// Search for a creation date: any, no creation date, or withing range
     pub created: Option<DateRange>,
     /// Search for a finished date: any, no finish date, or withing range
     pub finished: Option<DateRange>,
+    /// Search for todos which due date equals their threshold date:
+    /// * `Some(true)` - both dates are set and they are the same day;
+    /// * `Some(false)` - all other todos.
+    pub due_equals_threshold: Option<bool>,
+    /// Search for scheduled todos:
+    /// * `Some(true)` - todos that have due date, threshold date, or both;
+    /// * `Some(false)` - todos that have neither due nor threshold date.
+    pub scheduled: Option<bool>,
+    /// Search for todos that have only projects, contexts, hashtags, and tags
+    /// in their subject, without any regular word (see `Task::word_count`):
+    /// * `Some(true)` - non-empty todos without regular words, e.g. `+proj @ctx`;
+    /// * `Some(false)` - todos that have at least one regular word.
+    pub metadata_only: Option<bool>,
+    /// How include lists of projects, contexts, tags, and hashtags are combined:
+    /// * `false` - a todo must match all non-empty include lists;
+    /// * `true` - a todo must match at least one non-empty include list, e.g.
+    ///   `project=car OR context=kids`.
+    ///
+    /// In both cases, a todo matching any exclude list is skipped, and all other
+    /// filters (ID range, status, text search, dates etc) must be met.
+    pub match_any: bool,
 }
 
 impl Default for Conf {
     fn default() -> Conf {
         Conf {
             range: ItemRange::None,
-            include: TagFilter { projects: Vec::new(), contexts: Vec::new(), tags: Vec::new(), hashtags: Vec::new() },
-            exclude: TagFilter { projects: Vec::new(), contexts: Vec::new(), tags: Vec::new(), hashtags: Vec::new() },
+            include: TagFilter::default(),
+            exclude: TagFilter::default(),
             regex: None,
             use_regex: false,
+            exclude_regex: None,
+            exclude_use_regex: false,
+            regex_full_line: false,
 
             all: TodoStatus::Active,
             due: None,
             thr: None,
             rec: None,
+            rec_cadence: None,
             pri: None,
+            pri_range: None,
             tmr: None,
+            tmr_running_hours: None,
+            tag_count: None,
+            due_within_days_of_creation: None,
+            pairs: Vec::new(),
+            modified_since: None,
+            min_projects: None,
+            min_contexts: None,
             created: None,
             finished: None,
+            due_equals_threshold: None,
+            scheduled: None,
+            metadata_only: None,
+            match_any: false,
         }
     }
 }
 
-fn filter_regex(tasks: &todo::TaskSlice, v: todo::IDVec, c: &Conf) -> todo::IDVec {
-    let rx = match &c.regex {
-        None => return v,
+// Precompiled rule to check todo's text
+enum TextMatcher {
+    Any,
+    Substr(String),
+    Regex(Regex),
+}
+
+struct TextMatchers {
+    include: TextMatcher,
+    exclude: TextMatcher,
+}
+
+fn text_matcher(patt: Option<&String>, use_regex: bool) -> TextMatcher {
+    let rx = match patt {
+        None => return TextMatcher::Any,
         Some(s) => s,
     };
+    if !use_regex {
+        return TextMatcher::Substr(rx.to_lowercase());
+    }
+    match Regex::new(&format!("(?i){rx}")) {
+        Err(e) => {
+            terr::warn(&format!("Invalid regex: {e}"));
+            TextMatcher::Any
+        }
+        Ok(r) => TextMatcher::Regex(r),
+    }
+}
 
-    let mut new_v: todo::IDVec = Vec::new();
-    if c.use_regex {
-        let rx = match Regex::new(&format!("(?i){rx}")) {
-            Err(e) => {
-                eprintln!("Invalid regex: {}", e);
-                return v;
-            }
-            Ok(v) => v,
-        };
+fn text_matchers(c: &Conf) -> TextMatchers {
+    TextMatchers {
+        include: text_matcher(c.regex.as_ref(), c.use_regex),
+        exclude: text_matcher(c.exclude_regex.as_ref(), c.exclude_use_regex),
+    }
+}
 
-        for i in v.iter() {
-            let idx = *i;
-            if idx >= tasks.len() {
-                continue;
-            }
-            if rx.is_match(&tasks[idx].subject) {
-                new_v.push(idx);
-            }
-        }
-        return new_v;
+// Returns `None` if there is nothing to look for.
+fn text_found(text: &str, m: &TextMatcher) -> Option<bool> {
+    match m {
+        TextMatcher::Any => None,
+        TextMatcher::Substr(s) => Some(text.to_lowercase().contains(s)),
+        TextMatcher::Regex(rx) => Some(rx.is_match(text)),
     }
+}
 
-    let rstr = rx.to_lowercase();
-    for i in v.iter() {
-        let idx = *i;
-        let low = tasks[idx].subject.to_lowercase();
-        if low.contains(&rstr) {
-            new_v.push(idx);
-            continue;
-        }
+fn regex_ok(task: &todotxt::Task, c: &Conf, m: &TextMatchers) -> bool {
+    if matches!(m.include, TextMatcher::Any) && matches!(m.exclude, TextMatcher::Any) {
+        return true;
     }
-    new_v
+    let line;
+    let text = if c.regex_full_line {
+        line = task.to_string();
+        &line
+    } else {
+        &task.subject
+    };
+    text_found(text, &m.include).unwrap_or(true) && !text_found(text, &m.exclude).unwrap_or(false)
 }
 
-fn filter_empty(tasks: &todo::TaskSlice, v: todo::IDVec, c: &Conf) -> todo::IDVec {
+fn empty_ok(task: &todotxt::Task, c: &Conf) -> bool {
     if c.all == TodoStatus::All {
-        return v;
-    }
-    let mut new_v: todo::IDVec = Vec::new();
-    for i in v.iter() {
-        let idx = *i;
-        let empty = tasks[idx].subject.is_empty();
-        if (empty && c.all == TodoStatus::Empty) || (!empty && c.all != TodoStatus::Empty) {
-            new_v.push(idx);
-        }
+        return true;
     }
-    new_v
+    let empty = task.subject.is_empty();
+    (empty && c.all == TodoStatus::Empty) || (!empty && c.all != TodoStatus::Empty)
 }
 
 fn vec_match(task_list: &[String], filter: &[String]) -> bool {
@@ -300,219 +462,206 @@ fn vec_match(task_list: &[String], filter: &[String]) -> bool {
     false
 }
 
-fn filter_context(tasks: &todo::TaskSlice, v: todo::IDVec, c: &Conf) -> todo::IDVec {
-    if c.include.contexts.is_empty() && c.exclude.contexts.is_empty() {
-        return v;
+fn list_match(task_list: &[String], filter: &[String], all: bool) -> bool {
+    if all {
+        filter.iter().all(|f| vec_match(task_list, std::slice::from_ref(f)))
+    } else {
+        vec_match(task_list, filter)
     }
+}
 
-    let mut new_v: todo::IDVec = Vec::new();
-    for i in v.iter() {
-        let idx = *i;
-        if !c.exclude.contexts.is_empty() && vec_match(&tasks[idx].contexts, &c.exclude.contexts) {
-            continue;
-        }
-        if c.include.contexts.is_empty() || vec_match(&tasks[idx].contexts, &c.include.contexts) {
-            new_v.push(idx);
-        }
+fn list_ok(list: &[String], include: (&[String], bool), exclude: (&[String], bool)) -> bool {
+    if !exclude.0.is_empty() && list_match(list, exclude.0, exclude.1) {
+        return false;
     }
-    new_v
+    include.0.is_empty() || list_match(list, include.0, include.1)
 }
 
-fn filter_project(tasks: &todo::TaskSlice, v: todo::IDVec, c: &Conf) -> todo::IDVec {
-    if c.include.projects.is_empty() && c.exclude.projects.is_empty() {
-        return v;
-    }
+fn context_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    list_ok(
+        &task.contexts,
+        (&c.include.contexts, c.include.require_all_contexts),
+        (&c.exclude.contexts, c.exclude.require_all_contexts),
+    )
+}
 
-    let mut new_v: todo::IDVec = Vec::new();
-    for i in v.iter() {
-        let idx = *i;
-        if !c.exclude.projects.is_empty() && vec_match(&tasks[idx].projects, &c.exclude.projects) {
-            continue;
+fn project_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    list_ok(
+        &task.projects,
+        (&c.include.projects, c.include.require_all_projects),
+        (&c.exclude.projects, c.exclude.require_all_projects),
+    )
+}
+
+// Checks if any tag of a todo matches any filter. A filter is either a tag
+// name pattern or a pair `name:value` where both parts are patterns.
+fn tag_match(task: &todotxt::Task, filter: &[String]) -> bool {
+    for f in filter.iter() {
+        if (f == NONE_TITLE && task.tags.is_empty()) || (f == ANY_TITLE && !task.tags.is_empty()) {
+            return true;
         }
-        if c.include.projects.is_empty() || vec_match(&tasks[idx].projects, &c.include.projects) {
-            new_v.push(idx);
+        let f = f.to_lowercase();
+        let (name, value) = match f.split_once(':') {
+            Some((name, value)) if !value.is_empty() => (name, Some(value)),
+            Some((name, _)) => (name, None),
+            None => (f.as_str(), None),
+        };
+        for (k, v) in task.tags.iter() {
+            if !str_matches(&k.to_lowercase(), name) {
+                continue;
+            }
+            match value {
+                None => return true,
+                Some(val) if str_matches(&v.to_lowercase(), val) => return true,
+                _ => {}
+            }
         }
     }
-    new_v
+    false
 }
 
-fn filter_tag(tasks: &todo::TaskSlice, v: todo::IDVec, c: &Conf) -> todo::IDVec {
-    if c.include.tags.is_empty() && c.exclude.tags.is_empty() {
-        return v;
+fn tags_match(task: &todotxt::Task, filter: &[String], all: bool) -> bool {
+    if all {
+        filter.iter().all(|f| tag_match(task, std::slice::from_ref(f)))
+    } else {
+        tag_match(task, filter)
     }
+}
 
-    let mut new_v: todo::IDVec = Vec::new();
-    for i in v.iter() {
-        let idx = *i;
-        let mut tag_list: Vec<String> = Vec::new();
-        for (k, _v) in tasks[idx].tags.iter() {
-            tag_list.push(k.to_string());
-        }
-        if !c.exclude.tags.is_empty() && vec_match(&tag_list, &c.exclude.tags) {
-            continue;
-        }
-        if c.include.tags.is_empty() || vec_match(&tag_list, &c.include.tags) {
-            new_v.push(idx);
-        }
+fn tag_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    if !c.exclude.tags.is_empty() && tags_match(task, &c.exclude.tags, c.exclude.require_all_tags) {
+        return false;
     }
-    new_v
+    c.include.tags.is_empty() || tags_match(task, &c.include.tags, c.include.require_all_tags)
 }
 
-fn filter_hashtag(tasks: &todo::TaskSlice, v: todo::IDVec, c: &Conf) -> todo::IDVec {
-    if c.include.hashtags.is_empty() && c.exclude.hashtags.is_empty() {
-        return v;
-    }
+fn hashtag_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    list_ok(
+        &task.hashtags,
+        (&c.include.hashtags, c.include.require_all_hashtags),
+        (&c.exclude.hashtags, c.exclude.require_all_hashtags),
+    )
+}
 
-    let mut new_v: todo::IDVec = Vec::new();
-    for i in v.iter() {
-        let idx = *i;
-        let mut hashtag_list: Vec<String> = Vec::new();
-        for k in tasks[idx].hashtags.iter() {
-            hashtag_list.push(k.to_string());
-        }
-        if !c.exclude.hashtags.is_empty() && vec_match(&hashtag_list, &c.exclude.hashtags) {
-            continue;
-        }
-        if c.include.hashtags.is_empty() || vec_match(&hashtag_list, &c.include.hashtags) {
-            new_v.push(idx);
-        }
+// Checks projects, contexts, tags, and hashtags of a todo. By default, a todo
+// must match every non-empty include list. With `match_any` it is enough to
+// match any of them. A todo that matches any exclude list is always skipped.
+fn categories_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    if !c.match_any {
+        return tag_ok(task, c) && hashtag_ok(task, c) && project_ok(task, c) && context_ok(task, c);
+    }
+    let exc = &c.exclude;
+    let excluded = (!exc.projects.is_empty() && list_match(&task.projects, &exc.projects, exc.require_all_projects))
+        || (!exc.contexts.is_empty() && list_match(&task.contexts, &exc.contexts, exc.require_all_contexts))
+        || (!exc.hashtags.is_empty() && list_match(&task.hashtags, &exc.hashtags, exc.require_all_hashtags))
+        || (!exc.tags.is_empty() && tags_match(task, &exc.tags, exc.require_all_tags));
+    if excluded {
+        return false;
+    }
+    let inc = &c.include;
+    if inc.projects.is_empty() && inc.contexts.is_empty() && inc.hashtags.is_empty() && inc.tags.is_empty() {
+        return true;
     }
-    new_v
+    (!inc.projects.is_empty() && list_match(&task.projects, &inc.projects, inc.require_all_projects))
+        || (!inc.contexts.is_empty() && list_match(&task.contexts, &inc.contexts, inc.require_all_contexts))
+        || (!inc.hashtags.is_empty() && list_match(&task.hashtags, &inc.hashtags, inc.require_all_hashtags))
+        || (!inc.tags.is_empty() && tags_match(task, &inc.tags, inc.require_all_tags))
 }
 
-fn filter_priority(tasks: &todo::TaskSlice, v: todo::IDVec, c: &Conf) -> todo::IDVec {
+fn priority_ok(task: &todotxt::Task, c: &Conf) -> bool {
     match &c.pri {
-        None => v,
-        Some(p) => {
-            let mut new_v: todo::IDVec = Vec::new();
-            for i in v.iter() {
-                let idx = *i;
-                match p.span {
-                    ValueSpan::None => {
-                        if tasks[idx].priority == todotxt::NO_PRIORITY {
-                            new_v.push(idx);
-                        }
-                    }
-                    ValueSpan::Equal => {
-                        if p.value == tasks[idx].priority {
-                            new_v.push(idx);
-                        }
-                    }
-                    ValueSpan::Lower => {
-                        if p.value <= tasks[idx].priority {
-                            new_v.push(idx);
-                        }
-                    }
-                    ValueSpan::Higher => {
-                        if p.value >= tasks[idx].priority {
-                            new_v.push(idx);
-                        }
-                    }
-                    ValueSpan::Any => {
-                        if tasks[idx].priority < todotxt::NO_PRIORITY {
-                            new_v.push(idx);
-                        }
-                    }
-                    _ => {}
-                }
-            }
-            new_v
-        }
+        None => true,
+        Some(p) => match p.span {
+            ValueSpan::None => task.priority == todotxt::NO_PRIORITY,
+            ValueSpan::Equal => p.value == task.priority,
+            ValueSpan::Lower => p.value <= task.priority,
+            ValueSpan::Higher => p.value >= task.priority,
+            ValueSpan::Any => task.priority < todotxt::NO_PRIORITY,
+            _ => false,
+        },
     }
 }
 
-fn filter_recurrence(tasks: &todo::TaskSlice, v: todo::IDVec, c: &Conf) -> todo::IDVec {
-    match &c.rec {
-        None => v,
+fn priority_range_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    match &c.pri_range {
+        None => true,
         Some(r) => {
-            let mut new_v: todo::IDVec = Vec::new();
-            for i in v.iter() {
-                let idx = *i;
-                match r.span {
-                    ValueSpan::None => {
-                        if tasks[idx].recurrence.is_none() {
-                            new_v.push(idx);
-                        }
-                    }
-                    ValueSpan::Any => {
-                        if tasks[idx].recurrence.is_some() {
-                            new_v.push(idx);
-                        }
-                    }
-                    _ => {}
-                }
+            if task.priority >= todotxt::NO_PRIORITY {
+                return r.include_none;
             }
-            new_v
+            let (low, high) = if r.low > r.high { (r.high, r.low) } else { (r.low, r.high) };
+            task.priority >= low && task.priority <= high
         }
     }
 }
 
-fn filter_due(tasks: &todo::TaskSlice, v: todo::IDVec, c: &Conf) -> todo::IDVec {
+fn recurrence_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    match &c.rec {
+        None => true,
+        Some(r) => match r.span {
+            ValueSpan::None => task.recurrence.is_none(),
+            ValueSpan::Any => task.recurrence.is_some(),
+            _ => false,
+        },
+    }
+}
+
+fn rec_cadence_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    match &c.rec_cadence {
+        None => true,
+        Some(r) => tsort::recurrence_equal_ignoring_strict(&task.recurrence, &Some(*r)),
+    }
+}
+
+fn due_ok(task: &todotxt::Task, c: &Conf, today: NaiveDate) -> bool {
     match &c.due {
-        None => v,
-        Some(due) => {
-            let mut new_v: todo::IDVec = Vec::new();
-            for i in v.iter() {
-                let idx = *i;
-                if date_in_range(&tasks[idx].due_date, due) {
-                    new_v.push(idx);
-                }
-            }
-            new_v
-        }
+        None => true,
+        Some(due) => date_in_range(&task.due_date, due, today),
     }
 }
 
-fn filter_created(tasks: &todo::TaskSlice, v: todo::IDVec, c: &Conf) -> todo::IDVec {
+fn created_ok(task: &todotxt::Task, c: &Conf, today: NaiveDate) -> bool {
     match &c.created {
-        None => v,
-        Some(created) => {
-            let mut new_v: todo::IDVec = Vec::new();
-            for i in v.iter() {
-                let idx = *i;
-                if date_in_range(&tasks[idx].create_date, created) {
-                    new_v.push(idx);
-                }
-            }
-            new_v
-        }
+        None => true,
+        Some(created) => date_in_range(&task.create_date, created, today),
     }
 }
 
-fn filter_finished(tasks: &todo::TaskSlice, v: todo::IDVec, c: &Conf) -> todo::IDVec {
+fn finished_ok(task: &todotxt::Task, c: &Conf, today: NaiveDate) -> bool {
     match &c.finished {
-        None => v,
-        Some(finished) => {
-            let mut new_v: todo::IDVec = Vec::new();
-            for i in v.iter() {
-                let idx = *i;
-                if date_in_range(&tasks[idx].finish_date, finished) {
-                    new_v.push(idx);
-                }
-            }
-            new_v
-        }
+        None => true,
+        Some(finished) => date_in_range(&task.finish_date, finished, today),
     }
 }
 
-fn filter_threshold(tasks: &todo::TaskSlice, v: todo::IDVec, c: &Conf) -> todo::IDVec {
-    let flt = if let Some(thr) = &c.thr {
-        thr.clone()
-    } else {
-        DateRange { days: ValueRange { low: INCLUDE_NONE, high: 0 }, span: ValueSpan::Range }
-    };
-    let mut new_v: todo::IDVec = Vec::new();
-    for i in v.iter() {
-        let idx = *i;
-        if c.all == TodoStatus::All || date_in_range(&tasks[idx].threshold_date, &flt) {
-            new_v.push(idx);
+fn threshold_ok(task: &todotxt::Task, c: &Conf, today: NaiveDate) -> bool {
+    if c.all == TodoStatus::All {
+        return true;
+    }
+    match &c.thr {
+        Some(thr) => date_in_range(&task.threshold_date, thr, today),
+        None => !task.has_future_threshold(today),
+    }
+}
+
+fn due_equals_threshold_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    match c.due_equals_threshold {
+        None => true,
+        Some(eq) => {
+            let same = task.due_date.is_some() && task.due_date == task.threshold_date;
+            same == eq
         }
     }
-    new_v
 }
 
-fn date_in_range(date: &Option<chrono::NaiveDate>, range: &DateRange) -> bool {
-    let today = chrono::Local::now().date_naive();
+fn scheduled_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    match c.scheduled {
+        None => true,
+        Some(sch) => (task.due_date.is_some() || task.threshold_date.is_some()) == sch,
+    }
+}
+
+fn date_in_range(date: &Option<NaiveDate>, range: &DateRange, today: NaiveDate) -> bool {
     match range.span {
         ValueSpan::None => date.is_none(),
         ValueSpan::Any => date.is_some(),
@@ -545,38 +694,149 @@ fn date_in_range(date: &Option<chrono::NaiveDate>, range: &DateRange) -> bool {
                 range.days.low == INCLUDE_NONE || range.days.high == INCLUDE_NONE
             }
         }
+        ValueSpan::Dates(from, to) => match date {
+            Some(d) => from.map_or(true, |f| *d >= f) && to.map_or(true, |t| *d <= t),
+            None => false,
+        },
         _ => false,
     }
 }
 
-fn filter_timer(tasks: &todo::TaskSlice, v: todo::IDVec, c: &Conf) -> todo::IDVec {
+fn metadata_only_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    match c.metadata_only {
+        None => true,
+        Some(only) => only == (!task.subject.is_empty() && task.word_count() == 0),
+    }
+}
+
+fn timer_ok(task: &todotxt::Task, c: &Conf) -> bool {
     match &c.tmr {
-        None => v,
-        Some(r) => {
-            let mut new_v: todo::IDVec = Vec::new();
-            for i in v.iter() {
-                let idx = *i;
-                match r.span {
-                    ValueSpan::None => {
-                        if !timer::is_timer_on(&tasks[idx]) {
-                            new_v.push(idx);
-                        }
-                    }
-                    ValueSpan::Active => {
-                        if timer::is_timer_on(&tasks[idx]) {
-                            new_v.push(idx);
-                        }
-                    }
-                    _ => {}
-                }
+        None => true,
+        Some(r) => match r.span {
+            ValueSpan::None => !timer::is_timer_on(task),
+            ValueSpan::Active => timer::is_timer_on(task),
+            ValueSpan::Higher => timer::spent_time(task).num_seconds() >= r.value as i64,
+            ValueSpan::Lower => timer::spent_time(task).num_seconds() <= r.value as i64,
+            ValueSpan::Range => {
+                let spent = timer::spent_time(task).num_seconds();
+                r.range.contains(spent)
             }
-            new_v
+            _ => false,
+        },
+    }
+}
+
+fn timer_running_ok(task: &todotxt::Task, c: &Conf, now: DateTime<Utc>) -> bool {
+    match c.tmr_running_hours {
+        None => true,
+        Some(hours) => {
+            timer::running_time_at(task, now).is_some_and(|d| d >= chrono::Duration::hours(i64::from(hours)))
         }
     }
 }
 
+fn tag_count_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    match &c.tag_count {
+        None => true,
+        Some(range) => range.contains(task.tags.len() as i64),
+    }
+}
+
+fn due_after_creation_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    match c.due_within_days_of_creation {
+        None => true,
+        Some(days) => match (task.create_date, task.due_date) {
+            (Some(created), Some(due)) => (due - created).num_days() <= i64::from(days),
+            _ => false,
+        },
+    }
+}
+
+fn modified_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    match c.modified_since {
+        None => true,
+        Some(since) => task.modified_date().is_some_and(|d| d >= since),
+    }
+}
+
+fn list_count_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    c.min_projects.map_or(true, |n| task.project_count() >= n)
+        && c.min_contexts.map_or(true, |n| task.context_count() >= n)
+}
+
+fn pairs_ok(task: &todotxt::Task, c: &Conf) -> bool {
+    if c.pairs.is_empty() {
+        return true;
+    }
+    let has = |list: &[String], name: &str| list.iter().any(|v| v.to_lowercase() == name.to_lowercase());
+    c.pairs.iter().any(|(proj, ctx)| {
+        has(&task.projects, proj.trim_start_matches('+')) && has(&task.contexts, ctx.trim_start_matches('@'))
+    })
+}
+
 fn is_status_ok(task: &todotxt::Task, status: &TodoStatus) -> bool {
-    !((*status == TodoStatus::Active && task.finished) || (*status == TodoStatus::Done && !task.finished))
+    match status {
+        TodoStatus::Active => !task.finished,
+        TodoStatus::Done => task.finished,
+        TodoStatus::Recurring => !task.finished && task.recurrence.is_some(),
+        TodoStatus::All | TodoStatus::Empty => true,
+    }
+}
+
+fn task_matches(task: &todotxt::Task, c: &Conf, today: NaiveDate, now: DateTime<Utc>, m: &TextMatchers) -> bool {
+    is_status_ok(task, &c.all)
+        && empty_ok(task, c)
+        && regex_ok(task, c, m)
+        && categories_ok(task, c)
+        && priority_ok(task, c)
+        && priority_range_ok(task, c)
+        && recurrence_ok(task, c)
+        && rec_cadence_ok(task, c)
+        && due_ok(task, c, today)
+        && created_ok(task, c, today)
+        && finished_ok(task, c, today)
+        && threshold_ok(task, c, today)
+        && due_equals_threshold_ok(task, c)
+        && scheduled_ok(task, c)
+        && metadata_only_ok(task, c)
+        && timer_ok(task, c)
+        && timer_running_ok(task, c, now)
+        && tag_count_ok(task, c)
+        && due_after_creation_ok(task, c)
+        && pairs_ok(task, c)
+        && list_count_ok(task, c)
+        && modified_ok(task, c)
+}
+
+/// Checks whether a single todo meets all the filtering criteria.
+///
+/// The function evaluates the same rules as `filter` does, except `range`
+/// that makes sense only for a list of todos.
+///
+/// * `task` - the todo to check
+/// * `c` - filtering rules
+/// * `today` - the date relative to which date ranges are calculated. The
+///   running time of timers (see `Conf.tmr_running_hours`) is calculated
+///   until the start of this day in local time. Use `matches_at` to set the
+///   exact moment
+pub fn matches(task: &todotxt::Task, c: &Conf, today: NaiveDate) -> bool {
+    matches_at(task, c, today, start_of_day(today))
+}
+
+/// Checks whether a single todo meets all the filtering criteria like
+/// `matches` does, but the running time of timers is calculated until `now`.
+pub fn matches_at(task: &todotxt::Task, c: &Conf, today: NaiveDate, now: DateTime<Utc>) -> bool {
+    let m = text_matchers(c);
+    task_matches(task, c, today, now, &m)
+}
+
+// Returns the moment when the day starts in local time.
+fn start_of_day(day: NaiveDate) -> DateTime<Utc> {
+    let midnight = day.and_hms_opt(0, 0, 0).unwrap_or_default();
+    match chrono::Local.from_local_datetime(&midnight).earliest() {
+        Some(dt) => dt.with_timezone(&Utc),
+        None => Utc.from_utc_datetime(&midnight),
+    }
 }
 
 /// Entry function to filter the list of todo records
@@ -590,11 +850,23 @@ fn is_status_ok(task: &todotxt::Task, status: &TodoStatus) -> bool {
 /// Returns:
 /// the list of todo IDs which meet filtering criteria
 pub fn filter(tasks: &todo::TaskSlice, c: &Conf) -> todo::IDVec {
+    let mut v = range_ids(tasks, &c.range);
+    let now = chrono::Local::now();
+    let today = now.date_naive();
+    let now = now.with_timezone(&Utc);
+    let m = text_matchers(c);
+    v.retain(|idx| task_matches(&tasks[*idx], c, today, now, &m));
+    v
+}
+
+/// Returns IDs of todos selected by the range. IDs outside of the todo list
+/// are skipped. `ItemRange::None` selects all todos.
+pub fn range_ids(tasks: &todo::TaskSlice, range: &ItemRange) -> todo::IDVec {
     let mut v: todo::IDVec = Vec::new();
 
-    match c.range {
+    match *range {
         ItemRange::One(i) => {
-            if i < tasks.len() && is_status_ok(&tasks[i], &c.all) {
+            if i < tasks.len() {
                 v.push(i);
             }
         }
@@ -605,47 +877,48 @@ pub fn filter(tasks: &todo::TaskSlice, c: &Conf) -> todo::IDVec {
                 if start >= tasks.len() {
                     break;
                 }
-                if is_status_ok(&tasks[start], &c.all) {
-                    v.push(start);
-                }
+                v.push(start);
                 start += 1;
             }
         }
+        ItemRange::RangeExclusive(min, max) => {
+            v.extend(min..max.min(tasks.len()));
+        }
         ItemRange::List(ref lst) => {
             for idx in lst.iter() {
-                if *idx >= tasks.len() {
-                    continue;
-                }
-                if is_status_ok(&tasks[*idx], &c.all) {
+                if *idx < tasks.len() {
                     v.push(*idx);
                 }
             }
         }
         _ => {
-            for (i, item) in tasks.iter().enumerate() {
-                if is_status_ok(item, &c.all) {
-                    v.push(i);
-                }
-            }
+            v = todo::make_id_vec(tasks.len());
         }
     }
-    v = filter_empty(tasks, v, c);
-    v = filter_regex(tasks, v, c);
-    v = filter_tag(tasks, v, c);
-    v = filter_hashtag(tasks, v, c);
-    v = filter_project(tasks, v, c);
-    v = filter_context(tasks, v, c);
-    v = filter_priority(tasks, v, c);
-    v = filter_recurrence(tasks, v, c);
-    v = filter_due(tasks, v, c);
-    v = filter_created(tasks, v, c);
-    v = filter_finished(tasks, v, c);
-    v = filter_threshold(tasks, v, c);
-    v = filter_timer(tasks, v, c);
 
     v
 }
 
+/// Filters the list of todo records and returns one page of the result.
+///
+/// * `tasks` - list of todos to filter
+/// * `c` - filtering rules
+/// * `offset` - the number of matching todos to skip
+/// * `limit` - the maximum number of todo IDs to return
+///
+/// Returns:
+/// the list of at most `limit` todo IDs that meet filtering criteria
+/// starting from `offset`, and the total number of matching todos
+pub fn filter_page(tasks: &todo::TaskSlice, c: &Conf, offset: usize, limit: usize) -> (todo::IDVec, usize) {
+    let mut ids = filter(tasks, c);
+    let total = ids.len();
+    let start = offset.min(total);
+    let end = start.saturating_add(limit).min(total);
+    ids.truncate(end);
+    ids.drain(..start);
+    (ids, total)
+}
+
 fn str_matches(orig: &str, patt: &str) -> bool {
     if patt.starts_with('*') && patt.ends_with('*') {
         let p = patt.trim_matches('*');
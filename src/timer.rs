@@ -1,3 +1,5 @@
+use chrono::{DateTime, Utc};
+
 use crate::todo;
 use crate::todotxt;
 
@@ -11,8 +13,14 @@ pub fn is_timer_on(task: &todotxt::Task) -> bool {
 
 /// Returns the time spent on a given task
 pub fn spent_time(task: &todotxt::Task) -> chrono::Duration {
+    spent_time_at(task, Utc::now())
+}
+
+/// Returns the time spent on a given task. If the timer is running, the time
+/// elapsed since the timer start until `now` is included
+pub fn spent_time_at(task: &todotxt::Task, now: DateTime<Utc>) -> chrono::Duration {
     if is_timer_on(task) {
-        return match calc_time_spent(task) {
+        return match calc_time_spent(task, now) {
             Some(n) => chrono::Duration::seconds(n),
             None => chrono::Duration::seconds(0),
         };
@@ -32,45 +40,66 @@ pub fn spent_time(task: &todotxt::Task) -> chrono::Duration {
 /// Make the todo active - start its timer. Attribute `tmr` is set to the
 /// current time in seconds
 pub fn start_timer(task: &mut todotxt::Task) -> bool {
+    start_timer_at(task, Utc::now())
+}
+
+/// Make the todo active - start its timer. Attribute `tmr` is set to `now`
+/// in seconds
+pub fn start_timer_at(task: &mut todotxt::Task, now: DateTime<Utc>) -> bool {
     if task.finished || is_timer_on(task) {
         return false;
     }
 
-    let utc = chrono::Utc::now();
-    let seconds = format!("{}", utc.timestamp());
+    let seconds = format!("{}", now.timestamp());
     task.update_tag_with_value(todo::TIMER_TAG, &seconds);
 
     true
 }
 
-fn calc_time_spent(task: &todotxt::Task) -> Option<i64> {
-    if let Some(started) = task.tags.get(todo::TIMER_TAG) {
-        if let Ok(n) = started.parse::<i64>() {
-            let dt_start = chrono::DateTime::from_timestamp(n, 0)?;
-            let diff = chrono::Utc::now() - dt_start;
+fn timer_started(task: &todotxt::Task) -> Option<DateTime<Utc>> {
+    let started = task.tags.get(todo::TIMER_TAG)?;
+    let n = started.parse::<i64>().ok()?;
+    DateTime::from_timestamp(n, 0)
+}
 
-            let mut spent: i64 =
-                if let Some(sp) = task.tags.get(todo::SPENT_TAG) { sp.parse::<i64>().unwrap_or(0) } else { 0 };
+fn calc_time_spent(task: &todotxt::Task, now: DateTime<Utc>) -> Option<i64> {
+    let dt_start = timer_started(task)?;
+    let diff = now - dt_start;
 
-            if diff.num_seconds() > 0 {
-                spent += diff.num_seconds();
-            }
+    let mut spent: i64 = if let Some(sp) = task.tags.get(todo::SPENT_TAG) { sp.parse::<i64>().unwrap_or(0) } else { 0 };
 
-            return Some(spent);
-        }
+    if diff.num_seconds() > 0 {
+        spent += diff.num_seconds();
     }
 
-    None
+    Some(spent)
+}
+
+/// Returns the time elapsed since the running timer of the todo was started
+/// until `now`. The time spent before the timer was started is not included.
+/// Returns `None` if the timer is not running.
+pub fn running_time_at(task: &todotxt::Task, now: DateTime<Utc>) -> Option<chrono::Duration> {
+    if !is_timer_on(task) {
+        return None;
+    }
+    let diff = now - timer_started(task)?;
+    Some(diff.max(chrono::Duration::zero()))
 }
 
 /// Stops the todo's timer and updates the spent time. Attribute `tmr` gets
 /// value 'off'
 pub fn stop_timer(task: &mut todotxt::Task) -> bool {
+    stop_timer_at(task, Utc::now())
+}
+
+/// Stops the todo's timer at `now` and updates the spent time. Attribute
+/// `tmr` gets value 'off'
+pub fn stop_timer_at(task: &mut todotxt::Task, now: DateTime<Utc>) -> bool {
     if !is_timer_on(task) {
         return false;
     }
 
-    if let Some(spent) = calc_time_spent(task) {
+    if let Some(spent) = calc_time_spent(task, now) {
         let new_spent = format!("{spent}");
         task.update_tag_with_value(todo::SPENT_TAG, &new_spent);
         task.update_tag_with_value(todo::TIMER_TAG, todo::TIMER_OFF);
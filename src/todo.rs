@@ -1,16 +1,19 @@
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::fs;
 use std::fs::File;
 use std::fs::OpenOptions;
 use std::io::BufRead;
 use std::io::BufReader;
+use std::io::Lines;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::date_expr;
+use crate::human_date;
 use crate::terr;
+use crate::tfilter;
 use crate::timer;
 use crate::todotxt;
 use crate::todotxt::CompletionConfig;
@@ -21,6 +24,9 @@ pub const INVALID_ID: usize = 1_999_999_999;
 pub const TIMER_TAG: &str = "tmr";
 pub const SPENT_TAG: &str = "spent";
 pub const TIMER_OFF: &str = "off";
+/// How far, in days after a task's due date, `due_on` looks for the task's
+/// recurrent occurrences
+pub const RECURRENCE_HORIZON_DAYS: i64 = 366;
 
 pub type TaskVec = Vec<todotxt::Task>;
 pub type TaskSlice = [todotxt::Task];
@@ -31,7 +37,9 @@ pub type ChangedSlice = [bool];
 
 /// The new value for a date-like tag.
 /// Date - a fixed date, one for all tasks
-/// Expr - an expression that is calculated for each of selected tasks. E.g, `due+1w`
+/// Expr - an expression that is calculated for each of selected tasks. E.g, `due+1w`.
+///     If the expression is invalid, it is parsed as a phrase (see `human_date::parse`),
+///     e.g. `next friday` or `in 2 weeks`
 #[derive(Debug, Clone)]
 pub enum NewDateValue {
     Date(chrono::NaiveDate),
@@ -39,9 +47,27 @@ pub enum NewDateValue {
     None,
 }
 
+/// Line ending for saved todo files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
 /// Type of operation applied to todo properties. Every field supports
 /// its own set of operations (except `None` that can be used for all of them):
-/// * priority: `Set`, `Delete`, `Increase`, `Decrease`;
+/// * priority: `Set`, `Delete`, `Increase`, `Decrease`, `IncreaseBy`, `DecreaseBy`;
 /// * due date: `Set`, `Delete`;
 /// * recurrence: `Set`, `Delete`;
 /// * projects: `Set`, `Delete`, `Replace`;
@@ -59,6 +85,9 @@ pub enum Action {
     /// Replace old value with a new one. The format for the new value:
     /// projects: `old_value+new_value`
     /// contexts: `old_value@new_value`
+    /// The old value is matched case-insensitively, and the new value is
+    /// written as is, so `Car+car` renames `+Car` to `+car`. If a todo already
+    /// has the new value, the old one is just removed
     Replace,
     /// Only for priority: increases the priority by one level. If a todo
     /// has A priority the todo is not changed. If a todo does not have a
@@ -68,6 +97,18 @@ pub enum Action {
     /// has no priority the todo is not changed. If a todo has the lowest
     /// priority `Z` the priority is removed
     Decrease,
+    /// Only for priority: increases the priority by the given number of
+    /// levels, e.g. `D` increased by 3 becomes `A`. The priority never goes
+    /// higher than `A`. A todo without priority is treated as if its priority
+    /// were one level below `Z`: increasing it by 2 levels gives `Y`.
+    /// `IncreaseBy(0)` works the same way as `Increase`
+    IncreaseBy(u8),
+    /// Only for priority: decreases the priority by the given number of
+    /// levels. If a todo has no priority the todo is not changed. If the new
+    /// priority would be lower than `Z` the priority is removed, e.g. `Y`
+    /// decreased by 2 becomes no priority. `DecreaseBy(0)` works the same
+    /// way as `Decrease`
+    DecreaseBy(u8),
 }
 
 /// Describes how the date-like tag should be changed.
@@ -185,6 +226,48 @@ pub struct Conf {
     pub completion_date_mode: todotxt::CompletionDateMode,
     /// The value of `soon` for calculating expression like `soon`.
     pub soon_days: u8,
+    /// Order of tags at the end of a todo subject when saving todos with
+    /// `save_with_conf`. `TagOrder::Original`(default) keeps unchanged lines
+    /// byte-for-byte, `TagOrder::Alphabetical` sorts tags by their names.
+    pub tag_order: todotxt::TagOrder,
+    /// Write the completion date of a completed todo right after `x` when
+    /// saving todos with `save_with_conf`, even if the todo has a priority:
+    /// `x 2023-08-20 (B) Task` (see `todotxt::Task::to_string_with_options`)
+    pub completion_date_first: bool,
+    /// Keep the modification time of the original file when saving todos
+    /// with `save_with_conf`.
+    pub preserve_mtime: bool,
+    /// Line ending used when saving todos with `save_with_conf`. Default is `LineEnding::Lf`
+    pub line_ending: LineEnding,
+    /// Projects added by `add` to a new todo that does not have any project
+    pub default_projects: Vec<String>,
+    /// Contexts added by `add` to a new todo that does not have any context
+    pub default_contexts: Vec<String>,
+    /// Priority set by `add` to a new todo that does not have a priority.
+    /// `todotxt::NO_PRIORITY` means no default priority
+    pub default_priority: u8,
+    /// Compare projects and contexts case-insensitively when `edit` adds or
+    /// replaces them. E.g., adding `car` to a todo with `+Car` does nothing, and
+    /// the todo keeps its first-seen `+Car`. By default, `+Car` and `+car` are
+    /// different projects
+    pub ignore_case_dedupe: bool,
+    /// Put projects and contexts added by `edit` at the beginning of the
+    /// subject instead of appending them to its end
+    pub prepend_lists: bool,
+    /// Make `done_with_conf` return IDs of completed recurrent todos that
+    /// spawned new todos, so the caller can archive them right away
+    pub collect_recurrent_parents: bool,
+    /// The first day of a week for week-based dates: date phrases like
+    /// `end of week` in new due and threshold dates (see
+    /// `human_date::parse_with_week_start`) and week ranges in `calendar_range`
+    pub week_start: human_date::WeekStart,
+    /// Make `edit` skip new due and threshold dates if the todo would end up
+    /// with a due date earlier than its threshold date. Both dates are checked
+    /// after applying both changes, and either both are applied or none
+    pub validate_date_order: bool,
+    /// Make `edit` set the tag `modified:` of every changed todo to the
+    /// current date (see `todotxt::Task::touch`)
+    pub touch_modified: bool,
 }
 
 impl Default for Conf {
@@ -204,6 +287,19 @@ impl Default for Conf {
             completion_mode: todotxt::CompletionMode::JustMark,
             completion_date_mode: todotxt::CompletionDateMode::WhenCreationDateIsPresent,
             soon_days: 0,
+            tag_order: todotxt::TagOrder::Original,
+            completion_date_first: false,
+            preserve_mtime: false,
+            line_ending: LineEnding::Lf,
+            default_projects: Vec::new(),
+            default_contexts: Vec::new(),
+            default_priority: todotxt::NO_PRIORITY,
+            ignore_case_dedupe: false,
+            prepend_lists: false,
+            collect_recurrent_parents: false,
+            week_start: human_date::WeekStart::Monday,
+            validate_date_order: false,
+            touch_modified: false,
         }
     }
 }
@@ -232,38 +328,212 @@ pub fn is_tag_special(tag: &str) -> bool {
 /// Load a list of todo from a file in todo.txt format. If the file does not
 /// exist or cannot be opened the function returns empty list
 pub fn load(filename: &Path) -> Result<TaskVec, terr::TodoError> {
-    let mut tasks = Vec::new();
+    load_with_base(filename, chrono::Local::now().date_naive())
+}
+
+/// Load a list of todo from a file like `load` does, but relative dates
+/// (e.g., `due:2d`) are calculated from `base` instead of the current date.
+pub fn load_with_base(filename: &Path, base: chrono::NaiveDate) -> Result<TaskVec, terr::TodoError> {
+    Ok(read_lines(filename)?.map(|l| todotxt::Task::parse(&l, base)).collect())
+}
+
+// Opens a todo file for reading line by line. Returns `None` if the file does not exist.
+fn open_lines(filename: &Path) -> Result<Option<Lines<BufReader<File>>>, terr::TodoError> {
     if !filename.exists() {
-        return Ok(tasks);
+        return Ok(None);
     }
-
     let file = File::open(filename).map_err(|_| terr::TodoError::LoadFailed)?;
-    let now = chrono::Local::now().date_naive();
+    Ok(Some(BufReader::new(file).lines()))
+}
 
-    let br = BufReader::new(&file);
-    for l in br.lines().map_while(Result::ok) {
-        let t = todotxt::Task::parse(&l, now);
-        tasks.push(t);
+// Returns all lines of a todo file. Reading stops at the first line that
+// cannot be read. A file that does not exist has no lines.
+fn read_lines(filename: &Path) -> Result<impl Iterator<Item = String>, terr::TodoError> {
+    Ok(open_lines(filename)?.into_iter().flatten().map_while(Result::ok))
+}
+
+/// Options for `load_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    /// Lines that start with this prefix (after leading whitespace) are comments:
+    /// they are not parsed as todos. Default is `None` - every line is a todo.
+    /// An empty prefix is the same as `None`.
+    pub comment_prefix: Option<String>,
+    /// Extra rules applied to every parsed todo. See `todotxt::ParseOptions`
+    pub parse: todotxt::ParseOptions,
+}
+
+/// A comment line skipped by `load_with_options`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommentLine {
+    /// ID of the todo that follows the comment in the file. If the comment is
+    /// at the end of the file, it equals the number of loaded todos
+    pub before: usize,
+    /// The original text of the line
+    pub text: String,
+}
+
+/// Load a list of todo from a file like `load` does, but lines starting with
+/// `opts.comment_prefix` are not parsed as todos. The skipped lines are
+/// returned separately, so they can be written back with `save_with_comments`.
+/// Todos are parsed with `opts.parse` rules.
+pub fn load_with_options(filename: &Path, opts: &LoadOptions) -> Result<(TaskVec, Vec<CommentLine>), terr::TodoError> {
+    load_with_options_and_base(filename, opts, chrono::Local::now().date_naive())
+}
+
+/// Load a list of todo from a file like `load_with_options` does, but relative
+/// dates are calculated from `base` instead of the current date.
+pub fn load_with_options_and_base(
+    filename: &Path,
+    opts: &LoadOptions,
+    base: chrono::NaiveDate,
+) -> Result<(TaskVec, Vec<CommentLine>), terr::TodoError> {
+    let mut tasks = Vec::new();
+    let mut comments = Vec::new();
+    let prefix = opts.comment_prefix.as_deref().filter(|p| !p.is_empty());
+
+    for l in read_lines(filename)? {
+        if prefix.is_some_and(|p| l.trim_start().starts_with(p)) {
+            comments.push(CommentLine { before: tasks.len(), text: l });
+            continue;
+        }
+        tasks.push(todotxt::Task::parse_with_options(&l, base, &opts.parse));
     }
 
-    Ok(tasks)
+    Ok((tasks, comments))
+}
+
+/// Returns an iterator that reads and parses todos from a file line by line,
+/// so the whole file is never kept in memory. Relative dates are calculated
+/// from the date when the iterator was created. If the file does not exist
+/// the iterator is empty. If the file cannot be opened, the iterator yields
+/// a single error.
+pub fn load_iter(filename: &Path) -> impl Iterator<Item = Result<todotxt::Task, terr::TodoError>> {
+    load_iter_with_base(filename, chrono::Local::now().date_naive())
+}
+
+/// Returns an iterator like `load_iter` does, but relative dates are
+/// calculated from `base` instead of the current date.
+pub fn load_iter_with_base(
+    filename: &Path,
+    base: chrono::NaiveDate,
+) -> impl Iterator<Item = Result<todotxt::Task, terr::TodoError>> {
+    let (lines, err) = match open_lines(filename) {
+        Ok(lines) => (lines, None),
+        Err(e) => (None, Some(e)),
+    };
+    err.map(Err).into_iter().chain(lines.into_iter().flatten().map(move |l| match l {
+        Ok(l) => Ok(todotxt::Task::parse(&l, base)),
+        Err(e) => Err(terr::TodoError::IOError(e.to_string())),
+    }))
+}
+
+/// A problem found in a line of a todo file while loading it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineDiagnostic {
+    /// 0-based line number
+    pub line: usize,
+    /// Description of the problem
+    pub message: String,
+}
+
+/// Loads a list of todos from a file like `load` does, and reports lines that
+/// contain suspicious content: invalid priority, creation or completion date,
+/// or values of `due:`, `t:`, and `rec:` tags that cannot be parsed. Such
+/// lines are still loaded the same way `load` does it.
+pub fn load_with_report(filename: &Path) -> Result<(TaskVec, Vec<LineDiagnostic>), terr::TodoError> {
+    load_with_report_and_base(filename, chrono::Local::now().date_naive())
+}
+
+/// Loads a list of todos and reports suspicious lines like `load_with_report`
+/// does, but relative dates are calculated from `base` instead of the current
+/// date.
+pub fn load_with_report_and_base(
+    filename: &Path,
+    base: chrono::NaiveDate,
+) -> Result<(TaskVec, Vec<LineDiagnostic>), terr::TodoError> {
+    let mut tasks = Vec::new();
+    let mut diags = Vec::new();
+
+    for (idx, l) in read_lines(filename)?.enumerate() {
+        let (task, res) = todotxt::Task::parse_checked(&l, base);
+        if let Err(e) = res {
+            diags.push(LineDiagnostic { line: idx, message: e.to_string() });
+        }
+        tasks.push(task);
+    }
+
+    Ok((tasks, diags))
 }
 
 /// Saves the list of todos into a local file. Returns an error if saving
-/// fails.
+/// fails. If the file exists, its permissions are kept. On Unix, its owner and
+/// group are kept as well if the current user is allowed to set them;
+/// otherwise, the file gets the owner of the current process.
 pub fn save(tasks: &TaskSlice, filename: &Path) -> Result<(), terr::TodoError> {
+    save_with_conf(tasks, filename, &Conf::default())
+}
+
+/// Saves the list of todos to a file like `save` does, but uses the
+/// output options from `c` (e.g., `tag_order`).
+pub fn save_with_conf(tasks: &TaskSlice, filename: &Path, c: &Conf) -> Result<(), terr::TodoError> {
+    save_with_comments(tasks, &[], filename, c)
+}
+
+/// Saves the list of todos to a file like `save_with_conf` does, and writes
+/// every comment line before the todo with ID `before`. Comments with `before`
+/// greater than or equal to the number of todos go to the end of the file.
+pub fn save_with_comments(
+    tasks: &TaskSlice,
+    comments: &[CommentLine],
+    filename: &Path,
+    c: &Conf,
+) -> Result<(), terr::TodoError> {
     let tmpname = filename.with_extension(OsStr::new("todo.tmp"));
 
     let mut output = File::create(&tmpname).map_err(|_| terr::TodoError::SaveFailed)?;
-    for t in tasks {
-        let line = format!("{t}\n");
-        write!(output, "{line}").map_err(|_| terr::TodoError::FileWriteFailed)?;
+    let eol = c.line_ending.as_str();
+    let mut cmnts = comments.iter().peekable();
+    for (idx, t) in tasks.iter().enumerate() {
+        while let Some(cmnt) = cmnts.next_if(|cm| cm.before <= idx) {
+            write!(output, "{}{eol}", cmnt.text).map_err(|_| terr::TodoError::FileWriteFailed)?;
+        }
+        write!(output, "{}{eol}", t.to_string_with_options(c.tag_order, c.completion_date_first))
+            .map_err(|_| terr::TodoError::FileWriteFailed)?;
+    }
+    for cmnt in cmnts {
+        write!(output, "{}{eol}", cmnt.text).map_err(|_| terr::TodoError::FileWriteFailed)?;
     }
+    // Keep permissions of the original file: the temporary file replaces it
+    if let Ok(meta) = fs::metadata(filename) {
+        output.set_permissions(meta.permissions()).map_err(|e| terr::TodoError::IOError(e.to_string()))?;
+        keep_owner(&output, &meta);
+        if c.preserve_mtime {
+            if let Ok(mtime) = meta.modified() {
+                output.set_modified(mtime).map_err(|e| terr::TodoError::IOError(e.to_string()))?;
+            }
+        }
+    }
+    drop(output);
 
     fs::rename(tmpname, filename).map_err(|e| terr::TodoError::IOError(e.to_string()))?;
     Ok(())
 }
 
+// Copies the owner and group of the original file to the new one. Only a
+// privileged user can change the owner, so the errors are ignored, and then
+// the group alone is tried: a user may set any group they belong to.
+#[cfg(unix)]
+fn keep_owner(output: &File, meta: &fs::Metadata) {
+    use std::os::unix::fs::{fchown, MetadataExt};
+    if fchown(output, Some(meta.uid()), Some(meta.gid())).is_err() {
+        let _ = fchown(output, None, Some(meta.gid()));
+    }
+}
+
+#[cfg(not(unix))]
+fn keep_owner(_output: &File, _meta: &fs::Metadata) {}
+
 /// Appends todos to a file. If file does not exist it is created.
 ///
 /// * `tasks` - todo list to append to the file
@@ -282,11 +552,90 @@ pub fn archive(tasks: &TaskSlice, filename: &Path) -> Result<(), terr::TodoError
     Ok(())
 }
 
+/// Appends todos to a file like `archive` does, but skips todos which text
+/// (`Display` output) already exists in the file as a line, or appears earlier
+/// in `tasks`. The comparison is exact and case-sensitive; only trailing
+/// whitespace of file lines is ignored. If the file does not exist, it is created.
+///
+/// * `tasks` - todo list to append to the file
+/// * `filename` - the name of the file to save the data (usually it is `done.txt`)
+///
+/// Returns the list of IDs of todos that were appended to the file.
+pub fn archive_dedup(tasks: &TaskSlice, filename: &Path) -> Result<IDVec, terr::TodoError> {
+    let mut seen: HashSet<String> = HashSet::new();
+    for l in open_lines(filename)?.into_iter().flatten() {
+        let l = l.map_err(|e| terr::TodoError::IOError(e.to_string()))?;
+        seen.insert(l.trim_end().to_string());
+    }
+
+    let mut ids = Vec::new();
+    let mut lines = Vec::new();
+    for (idx, t) in tasks.iter().enumerate() {
+        let line = t.to_string();
+        if seen.insert(line.clone()) {
+            ids.push(idx);
+            lines.push(line);
+        }
+    }
+    if lines.is_empty() {
+        return Ok(ids);
+    }
+
+    let mut output =
+        OpenOptions::new().append(true).create(true).open(filename).map_err(|_| terr::TodoError::AppendFailed)?;
+    for line in lines {
+        writeln!(output, "{line}").map_err(|_| terr::TodoError::FileWriteFailed)?;
+    }
+
+    Ok(ids)
+}
+
+/// Returns the path to the archive file that is kept next to the todo file.
+/// The stem of the todo file name is replaced with `done`, the extension is
+/// kept: `todo.txt` becomes `done.txt`, `work.md` becomes `done.md`.
+///
+/// * `todo_path` - the path to the todo file
+pub fn default_archive_path(todo_path: &Path) -> PathBuf {
+    let name = match todo_path.extension() {
+        None => "done".to_string(),
+        Some(ext) => format!("done.{}", ext.to_string_lossy()),
+    };
+    todo_path.with_file_name(name)
+}
+
+/// Appends todos to the default archive file of the todo file (see
+/// `default_archive_path`). If the archive does not exist it is created.
+///
+/// * `tasks` - todo list to append to the archive
+/// * `todo_path` - the path to the todo file
+pub fn archive_to_default(tasks: &TaskSlice, todo_path: &Path) -> Result<(), terr::TodoError> {
+    archive(tasks, &default_archive_path(todo_path))
+}
+
+/// Moves all completed todos to an archive file. Completed todos are appended
+/// to the file and then removed from the list. Incomplete todos, including
+/// new todos spawned by completing recurrent ones, are kept in the list.
+///
+/// * `tasks` - the full list of todos
+/// * `filename` - the name of the archive file (usually it is `done.txt`)
+///
+/// Returns the list of IDs of archived todos. The IDs are valid for the list
+/// before archiving.
+pub fn archive_completed(tasks: &mut TaskVec, filename: &Path) -> Result<IDVec, terr::TodoError> {
+    let ids: IDVec = tasks.iter().enumerate().filter(|(_, t)| t.finished).map(|(i, _)| i).collect();
+    if ids.is_empty() {
+        return Ok(ids);
+    }
+    archive(&clone_tasks(tasks, &ids), filename)?;
+    remove(tasks, Some(&ids));
+    Ok(ids)
+}
+
 /// Makes a clones of selected todos
 ///
 /// * `tasks` - the full list of todos
 /// * `ids` - the list of todo IDs that must be clones. Invalid IDs (e.g, ID
-///     greater than the number of items in `tasks`) are skipped
+///   greater than the number of items in `tasks`) are skipped
 ///
 /// Returns the list of cloned todos. Size of the list is equal to or less than
 /// size of `ids` vector
@@ -305,31 +654,162 @@ pub fn clone_tasks(tasks: &TaskSlice, ids: &IDSlice) -> TaskVec {
 /// Appends a new todo to todo list
 ///
 /// * `tasks` - a list of todos for adding a new item
-/// * `c` - information about new todo. At this moment only `subject` field
-///     is used - it should contains all info including due date, priority etc.
-///     The `subject` field should be in todo.txt format
+/// * `c` - information about new todo. The `subject` field should contain
+///   all info including due date, priority etc in todo.txt format. If the
+///   subject has no projects, no contexts, or no priority, the values of
+///   `default_projects`, `default_contexts`, and `default_priority` are used.
+///   Values of `due:` and `t:` tags that are neither dates nor relative
+///   dates are parsed as phrases (see `human_date::parse_with_week_start`
+///   and `week_start`). A phrase may contain spaces, e.g. `due:next friday`
+///   or `t:in 2 weeks`, and it is replaced with an absolute date
 ///
 /// Returns:
 /// * INVALID_ID if the subject is empty or cannot be parsed as todo.txt entry
 /// * id of the new todo
 pub fn add(tasks: &mut TaskVec, c: &Conf) -> usize {
-    let s = match &c.subject {
-        None => return INVALID_ID,
-        Some(subj) => subj,
-    };
+    add_with_base(tasks, c, chrono::Local::now().date_naive())
+}
+
+/// Appends a new todo to todo list like `add` does, but uses `base` instead
+/// of the current date: relative dates in the subject (e.g., `due:2d`) are
+/// calculated from `base`, and `auto_create_date` sets creation date to `base`.
+/// Useful to import old todos.
+pub fn add_with_base(tasks: &mut TaskVec, c: &Conf, base: chrono::NaiveDate) -> usize {
+    match &c.subject {
+        None => INVALID_ID,
+        Some(subj) => add_subject(tasks, subj, c, base),
+    }
+}
 
+/// Appends a few new todos to todo list at once. Every subject is processed
+/// the same way `add` processes `subject` field of `c`, the `subject` field
+/// of `c` is ignored.
+///
+/// Returns the list of IDs of new todos in the same order as `subjects`.
+/// An empty subject is skipped and its ID in the list is `INVALID_ID`.
+pub fn add_many(tasks: &mut TaskVec, subjects: &[String], c: &Conf) -> IDVec {
     let now = chrono::Local::now().date_naive();
-    let mut t = todotxt::Task::parse(s, now);
+    subjects
+        .iter()
+        .map(|subj| if subj.trim().is_empty() { INVALID_ID } else { add_subject(tasks, subj, c, now) })
+        .collect()
+}
+
+// Replaces phrases in values of `due:` and `t:` tags with absolute dates.
+// A phrase can continue after the tag value (e.g, `due:in 2 weeks`), so up to
+// two following words are tried, the longest phrase wins. Only the tag and
+// the words of the phrase are replaced, the rest of the text is kept as is.
+// Values that are dates or relative dates are left as is.
+fn resolve_date_phrases(s: &str, base: chrono::NaiveDate, c: &Conf) -> String {
+    const MAX_EXTRA_WORDS: usize = 2;
+    // byte ranges of words in `s`
+    let mut words: Vec<(usize, usize)> = Vec::new();
+    let mut start = None;
+    for (idx, ch) in s.char_indices().chain(std::iter::once((s.len(), ' '))) {
+        match (start, ch.is_whitespace()) {
+            (None, false) => start = Some(idx),
+            (Some(st), true) => {
+                words.push((st, idx));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    let mut res = String::new();
+    let mut copied = 0;
+    let mut idx = 0;
+    while idx < words.len() {
+        let (st, end) = words[idx];
+        idx += 1;
+        let (tag, value) = match s[st..end].split_once(':') {
+            Some((tag, value)) if tag == todotxt::DUE_TAG || tag == todotxt::THR_TAG => (tag, value),
+            _ => continue,
+        };
+        if value.is_empty() || todotxt::parse_date(value, base).is_ok() {
+            continue;
+        }
+        let extra = MAX_EXTRA_WORDS.min(words.len() - idx);
+        let found = (0..=extra).rev().find_map(|n| {
+            let phrase = std::iter::once(value).chain(words[idx..idx + n].iter().map(|&(b, e)| &s[b..e]));
+            let phrase = phrase.collect::<Vec<_>>().join(" ");
+            human_date::parse_with_week_start(&phrase, base, c.week_start).map(|d| (d, n))
+        });
+        if let Some((d, n)) = found {
+            res.push_str(&s[copied..st]);
+            res.push_str(&format!("{tag}:{}", todotxt::format_date(d)));
+            idx += n;
+            copied = words[idx - 1].1;
+        }
+    }
+    res.push_str(&s[copied..]);
+    res
+}
+
+fn add_subject(tasks: &mut TaskVec, s: &str, c: &Conf, base: chrono::NaiveDate) -> usize {
+    let s = resolve_date_phrases(s, base, c);
+    let mut t = todotxt::Task::parse(&s, base);
     if c.auto_create_date && t.create_date.is_none() {
-        t.create_date = Some(chrono::Local::now().date_naive());
+        t.create_date = Some(base);
+    }
+    if t.priority == todotxt::NO_PRIORITY {
+        t.priority = c.default_priority;
+    }
+    if t.projects.is_empty() {
+        for p in &c.default_projects {
+            t.replace_project("", p);
+        }
+    }
+    if t.contexts.is_empty() {
+        for ctx in &c.default_contexts {
+            t.replace_context("", ctx);
+        }
     }
     tasks.push(t);
     tasks.len() - 1
 }
 
+// Returns a new todo that follows a recurrent one when it is completed on `now`.
+// Returns None if the todo is not recurrent, no occurrences are left for a
+// count-limited recurrence, or its next occurrence is after the date from
+// its `until:` tag.
+fn next_occurrence(task: &todotxt::Task, now: chrono::NaiveDate) -> Option<todotxt::Task> {
+    if task.finished || task.recurrence.is_none() || (task.due_date.is_none() && task.threshold_date.is_none()) {
+        return None;
+    }
+    let remaining = task.rec_remaining();
+    if remaining == Some(0) {
+        return None;
+    }
+    let mut next_task = task.clone();
+    if let Some(n) = remaining {
+        next_task.set_rec_remaining(n - 1);
+    }
+    if next_task.create_date.is_some() {
+        next_task.create_date = Some(now);
+    }
+    next_task.next_dates(now);
+    if let Some(until) = next_task.rec_until() {
+        if let Some(dt) = next_task.due_date.or(next_task.threshold_date) {
+            if dt > until {
+                return None;
+            }
+        }
+    }
+    next_task.cleanup_cloned_task();
+    Some(next_task)
+}
+
 fn done_undone(tasks: &mut TaskVec, ids: Option<&IDVec>, c: &Conf) -> ChangedVec {
+    done_undone_collect(tasks, ids, c).0
+}
+
+// Marks todos done or undone. Returns the list of changed flags and IDs of
+// completed recurrent todos that spawned new todos.
+fn done_undone_collect(tasks: &mut TaskVec, ids: Option<&IDVec>, c: &Conf) -> (ChangedVec, IDVec) {
+    let mut parents = Vec::new();
     if tasks.is_empty() {
-        return Vec::new();
+        return (Vec::new(), parents);
     }
     let longvec = make_id_vec(tasks.len());
     let id_iter = if let Some(v) = ids { v } else { &longvec };
@@ -343,20 +823,15 @@ fn done_undone(tasks: &mut TaskVec, ids: Option<&IDVec>, c: &Conf) -> ChangedVec
 
         if c.done {
             bools[i] = timer::stop_timer(&mut tasks[*idx]);
-            let mut next_task = (tasks[*idx]).clone();
+            let next_task = next_occurrence(&tasks[*idx], now);
             let completion_config =
                 CompletionConfig { completion_mode: c.completion_mode, completion_date_mode: c.completion_date_mode };
             let completed = tasks[*idx].complete_with_config(now, completion_config);
-            if completed
-                && next_task.recurrence.is_some()
-                && (next_task.due_date.is_some() || next_task.threshold_date.is_some())
-            {
-                if next_task.create_date.is_some() {
-                    next_task.create_date = Some(now);
+            if completed {
+                if let Some(next_task) = next_task {
+                    tasks.push(next_task);
+                    parents.push(*idx);
                 }
-                next_task.next_dates(now);
-                next_task.cleanup_cloned_task();
-                tasks.push(next_task);
             }
             bools[i] = bools[i] || completed;
         } else {
@@ -364,7 +839,7 @@ fn done_undone(tasks: &mut TaskVec, ids: Option<&IDVec>, c: &Conf) -> ChangedVec
         }
     }
 
-    bools
+    (bools, parents)
 }
 
 /// Marks todos completed.
@@ -375,10 +850,14 @@ fn done_undone(tasks: &mut TaskVec, ids: Option<&IDVec>, c: &Conf) -> ChangedVec
 /// If a todo is a recurrent one and any of due and threshold dates exist,
 /// the function marks the current task done and appends a new task with
 /// changed due and threshold dates (current values increased by recurrence value).
+/// If the recurrent todo has tag `until:` and the new due date (or threshold
+/// date if the todo does not have due date) is after it, no new todo is added.
+/// A count-limited recurrent todo (e.g., `rec:1w*3`) adds a new todo with
+/// the count decreased by one. A todo with `*0` does not add a new todo.
 ///
 /// * `tasks` - the task list
 /// * `ids` - the list of todo IDs which should be completed. If it is `None`
-///     the entire task list is marked completed
+///   the entire task list is marked completed
 /// * `completion_config` = how additional fields are set during completion (see todotxt::CompletionConfig)
 ///
 /// Returns a list of boolean values: a value per each ID in `ids` or `tasks`.
@@ -395,11 +874,54 @@ pub fn done(tasks: &mut TaskVec, ids: Option<&IDVec>, completion_config: todotxt
     done_undone(tasks, ids, &c)
 }
 
+/// Marks todos selected by `range` completed like `done` does. The range is
+/// resolved the same way `tfilter::filter` does it: IDs outside of the task
+/// list are skipped, and `ItemRange::None` selects the entire task list.
+///
+/// Returns a list of boolean values: a value per each resolved ID.
+pub fn done_range(
+    tasks: &mut TaskVec,
+    range: &tfilter::ItemRange,
+    completion_config: todotxt::CompletionConfig,
+) -> ChangedVec {
+    let ids = tfilter::range_ids(tasks, range);
+    done(tasks, Some(&ids), completion_config)
+}
+
+/// Removes flag `done` from todos selected by `range` like `undone` does.
+/// The range is resolved the same way `tfilter::filter` does it.
+///
+/// Returns a list of boolean values: a value per each resolved ID.
+pub fn undone_range(tasks: &mut TaskVec, range: &tfilter::ItemRange, mode: todotxt::CompletionMode) -> ChangedVec {
+    let ids = tfilter::range_ids(tasks, range);
+    undone(tasks, Some(&ids), mode)
+}
+
+/// Marks todos completed like `done` does, but takes completion options
+/// from `c` (`completion_mode` and `completion_date_mode`).
+///
+/// If `c.collect_recurrent_parents` is `true`, the second value is the list
+/// of IDs of completed recurrent todos that spawned new todos. The IDs are in
+/// the same order as the todos were processed (the order of `ids`, or
+/// ascending if `ids` is `None`). The IDs are valid for the list after the
+/// call: new todos are always appended to the end of the list, so removing the
+/// parents, e.g. with `archive` and `remove`, keeps their new todos. If the
+/// flag is not set, the list is empty.
+pub fn done_with_conf(tasks: &mut TaskVec, ids: Option<&IDVec>, c: &Conf) -> (ChangedVec, IDVec) {
+    let c = Conf { done: true, ..c.clone() };
+    let (changed, parents) = done_undone_collect(tasks, ids, &c);
+    if c.collect_recurrent_parents {
+        (changed, parents)
+    } else {
+        (changed, Vec::new())
+    }
+}
+
 /// Removes flag `done` from todos.
 ///
 /// * `tasks` - the task list
 /// * `ids` - the list of todo IDs which should be undone. If it is `None`
-///     the entire task list is marked undone.
+///   the entire task list is marked undone.
 /// * `completion_mode` = what to do with a priority on completion (see todotxt::CompletionMode)
 ///
 /// Returns a list of boolean values: a value per each ID in `ids` or `tasks`.
@@ -411,11 +933,60 @@ pub fn undone(tasks: &mut TaskVec, ids: Option<&IDVec>, mode: todotxt::Completio
     done_undone(tasks, ids, &c)
 }
 
+/// Runs any function that modifies todos, e.g. `done`, `undone`, or `edit`, on
+/// a copy of the task list and returns what would happen without changing
+/// `tasks`. Useful to preview changes before applying them.
+///
+/// * `tasks` - the task list
+/// * `f` - the function to run, it gets a copy of the task list
+///
+/// Returns the list of boolean values returned by `f` and the list of todos
+/// that would change: the todo ID and the new todo text. The list includes
+/// the todos that would be appended to the list, e.g. the next occurrence of
+/// a completed recurrent todo; their IDs start from `tasks.len()`.
+///
+/// Example: `dry_run(&tasks, |t| done(t, Some(&ids), completion_config))`.
+pub fn dry_run<F>(tasks: &TaskSlice, f: F) -> (ChangedVec, Vec<(usize, String)>)
+where
+    F: FnOnce(&mut TaskVec) -> ChangedVec,
+{
+    let mut copy = tasks.to_vec();
+    let changed = f(&mut copy);
+    let lines = copy
+        .iter()
+        .enumerate()
+        .filter(|(idx, t)| tasks.get(*idx) != Some(*t))
+        .map(|(idx, t)| (idx, t.to_string()))
+        .collect();
+    (changed, lines)
+}
+
+/// Combines two todo lists without duplicates, e.g. to sync todo files
+/// between devices.
+///
+/// The result contains all todos from `base` followed by the todos from
+/// `other` that are not in `base` yet. Todos are compared by their text
+/// representation (case-sensitive), so todos that differ only in a tag value,
+/// e.g. `tmr:`, are different. Duplicates inside `other` are added once.
+///
+/// The number of added todos is the difference between the lengths of the
+/// result and `base`.
+pub fn merge(base: &TaskSlice, other: &TaskSlice) -> TaskVec {
+    let mut seen: HashSet<String> = base.iter().map(|t| t.to_string()).collect();
+    let mut res = base.to_vec();
+    for t in other.iter() {
+        if seen.insert(t.to_string()) {
+            res.push(t.clone());
+        }
+    }
+    res
+}
+
 /// Removes todos from the list
 ///
 /// * `tasks` - the task list
 /// * `ids` - the list of todo IDs which should be removed. If it is `None`
-///     the task list is cleared.
+///   the task list is cleared.
 ///
 /// Returns a list of boolean values: a value per each ID in `ids` or `tasks`.
 /// The length of the result list equals either length of `ids`(if `ids` is
@@ -455,30 +1026,27 @@ pub fn remove(tasks: &mut TaskVec, ids: Option<&IDVec>) -> ChangedVec {
 }
 
 fn update_priority(task: &mut todotxt::Task, c: &Conf) -> bool {
-    match c.priority.action {
-        Action::Set => {
-            if task.priority != c.priority.value {
-                task.priority = c.priority.value;
-                return true;
-            }
+    let (action, step) = match c.priority.action {
+        Action::IncreaseBy(n) => (Action::Increase, n.max(1)),
+        Action::DecreaseBy(n) => (Action::Decrease, n.max(1)),
+        act => (act, 1),
+    };
+    match action {
+        Action::Set if task.priority != c.priority.value => {
+            task.priority = c.priority.value;
+            return true;
         }
-        Action::Delete => {
-            if task.priority != todotxt::NO_PRIORITY {
-                task.priority = todotxt::NO_PRIORITY;
-                return true;
-            }
+        Action::Delete if task.priority != todotxt::NO_PRIORITY => {
+            task.priority = todotxt::NO_PRIORITY;
+            return true;
         }
-        Action::Increase => {
-            if task.priority != 0 && task.priority != todotxt::NO_PRIORITY {
-                task.priority -= 1u8;
-                return true;
-            }
+        Action::Increase if task.priority != 0 => {
+            task.priority = task.priority.min(todotxt::NO_PRIORITY).saturating_sub(step);
+            return true;
         }
-        Action::Decrease => {
-            if task.priority != todotxt::NO_PRIORITY {
-                task.priority += 1u8;
-                return true;
-            }
+        Action::Decrease if task.priority < todotxt::NO_PRIORITY => {
+            task.priority = task.priority.saturating_add(step).min(todotxt::NO_PRIORITY);
+            return true;
         }
         _ => {}
     }
@@ -486,7 +1054,36 @@ fn update_priority(task: &mut todotxt::Task, c: &Conf) -> bool {
     false
 }
 
-fn update_due_date(task: &mut todotxt::Task, base: chrono::NaiveDate, c: &Conf) -> bool {
+// Returns true if both dates are set and the due date is before the threshold one
+fn is_date_order_inverted(due: Option<chrono::NaiveDate>, thr: Option<chrono::NaiveDate>) -> bool {
+    matches!((due, thr), (Some(d), Some(t)) if d < t)
+}
+
+// Updates due and threshold dates. With `validate_date_order`, both changes
+// are dropped if the todo would end up with due date before threshold date.
+fn update_dates(
+    task: &mut todotxt::Task,
+    base: chrono::NaiveDate,
+    c: &Conf,
+) -> (Result<bool, String>, Result<bool, String>) {
+    if !c.validate_date_order {
+        let due = update_due_date(task, base, c);
+        return (due, update_thr_date(task, base, c));
+    }
+    let mut updated = task.clone();
+    let due = update_due_date(&mut updated, base, c);
+    let thr = update_thr_date(&mut updated, base, c);
+    if !matches!(due, Ok(true)) && !matches!(thr, Ok(true)) {
+        return (due, thr);
+    }
+    if is_date_order_inverted(updated.due_date, updated.threshold_date) {
+        return (due.map(|_| false), thr.map(|_| false));
+    }
+    *task = updated;
+    (due, thr)
+}
+
+fn update_due_date(task: &mut todotxt::Task, base: chrono::NaiveDate, c: &Conf) -> Result<bool, String> {
     match c.due.action {
         Action::Set => {
             let new_due = match &c.due.value {
@@ -495,10 +1092,10 @@ fn update_due_date(task: &mut todotxt::Task, base: chrono::NaiveDate, c: &Conf)
                 NewDateValue::Expr(expr) => {
                     let mut tlist = date_expr::TaskTagList::from_task(task);
                     match date_expr::calculate_expr(base, expr, &mut tlist, c.soon_days) {
-                        Err(e) => {
-                            eprintln!("Failed to calculate due date expression [{expr}]: {e:?}");
-                            return false;
-                        }
+                        Err(e) => match human_date::parse_with_week_start(expr, base, c.week_start) {
+                            Some(d) => Some(d),
+                            None => return Err(format!("Failed to calculate due date expression [{expr}]: {e}")),
+                        },
                         Ok(d) => Some(d),
                     }
                 }
@@ -508,22 +1105,20 @@ fn update_due_date(task: &mut todotxt::Task, base: chrono::NaiveDate, c: &Conf)
                     None => task.update_tag_with_value(todotxt::DUE_TAG, ""),
                     Some(dt) => task.update_tag_with_value(todotxt::DUE_TAG, &todotxt::format_date(dt)),
                 };
-                return true;
+                return Ok(true);
             }
         }
-        Action::Delete => {
-            if task.due_date.is_some() {
-                task.update_tag_with_value(todotxt::DUE_TAG, "");
-                return true;
-            }
+        Action::Delete if task.due_date.is_some() => {
+            task.update_tag_with_value(todotxt::DUE_TAG, "");
+            return Ok(true);
         }
         _ => {}
     }
 
-    false
+    Ok(false)
 }
 
-fn update_thr_date(task: &mut todotxt::Task, base: chrono::NaiveDate, c: &Conf) -> bool {
+fn update_thr_date(task: &mut todotxt::Task, base: chrono::NaiveDate, c: &Conf) -> Result<bool, String> {
     match c.thr.action {
         Action::Set => {
             let new_thr = match &c.thr.value {
@@ -532,10 +1127,10 @@ fn update_thr_date(task: &mut todotxt::Task, base: chrono::NaiveDate, c: &Conf)
                 NewDateValue::Expr(expr) => {
                     let mut tlist = date_expr::TaskTagList::from_task(task);
                     match date_expr::calculate_expr(base, expr, &mut tlist, c.soon_days) {
-                        Err(e) => {
-                            eprintln!("Failed to calculate threshold date expression [{expr}]: {e:?}");
-                            return false;
-                        }
+                        Err(e) => match human_date::parse_with_week_start(expr, base, c.week_start) {
+                            Some(d) => Some(d),
+                            None => return Err(format!("Failed to calculate threshold date expression [{expr}]: {e}")),
+                        },
                         Ok(d) => Some(d),
                     }
                 }
@@ -545,40 +1140,32 @@ fn update_thr_date(task: &mut todotxt::Task, base: chrono::NaiveDate, c: &Conf)
                     None => task.update_tag_with_value(todotxt::THR_TAG, ""),
                     Some(dt) => task.update_tag_with_value(todotxt::THR_TAG, &todotxt::format_date(dt)),
                 };
-                return true;
+                return Ok(true);
             }
         }
-        Action::Delete => {
-            if task.threshold_date.is_some() {
-                task.update_tag_with_value(todotxt::THR_TAG, "");
-                return true;
-            }
+        Action::Delete if task.threshold_date.is_some() => {
+            task.update_tag_with_value(todotxt::THR_TAG, "");
+            return Ok(true);
         }
         _ => {}
     }
 
-    false
+    Ok(false)
 }
 
 fn update_recurrence(task: &mut todotxt::Task, c: &Conf) -> bool {
     match c.recurrence.action {
-        Action::Set => {
-            if !tsort::equal_opt_rec(&task.recurrence, &c.recurrence.value) {
-                if let Some(nr) = &c.recurrence.value {
-                    let new_rec = format!("{nr}");
-                    let updated = task.update_tag(&new_rec);
-                    if updated && task.finished {
-                        task.uncomplete(c.completion_mode);
-                    }
-                    return updated;
+        Action::Set if !tsort::equal_opt_rec(&task.recurrence, &c.recurrence.value) => {
+            if let Some(nr) = c.recurrence.value {
+                let updated = task.set_recurrence(Some(nr));
+                if updated && task.finished {
+                    task.uncomplete(c.completion_mode);
                 }
+                return updated;
             }
         }
-        Action::Delete => {
-            if task.recurrence.is_some() {
-                task.update_tag_with_value(todotxt::REC_TAG, "");
-                return true;
-            }
+        Action::Delete if task.recurrence.is_some() => {
+            return task.set_recurrence(None);
         }
         _ => {}
     }
@@ -586,14 +1173,43 @@ fn update_recurrence(task: &mut todotxt::Task, c: &Conf) -> bool {
     false
 }
 
+// Returns true if the list contains the value when `ignore_case_dedupe` is set.
+fn has_case_variant(list: &[String], value: &str, c: &Conf) -> bool {
+    c.ignore_case_dedupe && list.iter().any(|v| v.to_lowercase() == value.to_lowercase())
+}
+
+// Finds the value in the list ignoring case. An exact match is preferred.
+fn find_ignore_case(list: &[String], value: &str) -> Option<String> {
+    list.iter()
+        .find(|v| *v == value)
+        .or_else(|| list.iter().find(|v| v.to_lowercase() == value.to_lowercase()))
+        .cloned()
+}
+
+// Returns true if a value `new` already in the list (exact match or, with
+// `ignore_case_dedupe`, in any case) so renaming `old` to `new` would create a duplicate.
+fn rename_makes_duplicate(list: &[String], old: &str, new: &str, c: &Conf) -> bool {
+    if old.to_lowercase() == new.to_lowercase() {
+        return list.iter().any(|v| v == new);
+    }
+    list.iter().any(|v| v == new) || has_case_variant(list, new, c)
+}
+
 fn update_projects(task: &mut todotxt::Task, c: &Conf) -> bool {
     let mut changed = false;
 
     for new_p in &c.projects.value {
         match c.projects.action {
             Action::Set => {
+                if has_case_variant(&task.projects, new_p.trim_start_matches('+'), c) {
+                    continue;
+                }
                 let old_subj = task.subject.clone();
-                task.replace_project("", new_p);
+                if c.prepend_lists {
+                    task.prepend_project(new_p);
+                } else {
+                    task.replace_project("", new_p);
+                }
                 changed = old_subj != task.subject;
             }
             Action::Delete => {
@@ -604,8 +1220,19 @@ fn update_projects(task: &mut todotxt::Task, c: &Conf) -> bool {
             Action::Replace => {
                 let pair: Vec<&str> = new_p.split_terminator('+').collect();
                 if pair.len() == 2 && pair[0] != pair[1] && !pair[0].is_empty() && !pair[1].is_empty() {
+                    let old = match find_ignore_case(&task.projects, pair[0]) {
+                        None => continue,
+                        Some(old) => old,
+                    };
+                    if old == pair[1] {
+                        continue;
+                    }
                     let old_subj = task.subject.clone();
-                    task.replace_project(pair[0], pair[1]);
+                    if rename_makes_duplicate(&task.projects, &old, pair[1], c) {
+                        task.replace_project(&old, "");
+                    } else {
+                        task.replace_project(&old, pair[1]);
+                    }
                     changed = old_subj != task.subject;
                 }
             }
@@ -622,8 +1249,15 @@ fn update_contexts(task: &mut todotxt::Task, c: &Conf) -> bool {
     for new_c in &c.contexts.value {
         match c.contexts.action {
             Action::Set => {
+                if has_case_variant(&task.contexts, new_c.trim_start_matches('@'), c) {
+                    continue;
+                }
                 let old_subj = task.subject.clone();
-                task.replace_context("", new_c);
+                if c.prepend_lists {
+                    task.prepend_context(new_c);
+                } else {
+                    task.replace_context("", new_c);
+                }
                 changed = old_subj != task.subject;
             }
             Action::Delete => {
@@ -634,8 +1268,19 @@ fn update_contexts(task: &mut todotxt::Task, c: &Conf) -> bool {
             Action::Replace => {
                 let pair: Vec<&str> = new_c.split_terminator('@').collect();
                 if pair.len() == 2 && pair[0] != pair[1] && !pair[0].is_empty() && !pair[1].is_empty() {
+                    let old = match find_ignore_case(&task.contexts, pair[0]) {
+                        None => continue,
+                        Some(old) => old,
+                    };
+                    if old == pair[1] {
+                        continue;
+                    }
                     let old_subj = task.subject.clone();
-                    task.replace_context(pair[0], pair[1]);
+                    if rename_makes_duplicate(&task.contexts, &old, pair[1], c) {
+                        task.replace_context(&old, "");
+                    } else {
+                        task.replace_context(&old, pair[1]);
+                    }
                     changed = old_subj != task.subject;
                 }
             }
@@ -693,12 +1338,10 @@ fn hashtag_update_check(task: &mut todotxt::Task, hashtag: &str, act: Action) ->
                 return true;
             }
         }
-        Action::Set => {
-            if !task.hashtags.contains(&hashtag.to_string()) {
-                task.hashtags.push(hashtag.to_string());
-                task.subject = format!("{} {}", task.subject, hashtag_full);
-                return true;
-            }
+        Action::Set if !task.hashtags.contains(&hashtag.to_string()) => {
+            task.hashtags.push(hashtag.to_string());
+            task.subject = format!("{} {}", task.subject, hashtag_full);
+            return true;
         }
         Action::Replace => {
             if let Some((old, new)) = todotxt::split_tag(hashtag) {
@@ -735,6 +1378,36 @@ fn update_hashtags(task: &mut todotxt::Task, c: &Conf) -> bool {
     changed
 }
 
+/// Properties of a todo changed by `edit_detailed`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChangedFields {
+    /// The todo was replaced with a new subject
+    pub subject: bool,
+    pub priority: bool,
+    pub due: bool,
+    pub thr: bool,
+    pub recurrence: bool,
+    pub projects: bool,
+    pub contexts: bool,
+    pub tags: bool,
+    pub hashtags: bool,
+}
+
+impl ChangedFields {
+    /// Returns `true` if any property of the todo was changed.
+    pub fn any(&self) -> bool {
+        self.subject
+            || self.priority
+            || self.due
+            || self.thr
+            || self.recurrence
+            || self.projects
+            || self.contexts
+            || self.tags
+            || self.hashtags
+    }
+}
+
 /// Modifies existing todos.
 ///
 /// A powerful function to transform todo list. List of operations:
@@ -742,14 +1415,14 @@ fn update_hashtags(task: &mut todotxt::Task, c: &Conf) -> bool {
 /// - for subject: set a new subject;
 /// - for due date: remove or set a new one;
 /// - for recurrence: remove or set a new one (setting recurrence clears
-///     `done` flag;
+///   `done` flag;
 /// - for project: add a new, remove old, replace old with new one;
 /// - for context: add a new, remove old, replace old with new one;
 /// - for an arbitrary tag: add a new, remove old, replace old with a new one;
 ///
 /// * `tasks` - the task list
 /// * `ids` - the list of todo IDs which should be undone. If it is `None`
-///     the entire task list is marked undone.
+///   the entire task list is marked undone.
 /// * `c` - what to modify and how
 ///
 /// if `c` contains a new subject then only the first todo from `ids` is
@@ -759,16 +1432,54 @@ fn update_hashtags(task: &mut todotxt::Task, c: &Conf) -> bool {
 /// The length of the result list equals either length of `ids`(if `ids` is
 /// `Some`) or  length of `tasks`(if `ids` is `None`). Value `true` in this
 /// array means that corresponding item from `ids` or `tasks` was modified.
+///
+/// Errors of date expression calculation are sent to the warning handler.
+/// Use `edit_with_report` to get them.
 pub fn edit(tasks: &mut TaskVec, ids: Option<&IDVec>, c: &Conf) -> ChangedVec {
+    let (bools, errors) = edit_with_report(tasks, ids, c);
+    for (_, err) in errors {
+        terr::warn(&err);
+    }
+    bools
+}
+
+/// Modifies existing todos the same way `edit` does, and collects errors.
+///
+/// Returns the same list of boolean values as `edit` and the list of errors
+/// that happened while modifying todos: the todo ID and the error message.
+/// E.g., a due date expression that cannot be calculated for a todo. The
+/// due or threshold date of such todo is not changed, all other changes are
+/// applied.
+pub fn edit_with_report(tasks: &mut TaskVec, ids: Option<&IDVec>, c: &Conf) -> (ChangedVec, Vec<(usize, String)>) {
+    let (fields, errors) = edit_fields(tasks, ids, c);
+    (fields.iter().map(ChangedFields::any).collect(), errors)
+}
+
+/// Modifies existing todos the same way `edit` does, but returns what
+/// properties of every todo were changed instead of a single boolean value.
+///
+/// The length of the result list is the same as the length of the list
+/// returned by `edit`. Errors of date expression calculation are sent to the
+/// warning handler.
+pub fn edit_detailed(tasks: &mut TaskVec, ids: Option<&IDVec>, c: &Conf) -> Vec<ChangedFields> {
+    let (fields, errors) = edit_fields(tasks, ids, c);
+    for (_, err) in errors {
+        terr::warn(&err);
+    }
+    fields
+}
+
+fn edit_fields(tasks: &mut TaskVec, ids: Option<&IDVec>, c: &Conf) -> (Vec<ChangedFields>, Vec<(usize, String)>) {
+    let mut errors = Vec::new();
     if tasks.is_empty() {
-        return vec![];
+        return (vec![], errors);
     }
 
     let longvec = make_id_vec(tasks.len());
     let idlist = if let Some(v) = ids { v } else { &longvec };
     let now = chrono::Local::now().date_naive();
 
-    let mut bools = vec![false; idlist.len()];
+    let mut fields = vec![ChangedFields::default(); idlist.len()];
     for (i, idx) in idlist.iter().enumerate() {
         let id = *idx;
         if id >= tasks.len() {
@@ -780,24 +1491,84 @@ pub fn edit(tasks: &mut TaskVec, ids: Option<&IDVec>, c: &Conf) -> ChangedVec {
             if t.create_date.is_none() && tasks[id].create_date.is_some() {
                 t.create_date = tasks[id].create_date;
             }
+            if c.touch_modified {
+                t.touch(now);
+            }
             tasks[id] = t;
-            bools[i] = true;
+            fields[i].subject = true;
             // it does not make sense to replace more than 1 todo's subject
             // with the same text. So, replace for the first one and stop
             break;
         }
 
-        bools[i] = update_priority(&mut tasks[id], c);
-        bools[i] |= update_due_date(&mut tasks[id], now, c);
-        bools[i] |= update_thr_date(&mut tasks[id], now, c);
-        bools[i] |= update_recurrence(&mut tasks[id], c);
-        bools[i] |= update_projects(&mut tasks[id], c);
-        bools[i] |= update_contexts(&mut tasks[id], c);
-        bools[i] |= update_tags(&mut tasks[id], c);
-        bools[i] |= update_hashtags(&mut tasks[id], c);
+        let f = &mut fields[i];
+        f.priority = update_priority(&mut tasks[id], c);
+        let (due, thr) = update_dates(&mut tasks[id], now, c);
+        match due {
+            Ok(changed) => f.due = changed,
+            Err(e) => errors.push((id, e)),
+        }
+        match thr {
+            Ok(changed) => f.thr = changed,
+            Err(e) => errors.push((id, e)),
+        }
+        f.recurrence = update_recurrence(&mut tasks[id], c);
+        f.projects = update_projects(&mut tasks[id], c);
+        f.contexts = update_contexts(&mut tasks[id], c);
+        f.tags = update_tags(&mut tasks[id], c);
+        f.hashtags = update_hashtags(&mut tasks[id], c);
+        if c.touch_modified && f.any() {
+            tasks[id].touch(now);
+        }
     }
 
-    bools
+    (fields, errors)
+}
+
+/// Returns IDs of active todos that are due on a given date.
+///
+/// A todo is selected if its due date equals `date`. A recurrent todo is also
+/// selected if any of its future occurrences, calculated from its due date by
+/// applying the recurrence again and again, falls on `date`. The occurrences
+/// are calculated only for dates within `RECURRENCE_HORIZON_DAYS` days after the
+/// todo's due date.
+///
+/// * `tasks` - the task list
+/// * `date` - the date to look for
+pub fn due_on(tasks: &TaskSlice, date: chrono::NaiveDate) -> IDVec {
+    let mut ids: IDVec = Vec::new();
+    for (idx, task) in tasks.iter().enumerate() {
+        if task.finished {
+            continue;
+        }
+        let due = match task.due_date {
+            None => continue,
+            Some(d) => d,
+        };
+        if due == date {
+            ids.push(idx);
+            continue;
+        }
+        let rec = match task.recurrence {
+            None => continue,
+            Some(r) => r,
+        };
+        if date < due || (date - due).num_days() > RECURRENCE_HORIZON_DAYS {
+            continue;
+        }
+        let mut dt = due;
+        while dt < date {
+            let next = rec.next_date(dt);
+            if next <= dt {
+                break;
+            }
+            dt = next;
+        }
+        if dt == date {
+            ids.push(idx);
+        }
+    }
+    ids
 }
 
 /// Starts timers of all toods that are not done
@@ -843,3 +1614,189 @@ pub fn stop(tasks: &mut TaskVec, ids: Option<&IDVec>) -> ChangedVec {
 
     bools
 }
+
+/// Where the due date of a todo is relative to a given date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DueStatus {
+    /// The todo is completed
+    Done,
+    /// The todo does not have a due date
+    NoDue,
+    /// The due date is in the past
+    Overdue,
+    /// The todo is due the given date
+    Today,
+    /// The todo is due within the next `soon_days` days
+    Soon,
+    /// The todo is due later than in `soon_days` days
+    Later,
+}
+
+/// Returns the due status of a todo relative to `today`.
+///
+/// * `task` - the todo to check
+/// * `today` - the date to compare the due date with
+/// * `soon_days` - the number of days after `today` that are considered `soon`
+pub fn due_status(task: &todotxt::Task, today: chrono::NaiveDate, soon_days: u8) -> DueStatus {
+    if task.finished {
+        return DueStatus::Done;
+    }
+    let due = match task.due_date {
+        None => return DueStatus::NoDue,
+        Some(d) => d,
+    };
+    let days = (due - today).num_days();
+    match days {
+        d if d < 0 => DueStatus::Overdue,
+        0 => DueStatus::Today,
+        d if d <= soon_days as i64 => DueStatus::Soon,
+        _ => DueStatus::Later,
+    }
+}
+
+/// Todo IDs grouped by their due status (see `due_status`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Agenda {
+    pub overdue: IDVec,
+    pub today: IDVec,
+    pub soon: IDVec,
+    pub later: IDVec,
+    pub no_due: IDVec,
+    pub done: IDVec,
+}
+
+/// Groups the todos by their due status for displaying an agenda.
+///
+/// Every group is sorted by priority, from highest to lowest, todos without
+/// priority go last. Todos with the same priority keep their order in the list.
+///
+/// * `tasks` - the task list
+/// * `today` - the date to compare due dates with
+/// * `soon_days` - the number of days after `today` that are considered `soon`
+pub fn agenda(tasks: &TaskSlice, today: chrono::NaiveDate, soon_days: u8) -> Agenda {
+    let mut res = Agenda::default();
+    for (idx, task) in tasks.iter().enumerate() {
+        let group = match due_status(task, today, soon_days) {
+            DueStatus::Done => &mut res.done,
+            DueStatus::NoDue => &mut res.no_due,
+            DueStatus::Overdue => &mut res.overdue,
+            DueStatus::Today => &mut res.today,
+            DueStatus::Soon => &mut res.soon,
+            DueStatus::Later => &mut res.later,
+        };
+        group.push(idx);
+    }
+    let c = tsort::Conf { fields: Some("pri".to_string()), rev: false };
+    for group in [&mut res.overdue, &mut res.today, &mut res.soon, &mut res.later, &mut res.no_due, &mut res.done] {
+        tsort::sort(group, tasks, &c);
+    }
+    res
+}
+
+/// Returns the first and the last dates of a calendar range, e.g. for `2w`
+/// the range starts on the first day of the current week and ends on the
+/// last day of the next week. Weeks start on `c.week_start`.
+///
+/// * `today` - the date the range is calculated from
+/// * `rng` - the calendar range
+/// * `c` - the configuration
+pub fn calendar_range(
+    today: chrono::NaiveDate,
+    rng: &human_date::CalendarRange,
+    c: &Conf,
+) -> (chrono::NaiveDate, chrono::NaiveDate) {
+    (human_date::calendar_first_day(today, rng, c.week_start), human_date::calendar_last_day(today, rng, c.week_start))
+}
+
+/// Returns the earliest and the latest dates of all todos in the list. All
+/// date fields are checked: creation, completion, due, and threshold dates.
+///
+/// * `tasks` - the task list
+///
+/// Returns `None` if no todo has any date.
+pub fn date_span(tasks: &TaskSlice) -> Option<(chrono::NaiveDate, chrono::NaiveDate)> {
+    let mut span: Option<(chrono::NaiveDate, chrono::NaiveDate)> = None;
+    for task in tasks {
+        for dt in [task.create_date, task.finish_date, task.due_date, task.threshold_date].into_iter().flatten() {
+            span = match span {
+                None => Some((dt, dt)),
+                Some((first, last)) => Some((first.min(dt), last.max(dt))),
+            };
+        }
+    }
+    span
+}
+
+/// Moves incomplete todos that are due on `from` to `to`. Todos due on other
+/// dates, todos without due date, and completed todos are not changed.
+///
+/// * `tasks` - the task list
+/// * `from` - the due date of todos to move
+/// * `to` - the new due date
+///
+/// Returns a list of boolean values: a value per each todo in `tasks`. Value
+/// `true` means that the corresponding todo was modified.
+pub fn carry_forward(tasks: &mut TaskVec, from: chrono::NaiveDate, to: chrono::NaiveDate) -> ChangedVec {
+    let mut changed = vec![false; tasks.len()];
+    if from == to {
+        return changed;
+    }
+    let new_due = todotxt::format_date(to);
+    for (idx, task) in tasks.iter_mut().enumerate() {
+        if task.finished || task.due_date != Some(from) {
+            continue;
+        }
+        changed[idx] = task.update_tag_with_value(todotxt::DUE_TAG, &new_due);
+    }
+    changed
+}
+
+/// What happens when a todo is completed (see `done_preview`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DonePreview {
+    /// A new todo is appended to the list
+    pub will_spawn: bool,
+    /// The due date of the new todo
+    pub next_due: Option<chrono::NaiveDate>,
+}
+
+/// Shows what `done` would do with todos if they were completed on `today`.
+/// The task list is not modified.
+///
+/// * `tasks` - the task list
+/// * `ids` - the list of todo IDs to check. If it is `None` the entire task
+///   list is checked
+/// * `today` - the date of completion
+///
+/// Returns a list of previews: a value per each ID in `ids` or `tasks`.
+/// Invalid IDs and already completed todos never spawn a new todo.
+pub fn done_preview(tasks: &TaskSlice, ids: Option<&IDVec>, today: chrono::NaiveDate) -> Vec<DonePreview> {
+    let longvec = make_id_vec(tasks.len());
+    let id_iter = if let Some(v) = ids { v } else { &longvec };
+    id_iter
+        .iter()
+        .map(|idx| match tasks.get(*idx).and_then(|t| next_occurrence(t, today)) {
+            None => DonePreview { will_spawn: false, next_due: None },
+            Some(t) => DonePreview { will_spawn: true, next_due: t.due_date },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn date_phrases() {
+        let base = chrono::NaiveDate::from_ymd_opt(2023, 8, 16).unwrap();
+        let c = Conf::default();
+        for s in ["call  mom\tdue:someday  later ", "fix car due:2023-09-01  +car", "due: t:soon-ish  plan", ""] {
+            assert_eq!(resolve_date_phrases(s, base, &c), s);
+        }
+        assert_eq!(
+            resolve_date_phrases("call\tbob  due:next  friday  about it", base, &c),
+            "call\tbob  due:2023-08-18  about it"
+        );
+        assert_eq!(resolve_date_phrases("t:tomorrow\tdue:in 2 days ", base, &c), "t:2023-08-17\tdue:2023-08-18 ");
+    }
+}
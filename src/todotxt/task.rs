@@ -2,9 +2,11 @@ use std::collections::HashMap;
 
 use chrono::{Local, NaiveDate};
 
+use crate::terr;
 use crate::todotxt::utils;
 
 const PRIORITY_TAG: &str = "pri";
+const NOTE_TAG: &str = "note";
 const CLEANUP_CLONE_TAGS: [&str; 2] = ["tmr:", "spent:"];
 
 /// Has options to manipulate how task information is handled when
@@ -46,10 +48,59 @@ pub enum CompletionMode {
 pub enum CompletionDateMode {
     /// Only add completion date if task has creation date
     WhenCreationDateIsPresent,
-    /// Always add completion date, regardless of whether or not creation date is present
+    /// Always add completion date, regardless of whether or not creation date is present.
+    /// The completion date is written right after `x` (e.g., `x 2023-08-20 Task`), so
+    /// the task can be parsed back with its completion date. The only exception is
+    /// `CompletionMode::JustMark` that keeps the priority between `x` and the date.
     AlwaysSet,
 }
 
+/// Extra rules that `Task::parse_with_options` applies to a parsed task.
+/// By default, no rule is applied and the result is the same as `Task::parse` returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParseOptions {
+    /// Restore the priority of a completed task from its `pri:` tag (see
+    /// `Task::restore_priority_from_tag`). The tag is kept.
+    ///
+    /// **Note:** the restored priority is written back when the task is
+    /// converted to a string, so a file saved after loading with this option
+    /// changes: the line `x testb pri:B` is written as `x (B) testb pri:B`.
+    pub restore_priority: bool,
+    /// Remove `rec:` tags with empty or invalid values (e.g., `rec:` or
+    /// `rec:xyz`) from the subject (see `Task::strip_invalid_recurrence_tags`).
+    /// Such lines are written without those tags when the file is saved.
+    pub strip_invalid_rec: bool,
+}
+
+/// Order of the block of `key:value` tags at the end of a task subject
+/// when the task is converted to a string with `Task::to_string_with_tag_order`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum TagOrder {
+    /// Keep tags in the order they were written. Changed tags stay in place,
+    /// new tags are appended to the end. Unchanged lines are saved as is.
+    #[default]
+    Original,
+    /// Sort tags alphabetically by tag name
+    Alphabetical,
+}
+
+/// A part of a task that `Task::equal_ignoring` skips when comparing tasks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IgnoredField {
+    /// Priority
+    Priority,
+    /// Completion mark
+    Finished,
+    /// Creation date
+    CreateDate,
+    /// Completion date
+    FinishDate,
+    /// Timer state: tags `tmr:` and `spent:`
+    Timer,
+    /// A tag with the given name, e.g. `modified`
+    Tag(String),
+}
+
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Task {
     pub subject: String,
@@ -87,25 +138,42 @@ impl Default for Task {
 
 impl std::fmt::Display for Task {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.finished {
-            f.write_str("x ")?;
-        }
-        if self.priority < utils::NO_PRIORITY {
-            f.write_str(&utils::format_priority(self.priority))?;
-            f.write_str(" ")?;
-        }
-        if let Some(dt) = self.finish_date {
-            f.write_str(&utils::format_date(dt))?;
-            f.write_str(" ")?;
-        }
-        if let Some(dt) = self.create_date {
-            f.write_str(&utils::format_date(dt))?;
-            f.write_str(" ")?;
-        }
+        self.write_header(f, false)?;
         f.write_str(&self.subject)
     }
 }
 
+// Returns true if the word is a `key:value` tag and not a project, context, or hashtag.
+fn is_tag_word(word: &str) -> bool {
+    !word.starts_with('+') && !word.starts_with('@') && !word.starts_with('#') && utils::split_tag(word).is_some()
+}
+
+// Splits the subject that starts with a priority `(?)`, like it is after
+// completion with `CompletionMode::MovePriority`, into the priority and the rest
+// of the subject.
+fn split_moved_priority(subject: &str) -> Option<(u8, &str)> {
+    let pri_s = if let Some(idx) = subject.find(' ') { &subject[..idx] } else { subject };
+    let p = utils::parse_priority(pri_s).ok()?;
+    Some((p, subject[pri_s.len()..].trim_start()))
+}
+
+// Returns true if the word is a project, context, hashtag, or tag.
+fn is_metadata_word(word: &str) -> bool {
+    ((word.starts_with('+') || word.starts_with('@') || word.starts_with('#')) && word.len() > 1)
+        || utils::split_tag(word).is_some()
+}
+
+// Sorts alphabetically by key the block of tags at the end of the subject.
+fn sort_trailing_tags(subject: &str) -> String {
+    let mut words: Vec<&str> = subject.split(' ').collect();
+    let mut start = words.len();
+    while start > 0 && is_tag_word(words[start - 1]) {
+        start -= 1;
+    }
+    words[start..].sort_by_key(|w| utils::split_tag(w).map(|(k, _)| k).unwrap_or_default());
+    words.join(" ")
+}
+
 fn next_word(s: &str) -> &str {
     if s.is_empty() {
         return s;
@@ -116,22 +184,152 @@ fn next_word(s: &str) -> &str {
     }
 }
 
+// Returns true if the word looks like a recurrence tag but its value is not a valid recurrence.
+fn is_invalid_rec_word(word: &str) -> bool {
+    match word.strip_prefix(utils::REC_TAG_FULL) {
+        None => false,
+        Some(v) => v.parse::<utils::Recurrence>().is_err(),
+    }
+}
+
 fn try_read_date(s: &str, base: NaiveDate) -> Option<NaiveDate> {
-    let c = match s.chars().next() {
-        None => return None,
-        Some(ch) => ch,
-    };
+    let c = s.chars().next()?;
     if c.is_ascii_digit() {
         let dt = next_word(s);
-        match utils::parse_date(dt, base) {
-            Err(_) => None,
-            Ok(d) => Some(d),
-        }
+        utils::parse_date(dt, base).ok()
     } else {
         None
     }
 }
 
+// Encodes a note to be a value of a tag: a space becomes `_`, and special
+// characters are escaped with a backslash.
+fn encode_note(note: &str) -> String {
+    let mut res = String::with_capacity(note.len());
+    for c in note.chars() {
+        match c {
+            ' ' => res.push('_'),
+            '_' => res.push_str("\\_"),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\t' => res.push_str("\\t"),
+            _ => res.push(c),
+        }
+    }
+    res
+}
+
+fn decode_note(value: &str) -> String {
+    let mut res = String::with_capacity(value.len());
+    let mut escaped = false;
+    for c in value.chars() {
+        if escaped {
+            match c {
+                'n' => res.push('\n'),
+                't' => res.push('\t'),
+                _ => res.push(c),
+            }
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '_' => res.push(' '),
+            _ => res.push(c),
+        }
+    }
+    res
+}
+
+// Returns true if the word looks like a date in format `YYYY-MM-DD`, even an invalid one.
+fn looks_like_date(word: &str) -> bool {
+    let parts: Vec<&str> = word.split('-').collect();
+    parts.len() == 3 && parts.iter().all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+}
+
+// Checks the leading tokens of a line: priority, completion and creation dates.
+fn check_header(s: &str, base: NaiveDate) -> Result<(), terr::TodoError> {
+    let mut s = s.trim();
+    let finished = s.starts_with("x ");
+    if finished {
+        s = s["x ".len()..].trim();
+    }
+    let word = next_word(s);
+    if word.len() == 3 && word.starts_with('(') && word.ends_with(')') {
+        if utils::parse_priority(word).is_err() {
+            return Err(terr::TodoError::InvalidValue(word.to_string(), "priority".to_string()));
+        }
+        s = s[word.len()..].trim();
+    }
+    let names: &[&str] = if finished { &["completion date", "creation date"] } else { &["creation date"] };
+    for name in names {
+        let word = next_word(s);
+        if !looks_like_date(word) {
+            break;
+        }
+        if utils::parse_date(word, base).is_err() {
+            return Err(terr::TodoError::InvalidValue(word.to_string(), name.to_string()));
+        }
+        s = s[word.len()..].trim();
+    }
+    Ok(())
+}
+
+/// Converts a single todo.txt line into its canonical form. It is the same as
+/// parsing the line and converting the task back to a string, with the
+/// following guarantees:
+/// * leading and trailing whitespaces are removed;
+/// * runs of spaces inside the subject are collapsed into a single space;
+/// * relative dates of `due:` and `t:` tags are replaced with absolute ones
+///   calculated from `base`;
+/// * completion date always goes before creation date.
+pub fn canonicalize(line: &str, base: NaiveDate) -> String {
+    let task = Task::parse(line.trim(), base);
+    task.to_string().trim_end().to_string()
+}
+
+/// The most often used properties of a todo (see `quick_meta`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuickMeta {
+    pub finished: bool,
+    pub priority: u8,
+    pub due: Option<NaiveDate>,
+    pub recurrence: Option<utils::Recurrence>,
+}
+
+/// Reads completion flag, priority, due date, and recurrence of a todo in a
+/// single pass without full parsing: projects, contexts, and tags are not
+/// collected. The values are the same as `Task::parse` returns for the line:
+/// a priority is read only at the beginning of the line or right after `x`,
+/// and `(B)` after completion or creation date is a part of the subject
+/// (see `CompletionMode::MovePriority`).
+pub fn quick_meta(s: &str, base: NaiveDate) -> QuickMeta {
+    let s = s.replace('\t', " ");
+    let mut s = s.trim_start();
+    let mut meta = QuickMeta { finished: false, priority: utils::NO_PRIORITY, due: None, recurrence: None };
+    let mut due = None;
+    let mut rec = None;
+    for word in s.split(' ') {
+        match utils::split_tag(word) {
+            Some((utils::DUE_TAG, value)) => due = Some(value),
+            Some((utils::REC_TAG, value)) => rec = Some(value),
+            _ => {}
+        }
+    }
+    if s.starts_with("x ") {
+        meta.finished = true;
+        s = s["x ".len()..].trim();
+    }
+    if s.starts_with('(') {
+        if let Ok(p) = utils::parse_priority(next_word(s)) {
+            meta.priority = p;
+        }
+    }
+    meta.due = due.and_then(|v| utils::parse_date(v, base).ok());
+    meta.recurrence = rec.and_then(|v| v.parse::<utils::Recurrence>().ok());
+    meta
+}
+
 impl Task {
     fn parse_special_tags(&mut self, base: NaiveDate) {
         let mut old_tags: Vec<String> = Vec::new();
@@ -172,12 +370,64 @@ impl Task {
     }
 
     /// Coverts a string to a task.
+    /// Runs of spaces inside the subject are collapsed into a single space, so
+    /// the task is displayed the same way after it is modified.
+    /// Tabs are treated as spaces, and leading whitespaces are ignored.
     pub fn parse(s: &str, base: NaiveDate) -> Self {
-        let mut task = Task::validate(s, base);
+        let s = s.replace('\t', " ");
+        let mut task = Task::validate(s.trim_start(), base);
+        task.subject = utils::collapse_spaces(&task.subject);
         task.parse_special_tags(base);
         task
     }
 
+    /// Converts a string to a task like `parse` does, and applies the extra
+    /// rules enabled in `opts` to the result.
+    pub fn parse_with_options(s: &str, base: NaiveDate, opts: &ParseOptions) -> Self {
+        let mut task = Task::parse(s, base);
+        if opts.restore_priority {
+            task.restore_priority_from_tag();
+        }
+        if opts.strip_invalid_rec {
+            task.strip_invalid_recurrence_tags();
+        }
+        task
+    }
+
+    /// Converts a string to a task like `parse` does, but returns an error if
+    /// the line contains a value that looks like a priority, a date, or a
+    /// recurrence but is invalid (e.g., `2020-31-03` as a creation date, or
+    /// `due:2020-13-01`). The error is `TodoError::InvalidValue` with the
+    /// offending token and the name of the field.
+    pub fn parse_result(s: &str, base: NaiveDate) -> Result<Self, terr::TodoError> {
+        let (task, res) = Task::parse_checked(s, base);
+        res.map(|_| task)
+    }
+
+    // Parses a line like `parse` does and checks it like `parse_result` does.
+    // The task is returned even if the line contains an invalid value.
+    pub(crate) fn parse_checked(s: &str, base: NaiveDate) -> (Self, Result<(), terr::TodoError>) {
+        let task = Task::parse(s, base);
+        if let Err(e) = check_header(&s.replace('\t', " "), base) {
+            return (task, Err(e));
+        }
+        let checks = [
+            (utils::DUE_TAG, task.due_date.is_none()),
+            (utils::THR_TAG, task.threshold_date.is_none()),
+            (utils::REC_TAG, task.recurrence.is_none()),
+        ];
+        for (tag, failed) in checks {
+            if !failed {
+                continue;
+            }
+            if let Some(value) = task.tags.get(tag) {
+                let err = terr::TodoError::InvalidValue(value.to_string(), tag.to_string());
+                return (task, Err(err));
+            }
+        }
+        (task, Ok(()))
+    }
+
     fn validate(s: &str, base: NaiveDate) -> Self {
         let mut task = Task {
             finished: false,
@@ -251,6 +501,39 @@ impl Task {
         }
     }
 
+    /// Returns true if the subject starts with a word that looks like a date
+    /// but is not a valid one (e.g., `2020-31-03 call mother`). Such a word
+    /// is not recognized as a creation or completion date and becomes a part
+    /// of the subject.
+    pub fn has_unparsed_leading_date(&self) -> bool {
+        let word = next_word(&self.subject);
+        looks_like_date(word) && utils::parse_date(word, Local::now().date_naive()).is_err()
+    }
+
+    /// Returns true if the subject contains a `rec:` tag which value is empty or
+    /// is not a valid recurrence(e.g., `rec:` or `rec:xyz`). Such tags do not
+    /// set the task recurrence and are kept as a plain text.
+    pub fn has_invalid_recurrence_tag(&self) -> bool {
+        self.subject.split(' ').any(is_invalid_rec_word)
+    }
+
+    /// Removes all `rec:` tags with empty or invalid values from the subject.
+    /// A valid recurrence tag is kept intact. To do it while parsing, use
+    /// `parse_with_options` with `ParseOptions::strip_invalid_rec`.
+    /// Returns true if the subject was changed.
+    pub fn strip_invalid_recurrence_tags(&mut self) -> bool {
+        let invalid: Vec<String> =
+            self.subject.split(' ').filter(|w| is_invalid_rec_word(w)).map(|w| w.to_string()).collect();
+        for word in invalid.iter() {
+            utils::replace_word(&mut self.subject, word, "");
+            let value = &word[utils::REC_TAG_FULL.len()..];
+            if self.tags.get(utils::REC_TAG).is_some_and(|v| v == value) {
+                self.tags.remove(utils::REC_TAG);
+            }
+        }
+        !invalid.is_empty()
+    }
+
     /// Remove certain tags from a clone to avoid spoiling a new task with
     /// old data. Tags to remove see in `CLEANUP_CLONE_TAGS`.
     pub fn cleanup_cloned_task(&mut self) {
@@ -344,6 +627,111 @@ impl Task {
         }
     }
 
+    /// Sets or removes the task recurrence. Both the field `recurrence` and
+    /// the tag `rec:` in the subject are updated: the tag is added, replaced in
+    /// place, or removed. When the recurrence changes, the count of remaining
+    /// occurrences is kept: `rec:1w*3` becomes `rec:2d*3`.
+    /// Returns true if the task was changed.
+    pub fn set_recurrence(&mut self, rec: Option<utils::Recurrence>) -> bool {
+        match rec {
+            None => self.update_tag_with_value(utils::REC_TAG, ""),
+            Some(r) => {
+                if self.recurrence == rec {
+                    return false;
+                }
+                let value = r.to_string();
+                let value = value.strip_prefix(utils::REC_TAG_FULL).unwrap_or(&value);
+                match self.rec_remaining() {
+                    Some(count) => self.update_tag_with_value(utils::REC_TAG, &format!("{value}*{count}")),
+                    None => self.update_tag_with_value(utils::REC_TAG, value),
+                }
+            }
+        }
+    }
+
+    // With `date_first`, a completed task with completion date gets the
+    // priority after the dates: `x 2023-08-20 2023-08-01 (B) Task`.
+    fn write_header(&self, f: &mut impl std::fmt::Write, date_first: bool) -> std::fmt::Result {
+        let date_first = date_first && self.finished && self.finish_date.is_some();
+        if self.finished {
+            f.write_str("x ")?;
+        }
+        if !date_first && self.priority < utils::NO_PRIORITY {
+            f.write_str(&utils::format_priority(self.priority))?;
+            f.write_str(" ")?;
+        }
+        if let Some(dt) = self.finish_date {
+            f.write_str(&utils::format_date(dt))?;
+            f.write_str(" ")?;
+        }
+        if let Some(dt) = self.create_date {
+            f.write_str(&utils::format_date(dt))?;
+            f.write_str(" ")?;
+        }
+        if date_first && self.priority < utils::NO_PRIORITY {
+            f.write_str(&utils::format_priority(self.priority))?;
+            f.write_str(" ")?;
+        }
+        Ok(())
+    }
+
+    /// Converts the task to a string like `Display` does, but the block of
+    /// `key:value` tags at the end of the subject is sorted alphabetically by
+    /// tag name. Projects, contexts, and tags in the middle of the subject keep
+    /// their positions. Useful to keep the output stable between saves.
+    pub fn to_string_with_tags_sorted(&self) -> String {
+        self.to_string_with_options(TagOrder::Alphabetical, false)
+    }
+
+    /// Converts the task to a string keeping the block of tags at the end
+    /// of the subject in the given order.
+    pub fn to_string_with_tag_order(&self, order: TagOrder) -> String {
+        self.to_string_with_options(order, false)
+    }
+
+    /// Converts the task to a string like `to_string_with_tag_order` does.
+    /// If `date_first` is `true`, a completed task with completion date gets
+    /// its priority after the dates, so the completion date goes right after
+    /// `x`: `x 2023-08-20 (B) Task`. Note: when such line is parsed back, the
+    /// priority becomes a part of the subject, the same way as after
+    /// completion with `CompletionMode::MovePriority`.
+    pub fn to_string_with_options(&self, order: TagOrder, date_first: bool) -> String {
+        let mut res = String::new();
+        let _ = self.write_header(&mut res, date_first);
+        match order {
+            TagOrder::Original => res.push_str(&self.subject),
+            TagOrder::Alphabetical => res.push_str(&sort_trailing_tags(&self.subject)),
+        }
+        res
+    }
+
+    /// Rebuilds projects, contexts, hashtags, tags, due and threshold dates,
+    /// and recurrence from the current subject. Use it after changing the
+    /// field `subject` directly. Relative dates in `due:` and `t:` tags are
+    /// calculated from `base` and replaced with absolute ones, the same way
+    /// `parse` does it. Priority, completion mark, and dates from the header
+    /// are not changed.
+    pub fn resync_from_subject(&mut self, base: NaiveDate) {
+        self.projects = utils::extract_projects(&self.subject);
+        self.contexts = utils::extract_contexts(&self.subject);
+        self.hashtags = utils::extract_hashtags(&self.subject);
+        self.tags = utils::extract_tags(&self.subject);
+        self.due_date = None;
+        self.threshold_date = None;
+        self.recurrence = None;
+        self.parse_special_tags(base);
+    }
+
+    /// Converts the task to a string like `Display` does, but every run of
+    /// spaces in the subject is replaced with a single space, and leading and
+    /// trailing spaces of the subject are removed. The task is not changed.
+    pub fn to_string_compact(&self) -> String {
+        let mut res = String::new();
+        let _ = self.write_header(&mut res, false);
+        res.push_str(utils::collapse_spaces(&self.subject).trim());
+        res
+    }
+
     /// Mark the task completed.
     /// Returns true if the task was changed(e.g., for a completed task the function return false).
     #[deprecated(note = "Please use `complete_with_config` - it has more stable API")]
@@ -381,6 +769,315 @@ impl Task {
         true
     }
 
+    /// Returns the subject of a task completed with `CompletionMode::MovePriority`
+    /// without the leading priority, e.g. `(B) call mother` becomes `call mother`,
+    /// and the priority moved into the subject as a separate value. For
+    /// incomplete tasks and tasks without such priority, the subject is
+    /// returned intact with `None`.
+    pub fn subject_without_priority_text(&self) -> (&str, Option<char>) {
+        if !self.finished {
+            return (&self.subject, None);
+        }
+        match split_moved_priority(&self.subject) {
+            Some((p, rest)) => (rest, Some(utils::priority_to_char(p))),
+            None => (&self.subject, None),
+        }
+    }
+
+    /// Returns the number of projects of the task.
+    pub fn project_count(&self) -> usize {
+        self.projects.len()
+    }
+
+    /// Returns the number of contexts of the task.
+    pub fn context_count(&self) -> usize {
+        self.contexts.len()
+    }
+
+    /// Compares two tasks like `==` does, but skips the fields from `ignored`.
+    /// Ignored tags are skipped both in the tag list and in the subject, so
+    /// `fix car tmr:off spent:60` equals `fix car` when `IgnoredField::Timer`
+    /// is ignored.
+    pub fn equal_ignoring(&self, other: &Task, ignored: &[IgnoredField]) -> bool {
+        if ignored.is_empty() {
+            return self == other;
+        }
+        self.without_fields(ignored) == other.without_fields(ignored)
+    }
+
+    /// Returns a hash of the todo as it is written to a file, so two todos
+    /// that render identically always get the same hash. With `ignore_timer`,
+    /// the timer tags are skipped, and starting or stopping a timer does not
+    /// change the hash. The value does not depend on the process or on the
+    /// Rust version, so it can be stored between runs.
+    pub fn content_hash(&self, ignore_timer: bool) -> u64 {
+        let line =
+            if ignore_timer { self.without_fields(&[IgnoredField::Timer]).to_string() } else { self.to_string() };
+        // FNV-1a
+        line.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3))
+    }
+
+    // Returns a copy of the task with the fields from `ignored` reset.
+    fn without_fields(&self, ignored: &[IgnoredField]) -> Task {
+        let mut task = self.clone();
+        for field in ignored {
+            match field {
+                IgnoredField::Priority => task.priority = utils::NO_PRIORITY,
+                IgnoredField::Finished => task.finished = false,
+                IgnoredField::CreateDate => task.create_date = None,
+                IgnoredField::FinishDate => task.finish_date = None,
+                IgnoredField::Timer => {
+                    for tag in CLEANUP_CLONE_TAGS {
+                        let _ = task.update_tag(tag);
+                    }
+                }
+                IgnoredField::Tag(name) => {
+                    task.update_tag_with_value(name, "");
+                }
+            }
+        }
+        task
+    }
+
+    /// Returns the subject without projects, contexts, hashtags, and tags,
+    /// e.g. `call +family mom @phone due:2020-01-01` becomes `call mom`.
+    /// Words are separated with a single space.
+    pub fn plain_subject(&self) -> String {
+        self.subject.split(' ').filter(|w| !w.is_empty() && !is_metadata_word(w)).collect::<Vec<&str>>().join(" ")
+    }
+
+    /// Returns the number of words in the subject that are not projects,
+    /// contexts, hashtags, or tags.
+    pub fn word_count(&self) -> usize {
+        self.subject.split(' ').filter(|w| !w.is_empty() && !is_metadata_word(w)).count()
+    }
+
+    /// Returns the priority of the task as a letter, e.g. `Some('B')`, or
+    /// `None` if the task has no priority.
+    pub fn priority_char(&self) -> Option<char> {
+        if self.priority < utils::NO_PRIORITY {
+            Some(utils::priority_to_char(self.priority))
+        } else {
+            None
+        }
+    }
+
+    /// Sets the priority of the task from a letter `A` to `Z`. `None` removes
+    /// the priority. Any other character is rejected with an error and the
+    /// priority is not changed.
+    pub fn set_priority_char(&mut self, pri: Option<char>) -> Result<(), terr::TodoError> {
+        match pri {
+            None => self.priority = utils::NO_PRIORITY,
+            Some(c) if c.is_ascii_uppercase() => self.priority = utils::char_to_priority(c),
+            Some(c) => return Err(terr::TodoError::InvalidValue(c.to_string(), "priority".to_string())),
+        }
+        Ok(())
+    }
+
+    /// Sets the creation date of the task. `None` removes the date. A date
+    /// after the existing completion date is rejected with an error and the
+    /// creation date is not changed.
+    /// Returns true if the task was changed.
+    pub fn set_create_date(&mut self, date: Option<NaiveDate>) -> Result<bool, terr::TodoError> {
+        if let (Some(created), Some(finished)) = (date, self.finish_date) {
+            if created > finished {
+                return Err(terr::TodoError::InvalidValue(utils::format_date(created), "creation date".to_string()));
+            }
+        }
+        if self.create_date == date {
+            return Ok(false);
+        }
+        self.create_date = date;
+        Ok(true)
+    }
+
+    /// Returns the note attached to the task with the tag `note:`. The value
+    /// is decoded: `_` becomes a space, and escaped characters (`\_`, `\\`,
+    /// `\n`, and `\t`) are restored. Returns `None` if the task has no note.
+    pub fn note(&self) -> Option<String> {
+        self.tags.get(NOTE_TAG).map(|v| decode_note(v))
+    }
+
+    /// Attaches a note to the task. The note can contain any characters,
+    /// including spaces, colons, and new lines: it is encoded to be a value of
+    /// the tag `note:`. If the task has a note, it is replaced in place. An
+    /// empty note removes the tag.
+    /// Returns true if the task was changed.
+    pub fn set_note(&mut self, note: &str) -> bool {
+        self.update_tag_with_value(NOTE_TAG, &encode_note(note))
+    }
+
+    /// Adds a line to the note attached to the task: the text is appended to
+    /// the existing note after a new line. If the task has no note, it works
+    /// the same way as `set_note`. An empty text does not change the task.
+    /// Returns true if the task was changed.
+    pub fn append_note(&mut self, text: &str) -> bool {
+        if text.is_empty() {
+            return false;
+        }
+        match self.note() {
+            None => self.set_note(text),
+            Some(note) => self.set_note(&format!("{note}\n{text}")),
+        }
+    }
+
+    /// Returns `true` if the task has creation date and it is before `date`.
+    pub fn created_before(&self, date: NaiveDate) -> bool {
+        self.create_date.is_some_and(|d| d < date)
+    }
+
+    /// Returns `true` if the task has creation date and it is after `date`.
+    pub fn created_after(&self, date: NaiveDate) -> bool {
+        self.create_date.is_some_and(|d| d > date)
+    }
+
+    /// Returns `true` if the task has due date and it is before `date`.
+    pub fn due_before(&self, date: NaiveDate) -> bool {
+        self.due_date.is_some_and(|d| d < date)
+    }
+
+    /// Returns `true` if the task has due date and it is after `date`.
+    pub fn due_after(&self, date: NaiveDate) -> bool {
+        self.due_date.is_some_and(|d| d > date)
+    }
+
+    /// Returns `true` if the task is not completed and its due date is before `today`.
+    /// A task without due date is never overdue.
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        !self.finished && self.due_before(today)
+    }
+
+    /// Returns the number of days from `today` to the due date: negative value
+    /// for overdue tasks, `0` for the tasks due today.
+    /// Returns `None` if the task has no due date.
+    pub fn days_until_due(&self, today: NaiveDate) -> Option<i64> {
+        self.due_date.map(|d| (d - today).num_days())
+    }
+
+    /// Returns `true` if the task threshold date is after `today`: the task
+    /// is not actionable yet. A task without threshold date returns `false`.
+    pub fn has_future_threshold(&self, today: NaiveDate) -> bool {
+        self.threshold_date.is_some_and(|d| d > today)
+    }
+
+    /// Returns `true` if the task is not completed and its threshold date is before `today`.
+    /// A task without threshold date is never overdue.
+    pub fn is_threshold_overdue(&self, today: NaiveDate) -> bool {
+        !self.finished && self.threshold_date.is_some_and(|d| d < today)
+    }
+
+    /// Returns the number of days from `today` to the threshold date: negative
+    /// value for passed threshold dates, `0` for today.
+    /// Returns `None` if the task has no threshold date.
+    pub fn days_until_threshold(&self, today: NaiveDate) -> Option<i64> {
+        self.threshold_date.map(|d| (d - today).num_days())
+    }
+
+    /// Returns the number of days between the threshold and due dates
+    /// (`due - threshold`), or `None` if the task does not have both dates.
+    /// A negative value means the task is due before its threshold date.
+    pub fn due_threshold_gap_days(&self) -> Option<i64> {
+        match (self.due_date, self.threshold_date) {
+            (Some(due), Some(thr)) => Some((due - thr).num_days()),
+            _ => None,
+        }
+    }
+
+    /// Returns the date from the tag `until:` - the last date when a recurrent
+    /// task can occur. Returns `None` if the tag is missing or it is not a
+    /// valid absolute date.
+    pub fn rec_until(&self) -> Option<NaiveDate> {
+        let value = self.tags.get(utils::UNTIL_TAG)?;
+        if !value.contains('-') {
+            return None;
+        }
+        utils::parse_date(value, Local::now().date_naive()).ok()
+    }
+
+    /// Returns the date from the tag `modified:` - the date when the task was
+    /// changed last time (see `touch`). Returns `None` if the tag is missing
+    /// or it is not a valid absolute date.
+    pub fn modified_date(&self) -> Option<NaiveDate> {
+        let value = self.tags.get(utils::MODIFIED_TAG)?;
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+    }
+
+    /// Sets the tag `modified:` to `today`. The tag is added if the task does
+    /// not have it, or its value is replaced in place.
+    /// Returns true if the task was changed.
+    pub fn touch(&mut self, today: NaiveDate) -> bool {
+        self.update_tag_with_value(utils::MODIFIED_TAG, &utils::format_date(today))
+    }
+
+    /// Returns how many more times a count-limited recurrent task occurs. The
+    /// count is written after the recurrence: `rec:1w*5` means five more
+    /// occurrences. Every completion decreases the count of the spawned todo,
+    /// and a todo with `*0` does not spawn a new one. If the task has `until:`
+    /// as well, the recurrence stops at whichever limit is hit first.
+    /// Returns `None` if the task has no recurrence or its recurrence has no count.
+    pub fn rec_remaining(&self) -> Option<u32> {
+        self.recurrence?;
+        let value = self.tags.get(utils::REC_TAG)?;
+        let (_, cnt) = value.split_once('*')?;
+        cnt.parse::<u32>().ok()
+    }
+
+    /// Sets the number of remaining occurrences of a recurrent task: the
+    /// count in the `rec:` tag is replaced or added.
+    /// Returns true if the task was changed. A task without recurrence is
+    /// never changed.
+    pub fn set_rec_remaining(&mut self, count: u32) -> bool {
+        if self.recurrence.is_none() {
+            return false;
+        }
+        let value = match self.tags.get(utils::REC_TAG) {
+            None => return false,
+            Some(v) => v.split_once('*').map_or(v.as_str(), |(r, _)| r).to_string(),
+        };
+        self.update_tag_with_value(utils::REC_TAG, &format!("{value}*{count}"))
+    }
+
+    /// Returns how many more times a recurrent task occurs after its current
+    /// due date (or threshold date if the task has no due date), up to and
+    /// including the date from the tag `until:`. The first next date is
+    /// calculated the same way `next_dates` does it when the task is
+    /// completed on `today`. If the recurrence has a count (`rec:1w*5`, see
+    /// `rec_remaining`), the result is not greater than the count: whichever
+    /// limit is hit first stops the recurrence.
+    /// Returns `None` if the task is completed, or it does not have recurrence,
+    /// or it has neither due nor threshold date (such task never recurs, see
+    /// `next_dates`), or it has neither a count nor `until:` tag.
+    pub fn remaining_occurrences(&self, today: NaiveDate) -> Option<u32> {
+        if self.finished {
+            return None;
+        }
+        let rec = self.recurrence?;
+        let due = self.due_date.or(self.threshold_date)?;
+        let limit = self.rec_remaining();
+        let until = match self.rec_until() {
+            Some(until) => until,
+            None => return limit,
+        };
+        let mut next = if rec.strict { rec.next_date(due) } else { rec.next_date(today) };
+        while next < today {
+            let new_next = rec.next_date(next);
+            if new_next <= next {
+                return Some(0);
+            }
+            next = new_next;
+        }
+        let mut count = 0;
+        while next <= until {
+            count += 1;
+            let new_next = rec.next_date(next);
+            if new_next <= next {
+                break;
+            }
+            next = new_next;
+        }
+        Some(limit.map_or(count, |l| count.min(l)))
+    }
+
     /// If the task has both recurrence and due or threshold date, the recurrence and due dates
     /// change so they point to some day in the future. The new values depends on
     /// recurrence strictness: for strict recurrence, the new date is always due+recurrence;
@@ -422,6 +1119,52 @@ impl Task {
         true
     }
 
+    /// Restores the priority of a task completed with `CompletionMode::PriorityToTag`
+    /// from its `pri:` tag after the task has been parsed. The tag is kept, so
+    /// `uncomplete` removes it later as usual. A `pri:` tag which value is not
+    /// a priority letter (e.g., `pri:high`) is treated as a regular tag.
+    /// To do it while parsing, use `parse_with_options` with
+    /// `ParseOptions::restore_priority`.
+    ///
+    /// **Note:** the restored priority is written when the task is converted
+    /// to a string, e.g. `x testb pri:B` becomes `x (B) testb pri:B`.
+    ///
+    /// Returns `true` if the priority was restored. The function does nothing
+    /// for incomplete tasks and tasks that already have a priority.
+    pub fn restore_priority_from_tag(&mut self) -> bool {
+        if !self.finished || self.priority != utils::NO_PRIORITY {
+            return false;
+        }
+        let pri = match self.tags.get(PRIORITY_TAG) {
+            None => return false,
+            Some(pri_s) => utils::str_to_priority(pri_s),
+        };
+        if pri == utils::NO_PRIORITY {
+            return false;
+        }
+        self.priority = pri;
+        true
+    }
+
+    /// Moves the priority from the `pri:` tag of a completed task into the field
+    /// `priority` like `restore_priority_from_tag` does. If `keep_tag` is
+    /// `false`, the tag is removed from the subject, e.g. `x testb pri:B`
+    /// becomes `x (B) testb`. Otherwise, the tag stays and `uncomplete` with
+    /// `CompletionMode::PriorityToTag` removes it later.
+    ///
+    /// Returns `true` if the priority was restored. The function does nothing
+    /// for incomplete tasks, tasks that already have a priority, and tasks
+    /// which `pri:` tag is not a priority letter.
+    pub fn reconcile_priority_tag(&mut self, keep_tag: bool) -> bool {
+        if !self.restore_priority_from_tag() {
+            return false;
+        }
+        if !keep_tag {
+            self.update_tag_with_value(PRIORITY_TAG, "");
+        }
+        true
+    }
+
     /// Remove completion mark from the task.
     /// Returns true if the task was changed(e.g., for a incomplete task the function return false).
     pub fn uncomplete(&mut self, cmpl: CompletionMode) -> bool {
@@ -446,11 +1189,9 @@ impl Task {
                 }
             }
             CompletionMode::MovePriority => {
-                // Check if the subject starts with `(?)`
-                let pri_s = if let Some(idx) = self.subject.find(' ') { &self.subject[..idx] } else { &self.subject };
-                if let Ok(p) = utils::parse_priority(pri_s) {
+                if let Some((p, rest)) = split_moved_priority(&self.subject) {
                     self.priority = p;
-                    self.subject = self.subject[pri_s.len()..].trim_start().to_string();
+                    self.subject = rest.to_string();
                 }
             }
             _ => {}
@@ -460,6 +1201,34 @@ impl Task {
         true
     }
 
+    /// Reverts a completed task to a clean pending state: removes completion
+    /// mark and completion date the same way `uncomplete` does, restores the
+    /// priority according to `cmpl`, and removes the tags that `done` removes
+    /// from the next occurrence of a recurrent task (`tmr:` and `spent:`).
+    ///
+    /// If `cmpl` is `CompletionMode::PriorityToTag` and the task has both a
+    /// priority and a `pri:` tag, the existing priority is kept and the tag
+    /// is removed.
+    ///
+    /// Returns true if the task was changed (e.g., for an incomplete task the function returns false).
+    pub fn strip_completion(&mut self, cmpl: CompletionMode) -> bool {
+        if !self.finished {
+            return false;
+        }
+        let mut cmpl = cmpl;
+        if cmpl == CompletionMode::PriorityToTag && self.priority < utils::NO_PRIORITY {
+            if let Some(v) = self.tags.get(PRIORITY_TAG) {
+                if utils::str_to_priority(v) != utils::NO_PRIORITY {
+                    self.update_tag_with_value(PRIORITY_TAG, "");
+                }
+            }
+            cmpl = CompletionMode::JustMark;
+        }
+        self.uncomplete(cmpl);
+        self.cleanup_cloned_task();
+        true
+    }
+
     /// Replace existing project with a new one. Special cases:
     /// - new is empty: the old project is removed from the task
     /// - old is empty: the new project is appended to the task
@@ -483,6 +1252,28 @@ impl Task {
         }
     }
 
+    /// Adds a new project at the beginning of the subject. Does nothing if
+    /// `new` is empty or the task already has the project.
+    pub fn prepend_project(&mut self, new: &str) {
+        let new = new.strip_prefix('+').unwrap_or(new);
+        if new.is_empty() || self.projects.iter().any(|p| p == new) {
+            return;
+        }
+        self.projects.insert(0, new.to_string());
+        self.subject = if self.subject.is_empty() { format!("+{new}") } else { format!("+{new} {}", self.subject) };
+    }
+
+    /// Adds a new context at the beginning of the subject. Does nothing if
+    /// `new` is empty or the task already has the context.
+    pub fn prepend_context(&mut self, new: &str) {
+        let new = new.strip_prefix('@').unwrap_or(new);
+        if new.is_empty() || self.contexts.iter().any(|c| c == new) {
+            return;
+        }
+        self.contexts.insert(0, new.to_string());
+        self.subject = if self.subject.is_empty() { format!("@{new}") } else { format!("@{new} {}", self.subject) };
+    }
+
     /// Replace existing context with a new one. Special cases:
     /// - new is empty: the old context is removed from the task
     /// - old is empty: the new context is appended to the task
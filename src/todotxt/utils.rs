@@ -10,6 +10,10 @@ pub const REC_TAG: &str = "rec";
 pub const DUE_TAG_FULL: &str = "due:";
 pub const THR_TAG_FULL: &str = "t:";
 pub const REC_TAG_FULL: &str = "rec:";
+/// The tag that defines the last date when a recurrent todo can occur
+pub const UNTIL_TAG: &str = "until";
+/// The tag that keeps the date of the last modification of a todo
+pub const MODIFIED_TAG: &str = "modified";
 
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
 pub enum Period {
@@ -18,6 +22,10 @@ pub enum Period {
     Month,
     Year,
     BusinessDay,
+    /// The last business day of a month
+    BusinessMonth,
+    /// A week that ends on the given day of week (`rec:1w@mon`)
+    WeekDay(Weekday),
 }
 
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
@@ -45,6 +53,18 @@ pub fn days_in_month(y: i32, m: u32) -> u32 {
     }
 }
 
+// Returns the last business day of a month: if the month ends on a weekend,
+// the previous Friday is returned.
+fn last_business_day(y: i32, m: u32) -> Option<NaiveDate> {
+    let last = NaiveDate::from_ymd_opt(y, m, days_in_month(y, m))?;
+    let back = match last.weekday() {
+        Weekday::Sat => 1,
+        Weekday::Sun => 2,
+        _ => 0,
+    };
+    Some(last - Duration::days(back))
+}
+
 // Returns the number of business days between two days.
 // The date range is inclusive.
 pub fn business_days_between(start: NaiveDate, end: NaiveDate) -> i64 {
@@ -242,29 +262,54 @@ pub fn extract_hashtags(s: &str) -> Vec<String> {
     hashtags
 }
 
+/// Replaces every run of consecutive spaces with a single space.
+/// Leading and trailing spaces are collapsed as well but not removed.
+pub fn collapse_spaces(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    let mut prev_space = false;
+    for c in s.chars() {
+        if c == ' ' {
+            if prev_space {
+                continue;
+            }
+            prev_space = true;
+        } else {
+            prev_space = false;
+        }
+        res.push(c);
+    }
+    res
+}
+
 /// Replaces a word with another one. If `new` is empty, it removed the old value.
 /// A word is a group of characters between spaces(start and end of the string are virtual spaces).
+/// The spacing between other words is kept as is. When a word is removed, the spaces
+/// around it collapse into one space, so neighbor words never merge.
 pub fn replace_word(s: &mut String, old: &str, new: &str) {
-    if old == new {
-        return;
-    }
-    if s == old {
-        s.replace_range(.., new);
+    if old == new || old.is_empty() {
         return;
     }
-    if s.starts_with(&format!("{old} ")) {
-        let l = if new.is_empty() { old.len() + 1 } else { old.len() };
-        s.replace_range(..l, new);
-    }
-    if s.ends_with(&format!(" {old}")) {
-        let l = if new.is_empty() { old.len() + 1 } else { old.len() };
-        s.replace_range(s.len() - l.., new);
-    }
-    if new.is_empty() {
-        *s = s.replace(&format!(" {old} "), " ");
-    } else {
-        *s = s.replace(&format!(" {old} "), &format!(" {new} "));
+    let mut words: Vec<&str> = Vec::new();
+    let mut skip_spaces = false;
+    for word in s.split(' ') {
+        if word == old {
+            if new.is_empty() {
+                while words.last() == Some(&"") {
+                    words.pop();
+                }
+                skip_spaces = true;
+            } else {
+                words.push(new);
+            }
+            continue;
+        }
+        if skip_spaces && word.is_empty() {
+            continue;
+        }
+        skip_spaces = false;
+        words.push(word);
     }
+    *s = words.join(" ");
 }
 
 impl Default for Recurrence {
@@ -293,18 +338,81 @@ impl std::fmt::Display for Recurrence {
             Period::Month => f.write_str("m"),
             Period::Year => f.write_str("y"),
             Period::BusinessDay => f.write_str("b"),
+            Period::BusinessMonth => f.write_str("bm"),
+            Period::WeekDay(wd) => f.write_fmt(format_args!("w@{}", wd.to_string().to_lowercase())),
         }
     }
 }
 
 impl Recurrence {
+    /// Creates a recurrence with the given period and number of periods,
+    /// e.g. `from_period(Period::Week, 2, true)` is `rec:+2w`.
+    pub fn from_period(period: Period, count: u8, strict: bool) -> Self {
+        Recurrence { period, count, strict }
+    }
+
+    /// Creates a non-strict recurrence that repeats every `days` days using
+    /// the coarsest period that represents the number of days exactly. Only
+    /// weeks are exact: months and years vary in length, so `30` and `365`
+    /// stay days. E.g., `7` gives `rec:1w`, `14` gives `rec:2w`, and `10`
+    /// gives `rec:10d`.
+    ///
+    /// Returns `None` if `days` is `0` or the number of periods does not
+    /// fit in a recurrence count (more than 255 weeks or days).
+    pub fn from_days(days: u32) -> Option<Self> {
+        if days == 0 {
+            return None;
+        }
+        if days % 7 == 0 {
+            if let Ok(weeks) = u8::try_from(days / 7) {
+                return Some(Recurrence::from_period(Period::Week, weeks, false));
+            }
+        }
+        let count = u8::try_from(days).ok()?;
+        Some(Recurrence::from_period(Period::Day, count, false))
+    }
+
+    /// Parses a recurrence, e.g. `1w`, `+2m`, or `rec:1w@mon`. A count-limited
+    /// recurrence `1w*5` is accepted too: the number of remaining occurrences
+    /// is not a part of `Recurrence`, it is kept in the task's `rec:` tag (see
+    /// `Task::rec_remaining`).
     pub fn parse(s: &str) -> Result<Self, String> {
         let s = if let Some(stripped) = s.strip_prefix(REC_TAG_FULL) { stripped } else { s };
+        let s = match s.split_once('*') {
+            None => s,
+            Some((r, cnt)) => {
+                if cnt.parse::<u32>().is_err() {
+                    return Err(format!("invalid recurrence count '{cnt}'"));
+                }
+                r
+            }
+        };
         let mut rec = Recurrence::default();
-        if s.ends_with('d') {
+        let mut weekday = None;
+        let s = match s.split_once('@') {
+            None => s,
+            Some((r, wd)) => {
+                if !r.ends_with('w') {
+                    return Err(format!("weekday is allowed only for weekly recurrence '{s}'"));
+                }
+                match wd.parse::<Weekday>() {
+                    Ok(w) if wd.len() == 3 => weekday = Some(w),
+                    _ => return Err(format!("invalid weekday '{wd}'")),
+                }
+                r
+            }
+        };
+        let mut suffix_len = 1;
+        if s.ends_with("bm") {
+            rec.period = Period::BusinessMonth;
+            suffix_len = 2;
+        } else if s.ends_with('d') {
             rec.period = Period::Day;
         } else if s.ends_with('w') {
-            rec.period = Period::Week;
+            rec.period = match weekday {
+                None => Period::Week,
+                Some(wd) => Period::WeekDay(wd),
+            };
         } else if s.ends_with('m') {
             rec.period = Period::Month;
         } else if s.ends_with('y') {
@@ -317,7 +425,7 @@ impl Recurrence {
         if s.starts_with('+') {
             rec.strict = true;
         }
-        let num = s[..s.len() - 1].parse::<u8>();
+        let num = s[..s.len() - suffix_len].parse::<u8>();
         match num {
             Err(_) => Err(format!("invalid recurrence '{s}'")),
             Ok(n) => {
@@ -327,9 +435,94 @@ impl Recurrence {
         }
     }
 
+    /// Returns the recurrence in plain English, e.g. `every 2 weeks`,
+    /// `every month (strict)`, or `every 3 business days`.
+    pub fn describe(&self) -> String {
+        let (one, many) = match self.period {
+            Period::Day => ("day", "days"),
+            Period::Week | Period::WeekDay(_) => ("week", "weeks"),
+            Period::Month => ("month", "months"),
+            Period::Year => ("year", "years"),
+            Period::BusinessDay => ("business day", "business days"),
+            Period::BusinessMonth => ("month on the last business day", "months on the last business day"),
+        };
+        let mut res = if self.count == 1 { format!("every {one}") } else { format!("every {} {many}", self.count) };
+        if let Period::WeekDay(wd) = self.period {
+            let day = match wd {
+                Weekday::Mon => "Monday",
+                Weekday::Tue => "Tuesday",
+                Weekday::Wed => "Wednesday",
+                Weekday::Thu => "Thursday",
+                Weekday::Fri => "Friday",
+                Weekday::Sat => "Saturday",
+                Weekday::Sun => "Sunday",
+            };
+            res.push_str(" on ");
+            res.push_str(day);
+        }
+        if self.strict {
+            res.push_str(" (strict)");
+        }
+        res
+    }
+
+    /// Returns the "base" date decreased by a recurrence value. It is the
+    /// reverse of `next_date`: months and years follow the same rules for the
+    /// last day of a month, business days skip weekends, and weekly recurrence
+    /// with a weekday snaps to the previous such weekday.
+    pub fn prev_date(&self, base: chrono::NaiveDate) -> chrono::NaiveDate {
+        let last = base.day() == days_in_month(base.year(), base.month());
+        match self.period {
+            Period::Day => base - Duration::days(self.count as i64),
+            Period::BusinessDay => {
+                let mut dt = base;
+                for _ in 0..self.count {
+                    dt -= Duration::days(1);
+                    while dt.weekday() == Weekday::Sat || dt.weekday() == Weekday::Sun {
+                        dt -= Duration::days(1);
+                    }
+                }
+                dt
+            }
+            Period::Week => base - Duration::weeks(self.count as i64),
+            Period::WeekDay(wd) => {
+                let diff = (base.weekday().num_days_from_monday() + 7 - wd.num_days_from_monday()) % 7;
+                let diff = if diff == 0 { 7 } else { diff as i64 };
+                base - Duration::days(diff) - Duration::weeks(self.count.saturating_sub(1) as i64)
+            }
+            Period::Month | Period::BusinessMonth => {
+                let months = base.year() * 12 + base.month() as i32 - 1 - self.count as i32;
+                let (y, m) = (months.div_euclid(12), months.rem_euclid(12) as u32 + 1);
+                if self.period == Period::BusinessMonth {
+                    return last_business_day(y, m).unwrap_or(base);
+                }
+                let mut d = base.day();
+                let mx = days_in_month(y, m);
+                if (last && mx != d) || (mx < d) {
+                    d = mx;
+                }
+                NaiveDate::from_ymd_opt(y, m, d).unwrap_or(base)
+            }
+            Period::Year => {
+                let y = base.year() - self.count as i32;
+                let m = base.month();
+                let mut d = base.day();
+                let mx = days_in_month(y, m);
+                if (last && mx != d) || (mx < d) {
+                    d = mx;
+                }
+                NaiveDate::from_ymd_opt(y, m, d).unwrap_or(base)
+            }
+        }
+    }
+
     /// Returns the "base" date increased by a recurrence value.
     /// Special case: when recurrence value is the number of months or years, and the "base" date
     /// is the last day of the month, the next date is always the end of a month.
+    /// For a weekly recurrence with a weekday, the next date is the first such weekday after
+    /// the "base" date (if the "base" date is that weekday, a full week later), plus `count - 1` weeks.
+    /// For `BusinessMonth` period, the next date is the last business day of the month
+    /// that is `count` months after the "base" date's month.
     pub fn next_date(&self, base: chrono::NaiveDate) -> chrono::NaiveDate {
         let last = base.day() == days_in_month(base.year(), base.month());
         match self.period {
@@ -341,6 +534,11 @@ impl Recurrence {
                 base + Duration::days(self.count as i64 + bd)
             }
             Period::Week => base + Duration::weeks(self.count as i64),
+            Period::WeekDay(wd) => {
+                let diff = (wd.num_days_from_monday() + 7 - base.weekday().num_days_from_monday()) % 7;
+                let diff = if diff == 0 { 7 } else { diff as i64 };
+                base + Duration::days(diff) + Duration::weeks(self.count.saturating_sub(1) as i64)
+            }
             Period::Month => {
                 let mut y = base.year();
                 let mut m = base.month() + self.count as u32;
@@ -359,6 +557,15 @@ impl Recurrence {
                     base
                 }
             }
+            Period::BusinessMonth => {
+                let mut y = base.year();
+                let mut m = base.month() + self.count as u32;
+                if m > 12 {
+                    y += ((m - 1) / 12) as i32;
+                    m = (m - 1) % 12 + 1;
+                }
+                last_business_day(y, m).unwrap_or(base)
+            }
             Period::Year => {
                 let y = base.year() + self.count as i32;
                 let m = base.month();
@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use std::collections::HashMap;
 
 use crate::timer;
 use crate::todo;
@@ -11,14 +12,35 @@ use crate::todotxt;
 pub struct Conf {
     /// comma separated list of field to sort by. Supported field names:
     /// * `pri` or `prioroty` - sort by priority (without priority are the last ones);
+    /// * `pri-none-first` or `priority-none-first` - sort by priority, but todos without priority go first;
     /// * `due` - sor by due date (todos that do not have due date are at the bottom);
     /// * `thr` - sor by threshold date (todos that do not have threshold date are at the bottom);
     /// * `completed` or `finished` - sort by completion date (incomplete ones are at the bottom);
     /// * `created` or `create` - sort by creation date;
     /// * `subject`, `subj` or `text` - sort by todo's subjects;
-    /// * `done` - order: incomplete, recurrent, and done todos;
+    /// * `done` - order: incomplete, active with running timer, recurrent, and done todos. The rank of a todo:
+    ///
+    ///   | completed | timer running | recurrent | rank |
+    ///   |-----------|---------------|-----------|------|
+    ///   | no        | no            | no        | 0    |
+    ///   | no        | yes           | any       | 1    |
+    ///   | no        | no            | yes       | 2    |
+    ///   | yes       | any           | no        | 3    |
+    ///   | yes       | any           | yes       | 3    |
+    ///
+    ///   A running timer matters only for active todos: a completed todo
+    ///   with a stale `tmr:` tag is ranked as completed.
+    ///
+    ///   Todos with the same rank keep their order. With `rev` the
+    ///   whole sorted list is reversed: completed todos go first, and todos
+    ///   with the same rank are in reversed order as well;
     /// * `project` or `proj` - sort by project names, if todos have more than one project they are compared in order of appearance and shorter list of projects goes first;
     /// * `context` or `ctx` - sort by contexts, if todos have more than one context they are compared in order of appearance and shorter list of contexts goes first;
+    /// * `projcount` - sort by the number of projects, todos with fewer projects go first;
+    /// * `ctxcount` - sort by the number of contexts, todos with fewer contexts go first;
+    /// * `tag:<name>` - sort by the value of the tag `<name>`: todos with the tag go first ordered by the tag value, then todos without the tag keep their order at the bottom. If all values of the tag are integer numbers, they are compared as numbers, otherwise as case-insensitive strings (so dates in format `YYYY-MM-DD` are sorted by date);
+    /// * `duration` - sort by the number of days between creation and completion dates, shortest first (todos without any of the dates are at the bottom);
+    /// * `age` - the same as `duration`, but for incomplete todos the current date is used instead of completion date;
     pub fields: Option<String>,
     /// reverse the list after sorting
     pub rev: bool,
@@ -33,7 +55,9 @@ pub(crate) fn cmp_opt_dates(d1: Option<chrono::NaiveDate>, d2: Option<chrono::Na
     }
 }
 
-pub(crate) fn equal_opt_rec(r1: &Option<todotxt::Recurrence>, r2: &Option<todotxt::Recurrence>) -> bool {
+/// Returns true if both recurrences are the same, including their strictness:
+/// `rec:1m` and `rec:+1m` are different recurrences.
+pub fn equal_opt_rec(r1: &Option<todotxt::Recurrence>, r2: &Option<todotxt::Recurrence>) -> bool {
     match (&r1, &r2) {
         (None, None) => true,
         (Some(_), None) | (None, Some(_)) => false,
@@ -41,6 +65,81 @@ pub(crate) fn equal_opt_rec(r1: &Option<todotxt::Recurrence>, r2: &Option<todotx
     }
 }
 
+/// Returns true if both recurrences have the same cadence, regardless of
+/// their strictness: `rec:1m` and `rec:+1m` are equal recurrences.
+pub fn recurrence_equal_ignoring_strict(r1: &Option<todotxt::Recurrence>, r2: &Option<todotxt::Recurrence>) -> bool {
+    match (&r1, &r2) {
+        (None, None) => true,
+        (Some(_), None) | (None, Some(_)) => false,
+        (Some(v1), Some(v2)) => v1.period == v2.period && v1.count == v2.count,
+    }
+}
+
+// Returns the number of days between creation and completion dates of a todo.
+// If `today` is defined, it is used for incomplete todos instead of completion date.
+fn days_to_finish(task: &todotxt::Task, today: Option<chrono::NaiveDate>) -> Option<i64> {
+    let created = task.create_date?;
+    let finished = if task.finished { task.finish_date } else { today }?;
+    Some((finished - created).num_days())
+}
+
+fn cmp_opt_days(d1: Option<i64>, d2: Option<i64>) -> Ordering {
+    match (d1, d2) {
+        (None, None) => Ordering::Equal,
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(v1), Some(v2)) => v1.cmp(&v2),
+    }
+}
+
+// Rank of a todo for sorting by field `done`: active non-recurrent todos go
+// first, then active todos with running timer, active recurrent ones, and
+// completed ones are the last.
+fn done_rank(task: &todotxt::Task) -> u8 {
+    if task.finished {
+        3
+    } else if timer::is_timer_on(task) {
+        1
+    } else if task.recurrence.is_some() {
+        2
+    } else {
+        0
+    }
+}
+
+// Compares priorities, optionally putting todos without priority first
+fn cmp_priorities(p1: u8, p2: u8, no_priority_first: bool) -> Ordering {
+    if no_priority_first {
+        let none1 = p1 >= todotxt::NO_PRIORITY;
+        let none2 = p2 >= todotxt::NO_PRIORITY;
+        if none1 != none2 {
+            return none2.cmp(&none1);
+        }
+    }
+    p1.cmp(&p2)
+}
+
+// Returns the value of a tag, the tag name is case-insensitive
+fn tag_value<'a>(task: &'a todotxt::Task, name: &str) -> Option<&'a str> {
+    task.tags.iter().find(|(k, _)| k.to_lowercase() == name).map(|(_, v)| v.as_str())
+}
+
+fn cmp_tag_values(v1: Option<&str>, v2: Option<&str>, numeric: bool) -> Ordering {
+    match (v1, v2) {
+        (None, None) => Ordering::Equal,
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(v1), Some(v2)) => {
+            if numeric {
+                if let (Ok(n1), Ok(n2)) = (v1.parse::<i64>(), v2.parse::<i64>()) {
+                    return n1.cmp(&n2);
+                }
+            }
+            v1.to_lowercase().cmp(&v2.to_lowercase())
+        }
+    }
+}
+
 fn cmp_opt_arrays(a1: &[String], a2: &[String]) -> Ordering {
     if a1.is_empty() && !a2.is_empty() {
         return Ordering::Greater;
@@ -93,7 +192,33 @@ pub fn sort(ids: &mut todo::IDVec, todos: &todo::TaskSlice, c: &Conf) {
         }
     };
 
+    // `tag` is followed by the name of a tag to sort by its value
+    let mut parsed: Vec<(&str, &str)> = Vec::new();
+    let mut field_iter = fields.iter();
+    while let Some(f) = field_iter.next() {
+        if *f == "tag" {
+            if let Some(name) = field_iter.next() {
+                parsed.push((f, name));
+            }
+        } else {
+            parsed.push((f, ""));
+        }
+    }
+    let numeric_tags: HashMap<&str, bool> = parsed
+        .iter()
+        .filter(|(f, _)| *f == "tag")
+        .map(|(_, name)| {
+            let numeric = ids
+                .iter()
+                .filter_map(|id| todos.get(*id).and_then(|t| tag_value(t, name)))
+                .all(|v| v.parse::<i64>().is_ok());
+            (*name, numeric)
+        })
+        .collect();
+    let fields = parsed;
+
     if !fields.is_empty() {
+        let today = chrono::Local::now().date_naive();
         ids.sort_by(|a, b| {
             if *a >= todos.len() && *b >= todos.len() {
                 return Ordering::Equal;
@@ -104,37 +229,30 @@ pub fn sort(ids: &mut todo::IDVec, todos: &todo::TaskSlice, c: &Conf) {
             }
 
             let mut res: Ordering = Ordering::Equal;
-            for f in &fields {
+            for (f, tag) in &fields {
                 res = match *f {
-                    "pri" | "priority" => todos[*a].priority.cmp(&todos[*b].priority),
+                    "pri" | "priority" => cmp_priorities(todos[*a].priority, todos[*b].priority, false),
+                    "pri-none-first" | "priority-none-first" => {
+                        cmp_priorities(todos[*a].priority, todos[*b].priority, true)
+                    }
                     "due" => cmp_opt_dates(todos[*a].due_date, todos[*b].due_date),
                     "thr" => cmp_opt_dates(todos[*a].threshold_date, todos[*b].threshold_date),
                     "completed" | "finished" => cmp_opt_dates(todos[*a].finish_date, todos[*b].finish_date),
                     "created" | "create" => cmp_opt_dates(todos[*a].create_date, todos[*b].create_date),
                     "subject" | "text" | "subj" => todos[*a].subject.cmp(&todos[*b].subject),
-                    "done" => {
-                        let f1 = if timer::is_timer_on(&todos[*a]) {
-                            1
-                        } else if todos[*a].recurrence.is_some() {
-                            2
-                        } else if todos[*a].finished {
-                            3
-                        } else {
-                            0
-                        };
-                        let f2 = if timer::is_timer_on(&todos[*b]) {
-                            1
-                        } else if todos[*b].recurrence.is_some() {
-                            2
-                        } else if todos[*b].finished {
-                            3
-                        } else {
-                            0
-                        };
-                        f1.cmp(&f2)
-                    }
+                    "done" => done_rank(&todos[*a]).cmp(&done_rank(&todos[*b])),
                     "proj" | "project" => cmp_opt_arrays(&todos[*a].projects, &todos[*b].projects),
                     "ctx" | "context" => cmp_opt_arrays(&todos[*a].contexts, &todos[*b].contexts),
+                    "projcount" => todos[*a].project_count().cmp(&todos[*b].project_count()),
+                    "ctxcount" => todos[*a].context_count().cmp(&todos[*b].context_count()),
+                    "duration" => cmp_opt_days(days_to_finish(&todos[*a], None), days_to_finish(&todos[*b], None)),
+                    "age" => {
+                        cmp_opt_days(days_to_finish(&todos[*a], Some(today)), days_to_finish(&todos[*b], Some(today)))
+                    }
+                    "tag" => {
+                        let numeric = numeric_tags.get(tag).copied().unwrap_or(false);
+                        cmp_tag_values(tag_value(&todos[*a], tag), tag_value(&todos[*b], tag), numeric)
+                    }
                     "active" => {
                         let a_act = timer::is_timer_on(&todos[*a]);
                         let b_act = timer::is_timer_on(&todos[*b]);
@@ -1,4 +1,3 @@
-use chrono;
 use todo_lib::tfilter::TodoStatus;
 use todo_lib::{tfilter, todo, todotxt, tsort};
 
@@ -36,10 +35,8 @@ fn init_tasks_with_empty() -> todo::TaskVec {
 #[test]
 fn one_item() {
     let t = init_tasks();
-    let mut cflt = tfilter::Conf::default();
-
     // invalid ranges
-    cflt.range = tfilter::ItemRange::One(t.len());
+    let mut cflt = tfilter::Conf { range: tfilter::ItemRange::One(t.len()), ..Default::default() };
     let ids = tfilter::filter(&t, &cflt);
     assert_eq!(ids.len(), 0);
 
@@ -51,10 +48,8 @@ fn one_item() {
 #[test]
 fn item_range() {
     let t = init_tasks();
-    let mut cflt = tfilter::Conf::default();
-
     // both ends are out of range
-    cflt.range = tfilter::ItemRange::Range(t.len(), t.len() + 5);
+    let mut cflt = tfilter::Conf { range: tfilter::ItemRange::Range(t.len(), t.len() + 5), ..Default::default() };
     let ids = tfilter::filter(&t, &cflt);
     assert_eq!(ids.len(), 0);
 
@@ -73,10 +68,8 @@ fn item_range() {
 #[test]
 fn item_status() {
     let t = init_tasks();
-    let mut cflt = tfilter::Conf::default();
-
     // one incomplete
-    cflt.range = tfilter::ItemRange::One(0);
+    let mut cflt = tfilter::Conf { range: tfilter::ItemRange::One(0), ..Default::default() };
     let ids = tfilter::filter(&t, &cflt);
     assert_eq!(ids, vec![0]);
 
@@ -102,11 +95,8 @@ fn item_status() {
 #[test]
 fn item_regex() {
     let t = init_tasks();
-    let mut cflt = tfilter::Conf::default();
-
     // all with 'car' anywhere
-    cflt.all = tfilter::TodoStatus::All;
-    cflt.regex = Some("car".to_owned());
+    let mut cflt = tfilter::Conf { all: tfilter::TodoStatus::All, regex: Some("car".to_owned()), ..Default::default() };
     let ids = tfilter::filter(&t, &cflt);
     assert_eq!(ids, vec![1, 2]);
     cflt.all = tfilter::TodoStatus::Active;
@@ -190,21 +180,19 @@ fn item_contexts() {
 #[test]
 fn item_priority() {
     let t = init_tasks();
-    let mut cflt = tfilter::Conf::default();
-    cflt.all = tfilter::TodoStatus::All;
-
     // only B priority
-    cflt.pri = Some(tfilter::Priority { value: 'b' as u8 - 'a' as u8, span: tfilter::ValueSpan::Equal });
+    let mut cflt = tfilter::Conf { all: tfilter::TodoStatus::All, ..Default::default() };
+    cflt.pri = Some(tfilter::Priority { value: b'b' - b'a', span: tfilter::ValueSpan::Equal });
     let ids = tfilter::filter(&t, &cflt);
     assert_eq!(ids, vec![2]);
 
     // B priority and higher
-    cflt.pri = Some(tfilter::Priority { value: 'b' as u8 - 'a' as u8, span: tfilter::ValueSpan::Higher });
+    cflt.pri = Some(tfilter::Priority { value: b'b' - b'a', span: tfilter::ValueSpan::Higher });
     let ids = tfilter::filter(&t, &cflt);
     assert_eq!(ids, vec![2, 3]);
 
     //  B priority and lower
-    cflt.pri = Some(tfilter::Priority { value: 'b' as u8 - 'a' as u8, span: tfilter::ValueSpan::Lower });
+    cflt.pri = Some(tfilter::Priority { value: b'b' - b'a', span: tfilter::ValueSpan::Lower });
     let ids = tfilter::filter(&t, &cflt);
     assert_eq!(ids, vec![0, 1, 2, 4, 5]);
 
@@ -222,10 +210,8 @@ fn item_priority() {
 #[test]
 fn item_recurrence() {
     let t = init_tasks();
-    let mut cflt = tfilter::Conf::default();
-    cflt.all = tfilter::TodoStatus::All;
-
     // with recurrence
+    let mut cflt = tfilter::Conf { all: tfilter::TodoStatus::All, ..Default::default() };
     cflt.rec = Some(tfilter::Recurrence { span: tfilter::ValueSpan::Any });
     let ids = tfilter::filter(&t, &cflt);
     assert_eq!(ids, vec![3]);
@@ -236,14 +222,30 @@ fn item_recurrence() {
     assert_eq!(ids, vec![0, 1, 2, 4, 5]);
 }
 
+#[test]
+fn item_recurrence_cadence() {
+    let now = chrono::Local::now().date_naive();
+    let t: todo::TaskVec = vec![
+        todotxt::Task::parse("pay rent rec:1m", now),
+        todotxt::Task::parse("pay taxes rec:+1m", now),
+        todotxt::Task::parse("call mother rec:1w", now),
+        todotxt::Task::parse("pay bills rec:2m", now),
+        todotxt::Task::parse("buy milk", now),
+    ];
+    let mut cflt = tfilter::Conf { rec_cadence: Some("1m".parse().unwrap()), ..Default::default() };
+    assert_eq!(tfilter::filter(&t, &cflt), vec![0, 1]);
+    cflt.rec_cadence = Some("+1m".parse().unwrap());
+    assert_eq!(tfilter::filter(&t, &cflt), vec![0, 1]);
+    cflt.rec_cadence = Some("1w".parse().unwrap());
+    assert_eq!(tfilter::filter(&t, &cflt), vec![2]);
+}
+
 #[test]
 fn item_due() {
     let t = init_tasks();
 
-    let mut cflt = tfilter::Conf::default();
-    cflt.all = tfilter::TodoStatus::All;
-
     // with due
+    let mut cflt = tfilter::Conf { all: tfilter::TodoStatus::All, ..Default::default() };
     cflt.due = Some(tfilter::DateRange { span: tfilter::ValueSpan::Any, days: Default::default() });
     let ids = tfilter::filter(&t, &cflt);
     assert_eq!(ids, vec![2, 3, 4, 5]);
@@ -263,10 +265,12 @@ fn item_due() {
 fn item_threshold() {
     let t = init_tasks();
 
-    let mut cflt = tfilter::Conf::default();
+    let mut cflt = tfilter::Conf {
+        thr: Some(tfilter::DateRange { span: tfilter::ValueSpan::Any, days: Default::default() }),
+        ..Default::default()
+    };
 
     // with thr
-    cflt.thr = Some(tfilter::DateRange { span: tfilter::ValueSpan::Any, days: Default::default() });
     let ids = tfilter::filter(&t, &cflt);
     assert_eq!(ids, vec![2]);
 
@@ -324,9 +328,629 @@ fn empty_tasks() {
         Test { inc: TodoStatus::All, res: vec![0, 1, 2, 3] },
     ];
     for (idx, test) in tests.iter().enumerate() {
-        let mut cflt = tfilter::Conf::default();
-        cflt.all = test.inc;
+        let cflt = tfilter::Conf { all: test.inc, ..Default::default() };
         let ids = tfilter::filter(&t, &cflt);
         assert_eq!(ids, test.res, "{idx}. {ids:?} != {:?}", test.res);
     }
 }
+
+#[test]
+fn item_due_equals_threshold() {
+    let mut t = init_tasks();
+    let now = chrono::Local::now().date_naive();
+    t.push(todotxt::Task::parse("pay rent due:2018-11-01 t:2018-11-01", now));
+    t.push(todotxt::Task::parse("pay taxes due:2018-11-02 t:2018-11-01", now));
+
+    let mut cflt =
+        tfilter::Conf { all: tfilter::TodoStatus::All, due_equals_threshold: Some(true), ..Default::default() };
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![6]);
+
+    cflt.due_equals_threshold = Some(false);
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 1, 2, 3, 4, 5, 7]);
+}
+
+#[test]
+fn single_task_matches() {
+    let t = init_tasks();
+    let today = chrono::NaiveDate::from_ymd_opt(2018, 11, 10).unwrap();
+    let matching = |c: &tfilter::Conf| -> todo::IDVec {
+        t.iter().enumerate().filter(|(_, task)| tfilter::matches(task, c, today)).map(|(i, _)| i).collect()
+    };
+
+    let cflt = tfilter::Conf::default();
+    assert_eq!(matching(&cflt), vec![0, 3, 4, 5]);
+
+    let cflt = tfilter::Conf { all: TodoStatus::Done, ..Default::default() };
+    assert_eq!(matching(&cflt), vec![1]);
+
+    let cflt = tfilter::Conf { regex: Some("car".to_string()), all: TodoStatus::All, ..Default::default() };
+    assert_eq!(matching(&cflt), vec![1, 2]);
+
+    let mut cflt = tfilter::Conf { all: TodoStatus::All, ..Default::default() };
+    cflt.include.projects = vec!["family*".to_string()];
+    assert_eq!(matching(&cflt), vec![0, 3, 4, 5]);
+
+    // due dates are compared with the given date, not with the current one
+    let mut cflt = tfilter::Conf {
+        due: Some(tfilter::DateRange {
+            span: tfilter::ValueSpan::Range,
+            days: tfilter::ValueRange { low: 0, high: 10 },
+        }),
+        ..Default::default()
+    };
+    assert_eq!(matching(&cflt), vec![3, 4]);
+
+    cflt.due =
+        Some(tfilter::DateRange { span: tfilter::ValueSpan::Lower, days: tfilter::ValueRange { low: 1, high: 0 } });
+    assert_eq!(matching(&cflt), vec![3]);
+
+    // future thresholds are hidden by default
+    let mut cflt = tfilter::Conf::default();
+    cflt.include.projects = vec!["car".to_string()];
+    assert!(!tfilter::matches(&t[2], &cflt, today));
+    let later = chrono::NaiveDate::from_ymd_opt(2019, 1, 2).unwrap();
+    assert!(tfilter::matches(&t[2], &cflt, later));
+
+    // `range` is ignored when checking a single todo
+    let cflt = tfilter::Conf { range: tfilter::ItemRange::One(3), ..Default::default() };
+    assert!(tfilter::matches(&t[0], &cflt, today));
+}
+
+#[test]
+fn item_scheduled() {
+    let now = chrono::Local::now().date_naive();
+    let t: todo::TaskVec = vec![
+        todotxt::Task::parse("no dates at all", now),
+        todotxt::Task::parse("only due due:2018-11-01", now),
+        todotxt::Task::parse("only threshold t:2018-11-01", now),
+        todotxt::Task::parse("both dates due:2018-11-02 t:2018-11-01", now),
+        todotxt::Task::parse("2018-10-01 only creation date", now),
+        todotxt::Task::parse("x 2018-10-02 2018-10-01 completed without dates", now),
+    ];
+
+    let mut cflt = tfilter::Conf { all: tfilter::TodoStatus::All, scheduled: Some(true), ..Default::default() };
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![1, 2, 3]);
+
+    cflt.scheduled = Some(false);
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 4, 5]);
+
+    cflt.scheduled = None;
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn item_priority_range() {
+    let t = init_tasks();
+    // A through B
+    let mut cflt = tfilter::Conf { all: tfilter::TodoStatus::All, ..Default::default() };
+    cflt.pri_range = Some(tfilter::PriorityRange { low: 0, high: 1, include_none: false });
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![2, 3]);
+
+    // B through C
+    cflt.pri_range = Some(tfilter::PriorityRange { low: 1, high: 2, include_none: false });
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![1, 2]);
+
+    // reversed bounds: C through A
+    cflt.pri_range = Some(tfilter::PriorityRange { low: 2, high: 0, include_none: false });
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![1, 2, 3]);
+
+    // D through Z
+    cflt.pri_range = Some(tfilter::PriorityRange { low: 3, high: 25, include_none: false });
+    let ids = tfilter::filter(&t, &cflt);
+    assert!(ids.is_empty());
+
+    // B through C, and todos without priority
+    cflt.pri_range = Some(tfilter::PriorityRange { low: 1, high: 2, include_none: true });
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 1, 2, 4, 5]);
+
+    // D through Z, and todos without priority
+    cflt.pri_range = Some(tfilter::PriorityRange { low: 3, high: 25, include_none: true });
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 4, 5]);
+}
+
+#[test]
+fn item_tag_values() {
+    let now = chrono::Local::now().date_naive();
+    let t: todo::TaskVec = vec![
+        todotxt::Task::parse("fix login id:PROJ-12", now),
+        todotxt::Task::parse("fix logout id:proj-13 area:web", now),
+        todotxt::Task::parse("write docs id:DOC-1", now),
+        todotxt::Task::parse("no tags at all", now),
+        todotxt::Task::parse("empty value id:", now),
+        todotxt::Task::parse("other tag area:PROJ-1", now),
+    ];
+
+    struct Test {
+        inc: Vec<&'static str>,
+        exc: Vec<&'static str>,
+        res: todo::IDVec,
+    }
+    let tests: Vec<Test> = vec![
+        Test { inc: vec!["id:PROJ-*"], exc: Vec::new(), res: vec![0, 1] },
+        Test { inc: vec!["id:PROJ-12"], exc: Vec::new(), res: vec![0] },
+        Test { inc: vec!["id:*-1"], exc: Vec::new(), res: vec![2] },
+        Test { inc: vec!["id:BUG-*"], exc: Vec::new(), res: vec![] },
+        Test { inc: vec!["id"], exc: Vec::new(), res: vec![0, 1, 2] },
+        Test { inc: vec!["id:"], exc: Vec::new(), res: vec![0, 1, 2] },
+        Test { inc: vec!["*:PROJ-*"], exc: Vec::new(), res: vec![0, 1, 5] },
+        Test { inc: vec!["id:*"], exc: vec!["id:doc*"], res: vec![0, 1] },
+        Test { inc: Vec::new(), exc: vec!["id:PROJ-*"], res: vec![2, 3, 4, 5] },
+        Test { inc: vec!["none"], exc: Vec::new(), res: vec![3, 4] },
+    ];
+
+    for (idx, test) in tests.iter().enumerate() {
+        let mut cflt = tfilter::Conf::default();
+        cflt.include.tags = test.inc.iter().map(|s| s.to_string()).collect();
+        cflt.exclude.tags = test.exc.iter().map(|s| s.to_string()).collect();
+        let ids = tfilter::filter(&t, &cflt);
+        assert_eq!(ids, test.res, "{}. {:?} != {:?}", idx, ids, test.res);
+    }
+}
+
+#[test]
+fn item_spent_time() {
+    let now = chrono::Local::now().date_naive();
+    let started = chrono::Utc::now().timestamp() - 3000;
+    let t: todo::TaskVec = vec![
+        todotxt::Task::parse("no time spent", now),
+        todotxt::Task::parse("long task spent:7200 tmr:off", now),
+        todotxt::Task::parse("short task spent:600", now),
+        todotxt::Task::parse(&format!("running task spent:1200 tmr:{started}"), now),
+    ];
+
+    let mut cflt = tfilter::Conf {
+        tmr: Some(tfilter::Timer { span: tfilter::ValueSpan::Higher, value: 3600, ..Default::default() }),
+        ..Default::default()
+    };
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![1, 3]);
+
+    cflt.tmr = Some(tfilter::Timer { span: tfilter::ValueSpan::Lower, value: 600, ..Default::default() });
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 2]);
+
+    cflt.tmr = Some(tfilter::Timer {
+        span: tfilter::ValueSpan::Range,
+        range: tfilter::ValueRange { low: 600, high: 5000 },
+        ..Default::default()
+    });
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![2, 3]);
+
+    cflt.tmr = Some(tfilter::Timer { span: tfilter::ValueSpan::Active, ..Default::default() });
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![3]);
+}
+
+#[test]
+fn item_long_running_timer() {
+    let now = chrono::Local::now().date_naive();
+    let long_ago = chrono::Utc::now().timestamp() - 6 * 3600;
+    let recent = chrono::Utc::now().timestamp() - 600;
+    let t: todo::TaskVec = vec![
+        todotxt::Task::parse("no timer", now),
+        todotxt::Task::parse(&format!("forgotten timer tmr:{long_ago}"), now),
+        todotxt::Task::parse(&format!("fresh timer spent:7200 tmr:{recent}"), now),
+        todotxt::Task::parse("stopped timer spent:36000 tmr:off", now),
+    ];
+
+    let mut cflt = tfilter::Conf { tmr_running_hours: Some(1), ..Default::default() };
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![1]);
+
+    cflt.tmr_running_hours = Some(0);
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![1, 2]);
+
+    cflt.tmr_running_hours = Some(7);
+    let ids = tfilter::filter(&t, &cflt);
+    assert!(ids.is_empty());
+
+    // the reference moment does not depend on the current time
+    let started = chrono::NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let task = todotxt::Task::parse(&format!("old timer tmr:{}", started.timestamp()), now);
+    cflt.tmr_running_hours = Some(2);
+    let day = chrono::NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+    assert!(tfilter::matches_at(&task, &cflt, day, started + chrono::Duration::hours(3)));
+    assert!(!tfilter::matches_at(&task, &cflt, day, started + chrono::Duration::hours(1)));
+    cflt.tmr_running_hours = Some(24);
+    assert!(tfilter::matches(&task, &cflt, chrono::NaiveDate::from_ymd_opt(2020, 1, 5).unwrap()));
+    assert!(!tfilter::matches(&task, &cflt, chrono::NaiveDate::from_ymd_opt(2019, 12, 30).unwrap()));
+}
+
+#[test]
+fn item_tag_count() {
+    let t = init_tasks();
+    let mut cflt = tfilter::Conf { all: tfilter::TodoStatus::All, ..Default::default() };
+
+    cflt.tag_count = Some(tfilter::ValueRange { low: 2, high: i64::MAX });
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![2, 3]);
+
+    cflt.tag_count = Some(tfilter::ValueRange { low: 0, high: 0 });
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 1]);
+
+    cflt.tag_count = Some(tfilter::ValueRange { low: 1, high: 1 });
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![4, 5]);
+}
+
+#[test]
+fn item_due_within_days_of_creation() {
+    let now = chrono::Local::now().date_naive();
+    let t: todo::TaskVec = vec![
+        todotxt::Task::parse("2020-01-01 same day due:2020-01-01", now),
+        todotxt::Task::parse("2020-01-01 two days due:2020-01-03", now),
+        todotxt::Task::parse("2020-01-01 three days due:2020-01-04", now),
+        todotxt::Task::parse("2020-01-01 two weeks due:2020-01-15", now),
+        todotxt::Task::parse("no creation date due:2020-01-02", now),
+        todotxt::Task::parse("2020-01-01 no due date", now),
+    ];
+    let mut cflt = tfilter::Conf { due_within_days_of_creation: Some(2), ..Default::default() };
+
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 1]);
+
+    cflt.due_within_days_of_creation = Some(0);
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0]);
+
+    cflt.due_within_days_of_creation = Some(14);
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn item_pairs() {
+    let t = init_tasks();
+    let mut cflt = tfilter::Conf { all: tfilter::TodoStatus::All, ..Default::default() };
+
+    cflt.pairs = vec![("Family".to_string(), "kids".to_string())];
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![3, 4]);
+
+    // a project and a context from different pairs do not match
+    cflt.pairs = vec![("family".to_string(), "repair".to_string()), ("car".to_string(), "kids".to_string())];
+    let ids = tfilter::filter(&t, &cflt);
+    assert!(ids.is_empty());
+
+    cflt.pairs = vec![("+family".to_string(), "@parents".to_string()), ("car".to_string(), "repair".to_string())];
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 1, 2]);
+}
+
+#[test]
+fn item_min_list_counts() {
+    let now = chrono::Local::now().date_naive();
+    let t: todo::TaskVec = vec![
+        todotxt::Task::parse("call mother", now),
+        todotxt::Task::parse("call mother +family @phone", now),
+        todotxt::Task::parse("fix car +car +garage @garage", now),
+        todotxt::Task::parse("plan trip +family +travel @home @work", now),
+    ];
+    let mut cflt = tfilter::Conf { min_projects: Some(2), ..Default::default() };
+
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![2, 3]);
+
+    cflt.min_contexts = Some(2);
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![3]);
+
+    cflt.min_projects = None;
+    cflt.min_contexts = Some(1);
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![1, 2, 3]);
+}
+
+#[test]
+fn item_modified_since() {
+    let now = chrono::Local::now().date_naive();
+    let d = |m, d| chrono::NaiveDate::from_ymd_opt(2023, m, d).unwrap();
+    let mut t: todo::TaskVec = vec![
+        todotxt::Task::parse("never touched", now),
+        todotxt::Task::parse("old modified:2023-01-10", now),
+        todotxt::Task::parse("recent modified:2023-05-01 due:2023-06-01", now),
+        todotxt::Task::parse("broken modified:yesterday", now),
+    ];
+
+    assert!(t[0].touch(d(5, 2)));
+    assert_eq!(t[0].subject, "never touched modified:2023-05-02");
+    assert!(!t[0].touch(d(5, 2)));
+    assert!(t[2].touch(d(5, 3)));
+    assert_eq!(t[2].subject, "recent modified:2023-05-03 due:2023-06-01");
+    assert_eq!(t[2].modified_date(), Some(d(5, 3)));
+    assert_eq!(t[3].modified_date(), None);
+
+    let mut cflt = tfilter::Conf { modified_since: Some(d(5, 2)), ..Default::default() };
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 2]);
+
+    cflt.modified_since = Some(d(5, 3));
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![2]);
+
+    cflt.modified_since = Some(d(1, 1));
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 1, 2]);
+}
+
+#[test]
+fn item_regex_full_line() {
+    let t = init_tasks();
+    let mut cflt = tfilter::Conf {
+        all: tfilter::TodoStatus::All,
+        regex: Some("^x ".to_string()),
+        use_regex: true,
+        ..Default::default()
+    };
+
+    // by default only subjects are checked
+    let ids = tfilter::filter(&t, &cflt);
+    assert!(ids.is_empty());
+
+    cflt.regex_full_line = true;
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![1]);
+
+    cflt.regex = Some(r"^\([AB]\) ".to_string());
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![2, 3]);
+
+    // priority of a completed todo goes before its dates
+    cflt.regex = Some(r"^x \(C\) \d{4}-\d{2}-\d{2} ".to_string());
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![1]);
+
+    cflt.regex = None;
+    cflt.exclude_regex = Some("^x ".to_string());
+    cflt.exclude_use_regex = true;
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 2, 3, 4, 5]);
+}
+
+#[test]
+fn item_range_exclusive() {
+    let t = init_tasks();
+    // paging through the list in chunks of 4 records
+    let mut cflt = tfilter::Conf { all: tfilter::TodoStatus::All, ..Default::default() };
+    let mut pages = Vec::new();
+    for page in 0..3 {
+        cflt.range = tfilter::ItemRange::RangeExclusive(page * 4, (page + 1) * 4);
+        pages.push(tfilter::filter(&t, &cflt));
+    }
+    assert_eq!(pages, vec![vec![0, 1, 2, 3], vec![4, 5], vec![]]);
+
+    // the upper bound is not included
+    cflt.range = tfilter::ItemRange::RangeExclusive(1, 3);
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![1, 2]);
+
+    // empty ranges
+    cflt.range = tfilter::ItemRange::RangeExclusive(2, 2);
+    assert!(tfilter::filter(&t, &cflt).is_empty());
+    cflt.range = tfilter::ItemRange::RangeExclusive(3, 1);
+    assert!(tfilter::filter(&t, &cflt).is_empty());
+}
+
+#[test]
+fn filter_page_test() {
+    let t = init_tasks();
+    let cflt = tfilter::Conf { all: tfilter::TodoStatus::Active, ..Default::default() };
+
+    let (ids, total) = tfilter::filter_page(&t, &cflt, 0, 2);
+    assert_eq!((ids, total), (vec![0, 2], 5));
+    let (ids, total) = tfilter::filter_page(&t, &cflt, 2, 2);
+    assert_eq!((ids, total), (vec![3, 4], 5));
+    let (ids, total) = tfilter::filter_page(&t, &cflt, 4, 2);
+    assert_eq!((ids, total), (vec![5], 5));
+    let (ids, total) = tfilter::filter_page(&t, &cflt, 10, 2);
+    assert_eq!((ids, total), (vec![], 5));
+    let (ids, total) = tfilter::filter_page(&t, &cflt, 1, 0);
+    assert_eq!((ids, total), (vec![], 5));
+    let (ids, total) = tfilter::filter_page(&t, &cflt, 1, usize::MAX);
+    assert_eq!((ids, total), (vec![2, 3, 4, 5], 5));
+}
+
+#[test]
+fn item_recurring() {
+    let mut t = init_tasks();
+    let now = chrono::Local::now().date_naive();
+    t.push(todotxt::Task::parse("x 2018-10-05 completed recurrent todo due:2018-10-01 rec:1m", now));
+    let mut cflt = tfilter::Conf { all: TodoStatus::Recurring, ..Default::default() };
+
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![3]);
+
+    cflt.range = tfilter::ItemRange::Range(0, 2);
+    let ids = tfilter::filter(&t, &cflt);
+    assert!(ids.is_empty());
+
+    cflt.range = tfilter::ItemRange::List(vec![3, 4, 6]);
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![3]);
+}
+
+#[test]
+fn item_match_any() {
+    let t = init_tasks();
+    let mut cflt = tfilter::Conf::default();
+    cflt.include.projects = vec!["car".to_string()];
+    cflt.include.contexts = vec!["kids".to_string()];
+
+    // AND: no active todo is in project `car` and context `kids` at the same time
+    let ids = tfilter::filter(&t, &cflt);
+    assert!(ids.is_empty());
+
+    // OR
+    cflt.match_any = true;
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![2, 3, 4]);
+
+    // excludes still apply
+    cflt.exclude.hashtags = vec!["tagone".to_string()];
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![2, 3]);
+
+    // other filters still apply
+    cflt.exclude.hashtags = Vec::new();
+    cflt.regex = Some("art".to_string());
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![3]);
+}
+
+#[test]
+fn item_exclude_regex() {
+    let t = init_tasks();
+    // substring, case-insensitive
+    let mut cflt = tfilter::Conf { exclude_regex: Some("FAMILY".to_string()), ..Default::default() };
+    let ids = tfilter::filter(&t, &cflt);
+    assert!(ids.is_empty());
+    cflt.exclude_regex = Some("kid".to_string());
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 2, 5]);
+
+    // include first, then exclude
+    cflt.regex = Some("family".to_string());
+    cflt.exclude_regex = Some("car".to_string());
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 3, 4, 5]);
+
+    // regular expression
+    cflt.regex = None;
+    cflt.exclude_regex = Some(r"^(call|xmas)\b".to_string());
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 2, 3, 4, 5]);
+    cflt.exclude_use_regex = true;
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![2, 3, 4]);
+}
+
+#[test]
+fn item_require_all() {
+    let t = init_tasks();
+    let mut cflt = tfilter::Conf::default();
+    cflt.include.hashtags = vec!["tagone".to_string(), "tagtwo".to_string()];
+
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 2, 4]);
+
+    cflt.include.require_all_hashtags = true;
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![4]);
+
+    cflt.include.hashtags = vec!["tagone".to_string(), "tagthree".to_string()];
+    let ids = tfilter::filter(&t, &cflt);
+    assert!(ids.is_empty());
+
+    // patterns and other categories
+    cflt.include.hashtags = vec!["tag*".to_string(), "*two".to_string()];
+    cflt.include.contexts = vec!["k*".to_string(), "*s".to_string()];
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![4]);
+
+    // every category opts in separately
+    cflt.include.hashtags = vec!["tagone".to_string()];
+    cflt.include.contexts = vec!["kids".to_string(), "parents".to_string()];
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 4]);
+    cflt.include.require_all_contexts = true;
+    let ids = tfilter::filter(&t, &cflt);
+    assert!(ids.is_empty());
+
+    // exclude todos that have both hashtags
+    cflt = tfilter::Conf::default();
+    cflt.exclude = tfilter::TagFilter {
+        hashtags: vec!["tagone".to_string(), "tagtwo".to_string()],
+        require_all_hashtags: true,
+        ..Default::default()
+    };
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 2, 3, 5]);
+}
+
+#[test]
+fn item_due_absolute_dates() {
+    let t = init_tasks();
+    let mut cflt = tfilter::Conf::default();
+    let dt = |m: u32, d: u32| chrono::NaiveDate::from_ymd_opt(2018, m, d);
+
+    // calendar month
+    cflt.due = Some(tfilter::DateRange::between(dt(11, 1), dt(11, 30)));
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![3, 4]);
+
+    // inclusive ends
+    cflt.due = Some(tfilter::DateRange::between(dt(11, 18), dt(12, 1)));
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![2, 4]);
+
+    // open-ended ranges
+    cflt.due = Some(tfilter::DateRange::between(dt(12, 1), None));
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![2, 5]);
+    cflt.due = Some(tfilter::DateRange::between(None, dt(11, 17)));
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![3]);
+    cflt.due = Some(tfilter::DateRange::between(None, None));
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![2, 3, 4, 5]);
+
+    // a range built by hand
+    cflt.due =
+        Some(tfilter::DateRange { span: tfilter::ValueSpan::Dates(dt(11, 1), dt(11, 30)), days: Default::default() });
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![3, 4]);
+}
+
+#[test]
+fn item_future_threshold() {
+    let today = chrono::NaiveDate::from_ymd_opt(2020, 5, 10).unwrap();
+    let t: todo::TaskVec = vec![
+        todotxt::Task::parse("no threshold", today),
+        todotxt::Task::parse("started yesterday t:2020-05-09", today),
+        todotxt::Task::parse("starts today t:2020-05-10", today),
+        todotxt::Task::parse("starts tomorrow t:2020-05-11", today),
+    ];
+    let matching = |c: &tfilter::Conf| (0..t.len()).filter(|i| tfilter::matches(&t[*i], c, today)).collect::<Vec<_>>();
+
+    // active todos that are not started yet are hidden
+    let mut cflt = tfilter::Conf::default();
+    assert_eq!(matching(&cflt), vec![0, 1, 2]);
+
+    // all todos are shown
+    cflt.all = TodoStatus::All;
+    assert_eq!(matching(&cflt), vec![0, 1, 2, 3]);
+
+    // explicit threshold filter replaces the default one
+    cflt.all = TodoStatus::Active;
+    cflt.thr = Some(tfilter::DateRange { span: tfilter::ValueSpan::Any, days: Default::default() });
+    assert_eq!(matching(&cflt), vec![1, 2, 3]);
+}
+
+#[test]
+fn item_metadata_only() {
+    let mut t = init_tasks();
+    let now = chrono::Local::now().date_naive();
+    t.push(todotxt::Task::parse("+car @garage", now));
+    t.push(todotxt::Task::parse("(B) #later due:2018-12-01", now));
+    let mut cflt = tfilter::Conf { metadata_only: Some(true), ..Default::default() };
+
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![6, 7]);
+
+    cflt.metadata_only = Some(false);
+    let ids = tfilter::filter(&t, &cflt);
+    assert_eq!(ids, vec![0, 2, 3, 4, 5]);
+}
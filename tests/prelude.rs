@@ -0,0 +1,29 @@
+use todo_lib::prelude::*;
+
+#[test]
+fn prelude_filter_sort_edit() {
+    let now = chrono::Local::now().date_naive();
+    let mut tasks: TaskVec = vec![
+        Task::parse("call mother +family", now),
+        Task::parse("(B) repair car +car due:2018-12-01", now),
+        Task::parse("x 2018-10-05 wash car +car", now),
+        Task::parse("(A) buy tires +car", now),
+    ];
+
+    let mut fc = FilterConf::default();
+    fc.include.projects = vec!["car".to_string()];
+    let mut ids: IDVec = filter(&tasks, &fc);
+    assert_eq!(ids, vec![1, 3]);
+
+    let sc = SortConf { fields: Some("pri".to_string()), ..Default::default() };
+    sort(&mut ids, &tasks, &sc);
+    assert_eq!(ids, vec![3, 1]);
+
+    let mut tc = TodoConf::default();
+    tc.contexts = ListTagChange { action: Action::Set, value: vec!["garage".to_string()] };
+    let changed: ChangedVec = edit(&mut tasks, Some(&ids), &tc);
+    assert_eq!(changed, vec![true, true]);
+    assert_eq!(tasks[3].to_string(), "(A) buy tires +car @garage");
+    assert_eq!(tasks[1].contexts, vec!["garage".to_string()]);
+    assert!(tasks[0].contexts.is_empty());
+}
@@ -31,8 +31,7 @@ fn one_field() {
     let mut ids = make_id_vec(t.len());
 
     // by priority: items without priority must be last items
-    let mut c = tsort::Conf::default();
-    c.fields = Some("priority".to_owned());
+    let mut c = tsort::Conf { fields: Some("priority".to_owned()), ..Default::default() };
     tsort::sort(&mut ids, &t, &c);
     assert_eq!(ids, vec![3, 2, 1, 0, 4, 5]);
 
@@ -66,9 +65,7 @@ fn one_field_reverse() {
     let t = init_tasks();
     let mut ids = make_id_vec(t.len());
 
-    let mut c = tsort::Conf::default();
-    c.rev = true;
-    c.fields = Some("priority".to_owned());
+    let mut c = tsort::Conf { rev: true, fields: Some("priority".to_owned()) };
     tsort::sort(&mut ids, &t, &c);
     assert_eq!(ids, vec![5, 4, 0, 1, 2, 3]);
 
@@ -120,8 +117,196 @@ fn invalid_cases() {
 
     // non-existing IDs must be at the end
     let mut ids: todo::IDVec = vec![12, 0, 1, 2, 19, 3, 4, 5, 20];
-    let mut c = tsort::Conf::default();
-    c.fields = Some("priority".to_owned());
+    let c = tsort::Conf { fields: Some("priority".to_owned()), ..Default::default() };
     tsort::sort(&mut ids, &t, &c);
     assert_eq!(ids, vec![3, 2, 1, 0, 4, 5, 12, 19, 20]);
 }
+
+#[test]
+fn recurrence_equality() {
+    let rec = |s: &str| -> Option<todotxt::Recurrence> { Some(s.parse().unwrap()) };
+    struct Test {
+        r1: Option<todotxt::Recurrence>,
+        r2: Option<todotxt::Recurrence>,
+        strict: bool,
+        cadence: bool,
+    }
+    let tests: Vec<Test> = vec![
+        Test { r1: None, r2: None, strict: true, cadence: true },
+        Test { r1: rec("1m"), r2: None, strict: false, cadence: false },
+        Test { r1: None, r2: rec("+1m"), strict: false, cadence: false },
+        Test { r1: rec("1m"), r2: rec("1m"), strict: true, cadence: true },
+        Test { r1: rec("+1m"), r2: rec("+1m"), strict: true, cadence: true },
+        Test { r1: rec("1m"), r2: rec("+1m"), strict: false, cadence: true },
+        Test { r1: rec("1m"), r2: rec("2m"), strict: false, cadence: false },
+        Test { r1: rec("+1m"), r2: rec("1w"), strict: false, cadence: false },
+        Test { r1: rec("7d"), r2: rec("1w"), strict: false, cadence: false },
+    ];
+    for (idx, t) in tests.iter().enumerate() {
+        assert_eq!(tsort::equal_opt_rec(&t.r1, &t.r2), t.strict, "{idx}. strict");
+        assert_eq!(tsort::recurrence_equal_ignoring_strict(&t.r1, &t.r2), t.cadence, "{idx}. cadence");
+    }
+}
+
+#[test]
+fn sort_duration() {
+    let now = chrono::Local::now().date_naive();
+    let created = (now - chrono::Duration::days(5)).format("%Y-%m-%d").to_string();
+    let t: todo::TaskVec = vec![
+        todotxt::Task::parse("x 2023-08-20 2023-08-01 took 19 days", now),
+        todotxt::Task::parse("no dates", now),
+        todotxt::Task::parse("x 2023-08-03 2023-08-01 took 2 days", now),
+        todotxt::Task::parse(&format!("{created} active for 5 days"), now),
+        todotxt::Task::parse("x 2023-08-20 no creation date", now),
+        todotxt::Task::parse("x 2023-09-10 2023-08-01 took 40 days", now),
+    ];
+
+    let sconf = tsort::Conf { fields: Some("duration".to_string()), rev: false };
+    let mut ids: todo::IDVec = vec![0, 1, 2, 3, 4, 5];
+    tsort::sort(&mut ids, &t, &sconf);
+    assert_eq!(ids, vec![2, 0, 5, 1, 3, 4]);
+
+    let sconf = tsort::Conf { fields: Some("duration".to_string()), rev: true };
+    let mut ids: todo::IDVec = vec![0, 1, 2, 3, 4, 5];
+    tsort::sort(&mut ids, &t, &sconf);
+    assert_eq!(ids, vec![4, 3, 1, 5, 0, 2]);
+
+    let sconf = tsort::Conf { fields: Some("age".to_string()), rev: false };
+    let mut ids: todo::IDVec = vec![0, 1, 2, 3, 4, 5];
+    tsort::sort(&mut ids, &t, &sconf);
+    assert_eq!(ids, vec![2, 3, 0, 5, 1, 4]);
+}
+
+#[test]
+fn sort_tag_count() {
+    let now = chrono::Local::now().date_naive();
+    let t: todo::TaskVec = vec![
+        todotxt::Task::parse("three projects +a +b +c @home", now),
+        todotxt::Task::parse("one project +a @home @work", now),
+        todotxt::Task::parse("nothing", now),
+        todotxt::Task::parse("another one +b", now),
+    ];
+
+    let sconf = tsort::Conf { fields: Some("projcount".to_string()), rev: false };
+    let mut ids: todo::IDVec = vec![0, 1, 2, 3];
+    tsort::sort(&mut ids, &t, &sconf);
+    assert_eq!(ids, vec![2, 1, 3, 0]);
+
+    let sconf = tsort::Conf { fields: Some("ctxcount".to_string()), rev: false };
+    let mut ids: todo::IDVec = vec![0, 1, 2, 3];
+    tsort::sort(&mut ids, &t, &sconf);
+    assert_eq!(ids, vec![2, 3, 0, 1]);
+
+    let sconf = tsort::Conf { fields: Some("ctxcount,projcount".to_string()), rev: false };
+    let mut ids: todo::IDVec = vec![0, 1, 2, 3];
+    tsort::sort(&mut ids, &t, &sconf);
+    assert_eq!(ids, vec![2, 3, 0, 1]);
+}
+
+#[test]
+fn sort_tag_value() {
+    let now = chrono::Local::now().date_naive();
+    let t: todo::TaskVec = vec![
+        todotxt::Task::parse("first id:10", now),
+        todotxt::Task::parse("second id:9", now),
+        todotxt::Task::parse("no id", now),
+        todotxt::Task::parse("third id:100 +proj", now),
+        todotxt::Task::parse("fourth ID:-1", now),
+    ];
+
+    let sconf = tsort::Conf { fields: Some("tag:id".to_string()), rev: false };
+    let mut ids: todo::IDVec = vec![0, 1, 2, 3, 4];
+    tsort::sort(&mut ids, &t, &sconf);
+    assert_eq!(ids, vec![4, 1, 0, 3, 2]);
+
+    let sconf = tsort::Conf { fields: Some("proj,tag:id".to_string()), rev: false };
+    let mut ids: todo::IDVec = vec![0, 1, 2, 3, 4];
+    tsort::sort(&mut ids, &t, &sconf);
+    assert_eq!(ids, vec![3, 4, 1, 0, 2]);
+
+    // not all values are numbers
+    let t: todo::TaskVec = vec![
+        todotxt::Task::parse("first id:10", now),
+        todotxt::Task::parse("second id:9", now),
+        todotxt::Task::parse("third id:Abc", now),
+        todotxt::Task::parse("no id", now),
+        todotxt::Task::parse("fourth id:abb", now),
+    ];
+    let sconf = tsort::Conf { fields: Some("tag:id".to_string()), rev: false };
+    let mut ids: todo::IDVec = vec![0, 1, 2, 3, 4];
+    tsort::sort(&mut ids, &t, &sconf);
+    assert_eq!(ids, vec![0, 1, 4, 2, 3]);
+
+    // only values of sorted todos matter
+    let mut ids: todo::IDVec = vec![0, 1, 3];
+    tsort::sort(&mut ids, &t, &sconf);
+    assert_eq!(ids, vec![1, 0, 3]);
+}
+
+#[test]
+fn sort_tag_presence() {
+    let now = chrono::Local::now().date_naive();
+    let t: todo::TaskVec = vec![
+        todotxt::Task::parse("no deadline", now),
+        todotxt::Task::parse("late deadline:2023-10-01", now),
+        todotxt::Task::parse("(A) another without deadline", now),
+        todotxt::Task::parse("early deadline:2023-08-15", now),
+        todotxt::Task::parse("(B) middle deadline:2023-09-01", now),
+    ];
+
+    let sconf = tsort::Conf { fields: Some("tag:deadline".to_string()), rev: false };
+    let mut ids: todo::IDVec = vec![0, 1, 2, 3, 4];
+    tsort::sort(&mut ids, &t, &sconf);
+    assert_eq!(ids, vec![3, 4, 1, 0, 2]);
+
+    // presence and value of the tag go before the next field
+    let sconf = tsort::Conf { fields: Some("tag:deadline,pri".to_string()), rev: false };
+    let mut ids: todo::IDVec = vec![0, 1, 2, 3, 4];
+    tsort::sort(&mut ids, &t, &sconf);
+    assert_eq!(ids, vec![3, 4, 1, 2, 0]);
+}
+
+#[test]
+fn sort_done_rank() {
+    let now = chrono::Local::now().date_naive();
+    let t: todo::TaskVec = vec![
+        todotxt::Task::parse("x 2023-08-20 completed recurrent due:2023-08-20 rec:1w", now),
+        todotxt::Task::parse("x 2023-08-20 completed", now),
+        todotxt::Task::parse("active recurrent due:2023-08-20 rec:1w", now),
+        todotxt::Task::parse("active", now),
+        todotxt::Task::parse("active with timer tmr:1692500000", now),
+        todotxt::Task::parse("active recurrent with timer due:2023-08-20 rec:1d tmr:1692500000", now),
+        todotxt::Task::parse("x 2023-08-20 completed with stale timer tmr:1692500000", now),
+    ];
+
+    let mut c = tsort::Conf { fields: Some("done".to_string()), rev: false };
+    let mut ids = make_id_vec(t.len());
+    tsort::sort(&mut ids, &t, &c);
+    assert_eq!(ids, vec![3, 4, 5, 2, 0, 1, 6]);
+
+    c.rev = true;
+    let mut ids = make_id_vec(t.len());
+    tsort::sort(&mut ids, &t, &c);
+    assert_eq!(ids, vec![6, 1, 0, 2, 5, 4, 3]);
+}
+
+#[test]
+fn sort_no_priority_first() {
+    let t = init_tasks();
+    let mut c = tsort::Conf { fields: Some("pri".to_owned()), ..Default::default() };
+
+    let mut ids = make_id_vec(t.len());
+    tsort::sort(&mut ids, &t, &c);
+    assert_eq!(ids, vec![3, 2, 1, 0, 4, 5]);
+
+    c.fields = Some("pri-none-first".to_owned());
+    let mut ids = make_id_vec(t.len());
+    tsort::sort(&mut ids, &t, &c);
+    assert_eq!(ids, vec![0, 4, 5, 3, 2, 1]);
+
+    // todos without priority are then ordered by the next field
+    c.fields = Some("priority-none-first,due".to_owned());
+    let mut ids = make_id_vec(t.len());
+    tsort::sort(&mut ids, &t, &c);
+    assert_eq!(ids, vec![4, 5, 0, 3, 2, 1]);
+}
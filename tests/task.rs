@@ -1,6 +1,9 @@
-use chrono::NaiveDate;
+use chrono::{Datelike, NaiveDate};
 use todo_lib::todo::{done, edit, Action, Conf, DateTagChange, NewDateValue};
-use todo_lib::todotxt::{business_days_between, CompletionConfig, CompletionDateMode, CompletionMode, Task};
+use todo_lib::todotxt::{
+    business_days_between, canonicalize, quick_meta, CompletionConfig, CompletionDateMode, CompletionMode,
+    IgnoredField, ParseOptions, Recurrence, TagOrder, Task,
+};
 
 #[test]
 fn parse_tasks_simple() {
@@ -20,7 +23,7 @@ fn parse_tasks_simple() {
             i: "2020-01-03 just text",
             t: Task {
                 subject: "just text".to_string(),
-                create_date: Some(NaiveDate::from_ymd_opt(2020, 01, 03).unwrap()),
+                create_date: Some(NaiveDate::from_ymd_opt(2020, 1, 3).unwrap()),
                 ..Default::default()
             },
         },
@@ -28,7 +31,7 @@ fn parse_tasks_simple() {
             i: "2020-02-03 2020-01-03 just text",
             t: Task {
                 subject: "2020-01-03 just text".to_string(),
-                create_date: Some(NaiveDate::from_ymd_opt(2020, 02, 03).unwrap()),
+                create_date: Some(NaiveDate::from_ymd_opt(2020, 2, 3).unwrap()),
                 ..Default::default()
             },
         },
@@ -36,8 +39,8 @@ fn parse_tasks_simple() {
             i: "x 2020-02-03 2020-01-03 just text",
             t: Task {
                 subject: "just text".to_string(),
-                create_date: Some(NaiveDate::from_ymd_opt(2020, 01, 03).unwrap()),
-                finish_date: Some(NaiveDate::from_ymd_opt(2020, 02, 03).unwrap()),
+                create_date: Some(NaiveDate::from_ymd_opt(2020, 1, 3).unwrap()),
+                finish_date: Some(NaiveDate::from_ymd_opt(2020, 2, 3).unwrap()),
                 finished: true,
                 ..Default::default()
             },
@@ -47,8 +50,8 @@ fn parse_tasks_simple() {
             t: Task {
                 subject: "just text".to_string(),
                 priority: 4,
-                create_date: Some(NaiveDate::from_ymd_opt(2020, 01, 03).unwrap()),
-                finish_date: Some(NaiveDate::from_ymd_opt(2020, 02, 03).unwrap()),
+                create_date: Some(NaiveDate::from_ymd_opt(2020, 1, 3).unwrap()),
+                finish_date: Some(NaiveDate::from_ymd_opt(2020, 2, 3).unwrap()),
                 finished: true,
                 ..Default::default()
             },
@@ -141,6 +144,29 @@ fn parse_tasks_tags() {
     }
 }
 
+#[test]
+fn parse_tasks_double_spaces() {
+    struct Test {
+        i: &'static str,
+        o: &'static str,
+    }
+    let data: Vec<Test> = vec![
+        Test { i: "just  text", o: "just text" },
+        Test { i: "(B) 2020-01-03 buy   milk  +shop @store", o: "(B) 2020-01-03 buy milk +shop @store" },
+        Test { i: "x 2020-02-03 2020-01-03  call   mom", o: "x 2020-02-03 2020-01-03 call mom" },
+        Test { i: "task  due:2d   rec:1w", o: "task due:2020-03-17 rec:1w" },
+    ];
+    let base = NaiveDate::from_ymd_opt(2020, 3, 15).unwrap();
+    for d in data.iter() {
+        let t = Task::parse(d.i, base);
+        assert_eq!(d.o, &format!("{}", t), "{}", d.i);
+    }
+
+    let mut t = Task::parse("pay  bills due:2020-03-20  now", base);
+    t.update_tag_with_value("due", "2020-04-01");
+    assert_eq!("pay bills due:2020-04-01 now", &format!("{}", t));
+}
+
 #[test]
 #[allow(deprecated)]
 fn complete_old_signature() {
@@ -455,17 +481,15 @@ fn next_date() {
     let base = NaiveDate::from_ymd_opt(2020, 2, 2).unwrap();
     for d in data.iter() {
         let mut t = Task::parse(d.i, base);
-        let orig_due = t.due_date.clone();
-        let orig_thr = t.threshold_date.clone();
+        let orig_due = t.due_date;
+        let orig_thr = t.threshold_date;
         t.next_dates(base);
         assert_eq!(d.d, &format!("{}", t), "done {}", d.i);
-        if orig_due.is_some() && t.recurrence.is_some() && t.due_date.is_some() {
-            let orig = orig_due.unwrap();
-            assert!(orig < t.due_date.unwrap(), "due must change: {}", d.i);
+        if let (Some(orig), Some(_), Some(due)) = (orig_due, t.recurrence, t.due_date) {
+            assert!(orig < due, "due must change: {}", d.i);
         }
-        if orig_thr.is_some() && t.recurrence.is_some() && t.threshold_date.is_some() {
-            let orig = orig_thr.unwrap();
-            assert!(orig < t.threshold_date.unwrap(), "threshold must change: {}", d.i);
+        if let (Some(orig), Some(_), Some(thr)) = (orig_thr, t.recurrence, t.threshold_date) {
+            assert!(orig < thr, "threshold must change: {}", d.i);
         }
     }
 }
@@ -489,7 +513,7 @@ fn replace_projects() {
         Test { i: "efg ++tag str abc +tag", o: "efg ++tag str abc +newstr", r: "+tag", w: "+newstr" },
     ];
 
-    let dt = NaiveDate::from_ymd_opt(2021, 01, 05).unwrap();
+    let dt = NaiveDate::from_ymd_opt(2021, 1, 5).unwrap();
     for d in data.iter() {
         let mut t = Task::parse(d.i, dt);
         t.replace_project(d.r, d.w);
@@ -516,7 +540,7 @@ fn replace_contexts() {
         Test { i: "efg @@tag str abc @tag", o: "efg @@tag str abc @newstr", r: "@tag", w: "@newstr" },
     ];
 
-    let dt = NaiveDate::from_ymd_opt(2021, 01, 05).unwrap();
+    let dt = NaiveDate::from_ymd_opt(2021, 1, 5).unwrap();
     for d in data.iter() {
         let mut t = Task::parse(d.i, dt);
         t.replace_context(d.r, d.w);
@@ -544,7 +568,7 @@ fn replace_recurrences() {
         Test { i: "rrec:11 text rec:22", o: "rrec:11 text rec:345", w: "345" },
     ];
 
-    let dt = NaiveDate::from_ymd_opt(2021, 01, 05).unwrap();
+    let dt = NaiveDate::from_ymd_opt(2021, 1, 5).unwrap();
     for d in data.iter() {
         let mut t = Task::parse(d.i, dt);
         t.update_tag_with_value("rec", d.w);
@@ -604,3 +628,831 @@ fn due_expr_test() {
         assert_eq!(tasks[0].subject, d.d, "New value must be {0}, got {1}", d.d, tasks[0].subject);
     }
 }
+
+#[test]
+fn canonicalize_test() {
+    struct Test {
+        i: &'static str,
+        o: &'static str,
+    }
+    let data: Vec<Test> = vec![
+        Test { i: "just text", o: "just text" },
+        Test { i: "  just   text  ", o: "just text" },
+        Test { i: "(A)  2020-01-03   call  mom  due:2d", o: "(A) 2020-01-03 call mom due:2020-03-17" },
+        Test { i: "x 2020-02-03 2020-01-03  fix  car t:1w ", o: "x 2020-02-03 2020-01-03 fix car t:2020-03-22" },
+        Test { i: "(B) 2020-01-03 ", o: "(B) 2020-01-03" },
+        Test { i: "", o: "" },
+    ];
+    let base = NaiveDate::from_ymd_opt(2020, 3, 15).unwrap();
+    for d in data.iter() {
+        let c = canonicalize(d.i, base);
+        assert_eq!(d.o, &c, "{}", d.i);
+        assert_eq!(c, canonicalize(&c, base), "{}", d.i);
+    }
+}
+
+#[test]
+fn invalid_recurrence_tag_test() {
+    struct Test {
+        i: &'static str,
+        invalid: bool,
+        o: &'static str,
+    }
+    let data: Vec<Test> = vec![
+        Test { i: "task rec:", invalid: true, o: "task" },
+        Test { i: "task rec:xyz due:2020-03-17", invalid: true, o: "task due:2020-03-17" },
+        Test { i: "rec:10 task", invalid: true, o: "task" },
+        Test { i: "task rec:2w", invalid: false, o: "task rec:2w" },
+        Test { i: "task rec:2w rec: end", invalid: true, o: "task rec:2w end" },
+        Test { i: "task prec:abc", invalid: false, o: "task prec:abc" },
+    ];
+    let base = NaiveDate::from_ymd_opt(2020, 3, 15).unwrap();
+    for d in data.iter() {
+        let mut t = Task::parse(d.i, base);
+        assert_eq!(t.has_invalid_recurrence_tag(), d.invalid, "{}", d.i);
+        let rec = t.recurrence;
+        assert_eq!(t.strip_invalid_recurrence_tags(), d.invalid, "{}", d.i);
+        assert_eq!(d.o, &t.subject, "{}", d.i);
+        assert_eq!(rec, t.recurrence, "{}", d.i);
+        assert!(!t.has_invalid_recurrence_tag(), "{}", d.i);
+    }
+    let mut t = Task::parse("task rec:xyz", base);
+    assert_eq!(t.tags.get("rec"), Some(&"xyz".to_string()));
+    t.strip_invalid_recurrence_tags();
+    assert!(!t.tags.contains_key("rec"));
+
+    // normalization while parsing
+    let opts = ParseOptions { strip_invalid_rec: true, ..Default::default() };
+    for d in data.iter() {
+        let t = Task::parse_with_options(d.i, base, &opts);
+        assert_eq!(d.o, &t.subject, "{}", d.i);
+        assert!(!t.has_invalid_recurrence_tag(), "{}", d.i);
+        let t = Task::parse_with_options(d.i, base, &ParseOptions::default());
+        assert_eq!(t.has_invalid_recurrence_tag(), d.invalid, "{}", d.i);
+    }
+}
+
+#[test]
+fn completion_date_round_trip() {
+    struct Test {
+        i: &'static str,
+        d: &'static str,
+        m: CompletionMode,
+        subj: &'static str,
+    }
+    let data: Vec<Test> = vec![
+        Test { i: "Task", d: "x 2023-08-20 Task", m: CompletionMode::JustMark, subj: "Task" },
+        Test { i: "(B) Task", d: "x 2023-08-20 Task", m: CompletionMode::RemovePriority, subj: "Task" },
+        Test { i: "(B) Task", d: "x 2023-08-20 Task pri:B", m: CompletionMode::PriorityToTag, subj: "Task pri:B" },
+        Test { i: "(B) Task", d: "x 2023-08-20 (B) Task", m: CompletionMode::MovePriority, subj: "(B) Task" },
+        Test {
+            i: "Task due:2023-08-25 rec:1w",
+            d: "x 2023-08-20 Task due:2023-08-25 rec:1w",
+            m: CompletionMode::JustMark,
+            subj: "Task due:2023-08-25 rec:1w",
+        },
+    ];
+    let base = NaiveDate::from_ymd_opt(2023, 8, 20).unwrap();
+    for d in data.iter() {
+        let mut t = Task::parse(d.i, base);
+        t.complete_with_config(
+            base,
+            CompletionConfig { completion_mode: d.m, completion_date_mode: CompletionDateMode::AlwaysSet },
+        );
+        let line = format!("{t}");
+        assert_eq!(d.d, &line, "done '{}', mode: {:?}", d.i, d.m);
+
+        let back = Task::parse(&line, base);
+        assert!(back.finished, "'{line}' must be completed");
+        assert_eq!(back.finish_date, Some(base), "'{line}' lost completion date");
+        assert_eq!(back.create_date, None, "'{line}' must not have creation date");
+        assert_eq!(back.subject, d.subj, "'{line}' subject");
+        assert_eq!(format!("{back}"), line);
+
+        // the same when the task has creation date
+        let created = NaiveDate::from_ymd_opt(2023, 8, 1).unwrap();
+        let mut t = Task::parse(d.i, base);
+        t.create_date = Some(created);
+        t.complete_with_config(
+            base,
+            CompletionConfig { completion_mode: d.m, completion_date_mode: CompletionDateMode::AlwaysSet },
+        );
+        let line = format!("{t}");
+        let back = Task::parse(&line, base);
+        assert_eq!(back.finish_date, Some(base), "'{line}' lost completion date");
+        assert_eq!(back.create_date, Some(created), "'{line}' lost creation date");
+    }
+
+    // completion date right after `x` for a task with priority
+    let mut t = Task::parse("(B) 2023-08-01 Task", base);
+    t.complete_with_config(base, CompletionConfig::default());
+    assert_eq!(t.to_string(), "x (B) 2023-08-20 2023-08-01 Task");
+    let line = t.to_string_with_options(TagOrder::Original, true);
+    assert_eq!(line, "x 2023-08-20 2023-08-01 (B) Task");
+    let back = Task::parse(&line, base);
+    assert_eq!(back.finish_date, Some(base));
+    assert_eq!(back.create_date, NaiveDate::from_ymd_opt(2023, 8, 1));
+    assert_eq!(back.subject, "(B) Task");
+}
+
+#[test]
+fn parse_result_test() {
+    struct Test {
+        i: &'static str,
+        err: Option<(&'static str, &'static str)>,
+    }
+    let data: Vec<Test> = vec![
+        Test { i: "(A) 2020-03-01 call mother due:2020-03-10 rec:1w", err: None },
+        Test { i: "x 2020-03-02 2020-03-01 call mother", err: None },
+        Test { i: "call mother at 10-30", err: None },
+        Test { i: "(a) call mother", err: Some(("(a)", "priority")) },
+        Test { i: "2020-31-03 call mother", err: Some(("2020-31-03", "creation date")) },
+        Test { i: "(B) 2020-02-32 call mother", err: Some(("2020-02-32", "creation date")) },
+        Test { i: "x 2020-13-01 call mother", err: Some(("2020-13-01", "completion date")) },
+        Test { i: "x 2020-03-02 2020-00-01 call mother", err: Some(("2020-00-01", "creation date")) },
+        Test { i: "call mother due:2020-31-03", err: Some(("2020-31-03", "due")) },
+        Test { i: "call mother t:tomorrow", err: Some(("tomorrow", "t")) },
+        Test { i: "call mother rec:1x", err: Some(("1x", "rec")) },
+    ];
+    let base = NaiveDate::from_ymd_opt(2020, 3, 1).unwrap();
+    for d in data.iter() {
+        let res = Task::parse_result(d.i, base);
+        match (res, d.err) {
+            (Ok(t), None) => assert_eq!(t, Task::parse(d.i, base), "{}", d.i),
+            (Err(todo_lib::terr::TodoError::InvalidValue(val, name)), Some((ev, en))) => {
+                assert_eq!((val.as_str(), name.as_str()), (ev, en), "{}", d.i);
+            }
+            (res, _) => panic!("{}: unexpected result {res:?}", d.i),
+        }
+    }
+}
+
+#[test]
+fn unparsed_leading_date_test() {
+    let data: Vec<(&str, bool)> = vec![
+        ("2020-31-03 just text", true),
+        ("2020-01-43 just text", true),
+        ("x 2020-01-02 2020-13-01 just text", true),
+        ("(A) 2020-00-10 just text", true),
+        ("2020-01-03 just text", false),
+        ("x 2020-01-02 2020-01-01 2020-01-43 just text", true),
+        ("x 2020-01-02 2020-01-01 2020-01-03 just text", false),
+        ("just text 2020-31-03", false),
+        ("10-30 meeting", false),
+        ("call mother", false),
+    ];
+    let base = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+    for (line, res) in data {
+        let t = Task::parse(line, base);
+        assert_eq!(t.has_unparsed_leading_date(), res, "{line}");
+    }
+}
+
+#[test]
+fn remaining_occurrences_test() {
+    struct Test {
+        i: &'static str,
+        today: NaiveDate,
+        res: Option<u32>,
+    }
+    let d = |m: u32, day: u32| NaiveDate::from_ymd_opt(2023, m, day).unwrap();
+    let data: Vec<Test> = vec![
+        Test { i: "weekly due:2023-08-01 rec:+1w until:2023-09-01", today: d(8, 1), res: Some(4) },
+        Test { i: "weekly due:2023-08-01 rec:+1w until:2023-08-29", today: d(8, 1), res: Some(4) },
+        Test { i: "weekly due:2023-08-01 rec:+1w until:2023-08-28", today: d(8, 1), res: Some(3) },
+        Test { i: "weekly due:2023-08-01 rec:1w until:2023-09-01", today: d(8, 1), res: Some(4) },
+        Test { i: "weekly due:2023-08-01 rec:1w until:2023-09-01", today: d(8, 10), res: Some(3) },
+        Test { i: "weekly due:2023-08-01 rec:+1w until:2023-09-01", today: d(8, 10), res: Some(3) },
+        Test { i: "weekly due:2023-08-01 rec:+1w until:2023-08-05", today: d(8, 1), res: Some(0) },
+        Test { i: "no until due:2023-08-01 rec:+1w", today: d(8, 1), res: None },
+        Test { i: "no due rec:+1w until:2023-09-01", today: d(8, 1), res: None },
+        Test { i: "no dates rec:1w*5", today: d(8, 1), res: None },
+        Test { i: "no dates rec:1w*5 until:2023-09-01", today: d(8, 1), res: None },
+        Test { i: "count only due:2023-08-01 rec:1w*5", today: d(8, 1), res: Some(5) },
+        Test { i: "threshold t:2023-08-01 rec:+1w until:2023-09-01", today: d(8, 1), res: Some(4) },
+        Test { i: "threshold t:2023-08-01 rec:+1w*2 until:2023-09-01", today: d(8, 1), res: Some(2) },
+        Test { i: "no rec due:2023-08-01 until:2023-09-01", today: d(8, 1), res: None },
+        Test { i: "x done due:2023-08-01 rec:+1w until:2023-09-01", today: d(8, 1), res: None },
+    ];
+    for t in data.iter() {
+        let task = Task::parse(t.i, t.today);
+        assert_eq!(task.remaining_occurrences(t.today), t.res, "{} at {}", t.i, t.today);
+    }
+}
+
+#[test]
+fn note_test() {
+    let base = NaiveDate::from_ymd_opt(2023, 8, 20).unwrap();
+    let mut t = Task::parse("(A) call mother +family note:old_note @phone due:2023-09-01", base);
+    assert_eq!(t.note(), Some("old note".to_string()));
+
+    assert!(t.set_note("ask about: the trip, at 10:30"));
+    assert_eq!(t.subject, "call mother +family note:ask_about:_the_trip,_at_10:30 @phone due:2023-09-01");
+    assert_eq!(t.note(), Some("ask about: the trip, at 10:30".to_string()));
+    assert!(!t.set_note("ask about: the trip, at 10:30"));
+
+    let notes = ["snake_case and back\\slash", "two\nlines\tand tab", "  leading and trailing  ", "a:b:c"];
+    for note in notes {
+        t.set_note(note);
+        assert_eq!(t.note().as_deref(), Some(note));
+        let back = Task::parse(&t.to_string(), base);
+        assert_eq!(back.note().as_deref(), Some(note), "{t}");
+        assert_eq!(back.subject, t.subject);
+        assert_eq!(back.due_date, t.due_date);
+    }
+
+    assert!(t.set_note(""));
+    assert_eq!(t.note(), None);
+    assert_eq!(t.subject, "call mother +family @phone due:2023-09-01");
+
+    let mut t = Task::parse("call mother", base);
+    assert_eq!(t.note(), None);
+    t.set_note("buy flowers");
+    assert_eq!(t.subject, "call mother note:buy_flowers");
+
+    assert!(!t.append_note(""));
+    assert!(t.append_note("call: at 10:00"));
+    assert_eq!(t.subject, "call mother note:buy_flowers\\ncall:_at_10:00");
+    assert_eq!(t.note().as_deref(), Some("buy flowers\ncall: at 10:00"));
+    let back = Task::parse(&t.to_string(), base);
+    assert_eq!(back.note(), t.note());
+
+    let mut t = Task::parse("call mother @phone", base);
+    assert!(t.append_note("first line"));
+    assert_eq!(t.subject, "call mother @phone note:first_line");
+}
+
+#[test]
+fn parse_tabs_test() {
+    let base = NaiveDate::from_ymd_opt(2023, 8, 20).unwrap();
+    let t = Task::parse("\tx\t2023-08-20 2023-08-01\tcall\t\tmother +family\t@phone due:2023-09-01", base);
+    assert!(t.finished);
+    assert_eq!(t.finish_date, NaiveDate::from_ymd_opt(2023, 8, 20));
+    assert_eq!(t.create_date, NaiveDate::from_ymd_opt(2023, 8, 1));
+    assert_eq!(t.subject, "call mother +family @phone due:2023-09-01");
+    assert_eq!(t.projects, vec!["family".to_string()]);
+    assert_eq!(t.contexts, vec!["phone".to_string()]);
+    assert_eq!(t.due_date, NaiveDate::from_ymd_opt(2023, 9, 1));
+
+    // pseudo-random mix of tabs and spaces between tokens
+    let tokens = ["(B)", "2023-08-01", "fix", "car", "+car", "@garage", "+family", "id:7", "due:2023-09-01", "@phone"];
+    let mut seed: u32 = 12345;
+    for _ in 0..500 {
+        let mut line = String::new();
+        for (idx, tok) in tokens.iter().enumerate() {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let sep_len = if idx == 0 { (seed >> 16) % 3 } else { (seed >> 16) % 3 + 1 };
+            for i in 0..sep_len {
+                line.push(if (seed >> (20 + i)) & 1 == 1 { '\t' } else { ' ' });
+            }
+            line.push_str(tok);
+        }
+        let t = Task::parse(&line, base);
+        assert_eq!(t.priority, 1, "{line:?}");
+        assert_eq!(t.create_date, NaiveDate::from_ymd_opt(2023, 8, 1), "{line:?}");
+        assert_eq!(t.subject, "fix car +car @garage +family id:7 due:2023-09-01 @phone", "{line:?}");
+        assert_eq!(t.projects, vec!["car".to_string(), "family".to_string()], "{line:?}");
+        assert_eq!(t.contexts, vec!["garage".to_string(), "phone".to_string()], "{line:?}");
+        assert_eq!(t.tags.get("id").map(|s| s.as_str()), Some("7"), "{line:?}");
+        assert_eq!(t.due_date, NaiveDate::from_ymd_opt(2023, 9, 1), "{line:?}");
+    }
+}
+
+#[test]
+fn set_recurrence_test() {
+    let base = NaiveDate::from_ymd_opt(2023, 8, 20).unwrap();
+    let weekly: Recurrence = "1w".parse().unwrap();
+    let monthly: Recurrence = "+1m".parse().unwrap();
+
+    let mut t = Task::parse("pay rent due:2023-09-01 +home", base);
+    assert!(!t.set_recurrence(None));
+    assert!(t.set_recurrence(Some(weekly)));
+    assert_eq!(t.recurrence, Some(weekly));
+    assert_eq!(t.subject, "pay rent due:2023-09-01 +home rec:1w");
+    assert!(!t.set_recurrence(Some(weekly)));
+
+    assert!(t.set_recurrence(Some(monthly)));
+    assert_eq!(t.recurrence, Some(monthly));
+    assert_eq!(t.subject, "pay rent due:2023-09-01 +home rec:+1m");
+    assert_eq!(Task::parse(&t.to_string(), base), t);
+
+    let mut t = Task::parse("pay rent rec:1m due:2023-09-01 +home", base);
+    assert!(t.set_recurrence(Some(monthly)));
+    assert_eq!(t.subject, "pay rent rec:+1m due:2023-09-01 +home");
+    assert!(t.set_recurrence(None));
+    assert_eq!(t.recurrence, None);
+    assert_eq!(t.subject, "pay rent due:2023-09-01 +home");
+    assert!(!t.tags.contains_key("rec"));
+    assert!(!t.set_recurrence(None));
+}
+
+#[test]
+fn quick_meta_test() {
+    let base = NaiveDate::from_ymd_opt(2023, 8, 20).unwrap();
+    let lines = [
+        "call mother",
+        "(A) call mother due:2023-09-01",
+        "x (B) 2023-08-20 2023-08-01 pay rent due:2023-08-20 rec:+1m",
+        "x 2023-08-20 pay taxes rec:1y",
+        "x 2023-08-20 (B) pay rent",
+        "x 2023-08-20 2023-08-01 (C) pay rent due:2023-08-25",
+        "2023-08-01 (A) call mother",
+        "\t(C)\tfix car\tdue:3d rec:2w@mon +car @garage",
+        "(a) lowercase is not a priority due:2023-31-01",
+        "due:2023-10-01 first due:2023-11-01 second",
+        "rec:xyz invalid recurrence",
+        "xmas vacations due:1m",
+        "",
+        "x",
+        "(D)",
+    ];
+    for line in lines {
+        let t = Task::parse(line, base);
+        let meta = quick_meta(line, base);
+        assert_eq!(meta.finished, t.finished, "{line:?}");
+        assert_eq!(meta.priority, t.priority, "{line:?}");
+        assert_eq!(meta.due, t.due_date, "{line:?}");
+        assert_eq!(meta.recurrence, t.recurrence, "{line:?}");
+    }
+}
+
+#[test]
+fn due_expr_anchor_test() {
+    let today = chrono::Local::now().date_naive();
+    let next_month =
+        NaiveDate::from_ymd_opt(today.year() + today.month() as i32 / 12, today.month() % 12 + 1, 1).unwrap();
+    let eom = next_month.pred_opt().unwrap();
+    let data = [("eom", eom), ("eom-2d", eom - chrono::Duration::days(2)), ("bom", today.with_day(1).unwrap())];
+    for (expr, dt) in data {
+        let mut tasks = vec![Task::parse("pay rent due:2000-01-15", today)];
+        let mut c = Conf::default();
+        c.due = DateTagChange { action: Action::Set, value: NewDateValue::Expr(expr.to_string()) };
+        let changed = edit(&mut tasks, None, &c);
+        assert_eq!(changed, vec![true], "{expr}");
+        assert_eq!(tasks[0].due_date, Some(dt), "{expr}");
+    }
+}
+
+#[test]
+fn due_phrase_test() {
+    let today = chrono::Local::now().date_naive();
+    let mut tasks = vec![Task::parse("pay rent", today)];
+    let mut c = Conf::default();
+    c.due = DateTagChange { action: Action::Set, value: NewDateValue::Expr("in 3 days".to_string()) };
+    let changed = edit(&mut tasks, None, &c);
+    assert_eq!(changed, vec![true]);
+    assert_eq!(tasks[0].due_date, Some(today + chrono::Duration::days(3)));
+}
+
+#[test]
+fn due_end_of_week_test() {
+    use todo_lib::human_date::{this_week, WeekStart};
+
+    let today = chrono::Local::now().date_naive();
+    let mut c = Conf::default();
+    c.due = DateTagChange { action: Action::Set, value: NewDateValue::Expr("end of week".to_string()) };
+    for ws in [WeekStart::Monday, WeekStart::Sunday] {
+        c.week_start = ws;
+        let mut tasks = vec![Task::parse("pay rent", today)];
+        edit(&mut tasks, None, &c);
+        let (first, last) = this_week(today, ws);
+        assert_eq!(tasks[0].due_date, Some(last), "{ws:?}");
+        assert_eq!(last.weekday(), ws.last_weekday());
+        assert_eq!(first.weekday(), ws.first_weekday());
+        assert!(first <= today && today <= last);
+    }
+}
+
+#[test]
+fn content_hash_test() {
+    let base = NaiveDate::from_ymd_opt(2023, 5, 10).unwrap();
+    let t1 = Task::parse("(B) 2023-05-01 pay rent +home due:2023-06-01", base);
+    let t2 = Task::parse("(B) 2023-05-01 pay rent +home due:2023-06-01", base);
+    assert_eq!(t1.content_hash(false), t2.content_hash(false));
+    assert_eq!(t1.content_hash(false), t1.clone().content_hash(false));
+
+    let mut t3 = t2.clone();
+    t3.priority = 0;
+    assert_ne!(t1.content_hash(false), t3.content_hash(false));
+    let mut t3 = t2.clone();
+    t3.update_tag_with_value("due", "2023-06-02");
+    assert_ne!(t1.content_hash(false), t3.content_hash(false));
+
+    let mut running = t2.clone();
+    assert!(todo_lib::timer::start_timer(&mut running));
+    assert_ne!(t1.content_hash(false), running.content_hash(false));
+    assert_eq!(t1.content_hash(true), running.content_hash(true));
+}
+
+#[test]
+fn date_predicates_test() {
+    let base = NaiveDate::from_ymd_opt(2023, 5, 10).unwrap();
+    let d = |m, d| NaiveDate::from_ymd_opt(2023, m, d).unwrap();
+    let t = Task::parse("2023-05-01 pay rent due:2023-06-01", base);
+    assert!(t.created_before(d(5, 2)));
+    assert!(!t.created_before(d(5, 1)));
+    assert!(t.created_after(d(4, 30)));
+    assert!(!t.created_after(d(5, 1)));
+    assert!(t.due_before(d(6, 2)));
+    assert!(!t.due_before(d(6, 1)));
+    assert!(t.due_after(d(5, 31)));
+    assert!(!t.due_after(d(6, 1)));
+    assert!(t.created_after(d(4, 1)) && t.due_before(d(7, 1)));
+
+    // absent dates never match
+    let t = Task::parse("pay rent", base);
+    for date in [d(1, 1), d(12, 31)] {
+        assert!(!t.created_before(date));
+        assert!(!t.created_after(date));
+        assert!(!t.due_before(date));
+        assert!(!t.due_after(date));
+    }
+}
+
+#[test]
+fn overdue_test() {
+    let today = NaiveDate::from_ymd_opt(2023, 5, 10).unwrap();
+    struct Test {
+        i: &'static str,
+        overdue: bool,
+        due_days: Option<i64>,
+        thr_overdue: bool,
+        thr_days: Option<i64>,
+    }
+    let data: Vec<Test> = vec![
+        Test {
+            i: "past due:2023-05-01 t:2023-04-30",
+            overdue: true,
+            due_days: Some(-9),
+            thr_overdue: true,
+            thr_days: Some(-10),
+        },
+        Test {
+            i: "today due:2023-05-10 t:2023-05-10",
+            overdue: false,
+            due_days: Some(0),
+            thr_overdue: false,
+            thr_days: Some(0),
+        },
+        Test {
+            i: "future due:2023-06-01 t:2023-05-11",
+            overdue: false,
+            due_days: Some(22),
+            thr_overdue: false,
+            thr_days: Some(1),
+        },
+        Test { i: "no dates", overdue: false, due_days: None, thr_overdue: false, thr_days: None },
+        Test {
+            i: "x done due:2023-05-01 t:2023-04-30",
+            overdue: false,
+            due_days: Some(-9),
+            thr_overdue: false,
+            thr_days: Some(-10),
+        },
+    ];
+    for d in data.iter() {
+        let t = Task::parse(d.i, today);
+        assert_eq!(t.is_overdue(today), d.overdue, "{}", d.i);
+        assert_eq!(t.days_until_due(today), d.due_days, "{}", d.i);
+        assert_eq!(t.is_threshold_overdue(today), d.thr_overdue, "{}", d.i);
+        assert_eq!(t.days_until_threshold(today), d.thr_days, "{}", d.i);
+    }
+}
+
+#[test]
+fn restore_priority_from_tag_test() {
+    let base = NaiveDate::from_ymd_opt(2020, 2, 2).unwrap();
+    let mut t = Task::parse("(B) 2020-01-01 testb", base);
+    let cc = CompletionConfig {
+        completion_mode: CompletionMode::PriorityToTag,
+        completion_date_mode: CompletionDateMode::WhenCreationDateIsPresent,
+    };
+    t.complete_with_config(base, cc);
+    let line = t.to_string();
+    assert_eq!(line, "x 2020-02-02 2020-01-01 testb pri:B");
+
+    let mut t = Task::parse(&line, base);
+    assert_eq!(t.priority, todo_lib::todotxt::NO_PRIORITY);
+    assert!(t.restore_priority_from_tag());
+    assert_eq!(t.priority, 1);
+    assert_eq!(t.tags.get("pri"), Some(&"B".to_string()));
+    assert!(!t.restore_priority_from_tag());
+    t.uncomplete(CompletionMode::PriorityToTag);
+    assert_eq!(t.to_string(), "(B) 2020-01-01 testb");
+
+    // a user tag that is not a priority
+    let mut t = Task::parse("x 2020-02-02 testc pri:high", base);
+    assert!(!t.restore_priority_from_tag());
+    assert_eq!(t.priority, todo_lib::todotxt::NO_PRIORITY);
+    t.uncomplete(CompletionMode::PriorityToTag);
+    assert_eq!(t.to_string(), "testc pri:high");
+
+    // incomplete task
+    let mut t = Task::parse("testd pri:A", base);
+    assert!(!t.restore_priority_from_tag());
+    assert_eq!(t.priority, todo_lib::todotxt::NO_PRIORITY);
+}
+
+#[test]
+fn parse_restore_priority_test() {
+    let base = NaiveDate::from_ymd_opt(2020, 2, 2).unwrap();
+    let opts = ParseOptions { restore_priority: true, ..Default::default() };
+    let mut t = Task::parse("(B) 2020-01-01 testb", base);
+    let cc = CompletionConfig {
+        completion_mode: CompletionMode::PriorityToTag,
+        completion_date_mode: CompletionDateMode::WhenCreationDateIsPresent,
+    };
+    t.complete_with_config(base, cc);
+    let line = t.to_string();
+    assert_eq!(line, "x 2020-02-02 2020-01-01 testb pri:B");
+
+    let t = Task::parse_with_options(&line, base, &ParseOptions::default());
+    assert_eq!(t, Task::parse(&line, base));
+    let mut t = Task::parse_with_options(&line, base, &opts);
+    assert_eq!(t.priority, 1);
+    assert_eq!(t.tags.get("pri"), Some(&"B".to_string()));
+    assert_eq!(t.to_string(), "x (B) 2020-02-02 2020-01-01 testb pri:B");
+    t.uncomplete(CompletionMode::PriorityToTag);
+    assert_eq!(t.to_string(), "(B) 2020-01-01 testb");
+
+    // a user tag that is not a priority
+    let t = Task::parse_with_options("x 2020-02-02 testc pri:high", base, &opts);
+    assert_eq!(t.priority, todo_lib::todotxt::NO_PRIORITY);
+    assert_eq!(t.to_string(), "x 2020-02-02 testc pri:high");
+}
+
+#[test]
+fn set_create_date_test() {
+    let base = NaiveDate::from_ymd_opt(2020, 2, 2).unwrap();
+    let mut t = Task::parse("x 2020-01-20 2020-01-10 call mother", base);
+
+    let valid = NaiveDate::from_ymd_opt(2020, 1, 15);
+    assert!(t.set_create_date(valid).unwrap());
+    assert_eq!(t.create_date, valid);
+    assert!(!t.set_create_date(valid).unwrap());
+    let same_day = NaiveDate::from_ymd_opt(2020, 1, 20);
+    assert!(t.set_create_date(same_day).unwrap());
+    assert_eq!(t.to_string(), "x 2020-01-20 2020-01-20 call mother");
+
+    let late = NaiveDate::from_ymd_opt(2020, 1, 21);
+    assert!(t.set_create_date(late).is_err());
+    assert_eq!(t.create_date, same_day);
+
+    assert!(t.set_create_date(None).unwrap());
+    assert_eq!(t.create_date, None);
+    assert_eq!(t.to_string(), "x 2020-01-20 call mother");
+
+    // without completion date any date is fine
+    let mut t = Task::parse("call father", base);
+    assert!(t.set_create_date(late).unwrap());
+    assert_eq!(t.to_string(), "2020-01-21 call father");
+}
+
+#[test]
+fn subject_without_priority_text_test() {
+    let base = NaiveDate::from_ymd_opt(2020, 2, 2).unwrap();
+    let cfg = CompletionConfig {
+        completion_mode: CompletionMode::MovePriority,
+        completion_date_mode: CompletionDateMode::AlwaysSet,
+    };
+    let mut t = Task::parse("(B) call mother +family", base);
+    assert_eq!(t.subject_without_priority_text(), ("call mother +family", None));
+    assert!(t.complete_with_config(base, cfg));
+    assert_eq!(t.subject, "(B) call mother +family");
+    assert_eq!(t.subject_without_priority_text(), ("call mother +family", Some('B')));
+
+    let cfg = CompletionConfig {
+        completion_mode: CompletionMode::MovePriority,
+        completion_date_mode: CompletionDateMode::AlwaysSet,
+    };
+    let mut t = Task::parse("call father", base);
+    assert!(t.complete_with_config(base, cfg));
+    assert_eq!(t.subject_without_priority_text(), ("call father", None));
+
+    let t = Task::parse("x 2020-01-01 (b) lowercase is not a priority", base);
+    assert_eq!(t.subject_without_priority_text(), ("(b) lowercase is not a priority", None));
+    let t = Task::parse("(A) (C) incomplete task", base);
+    assert_eq!(t.subject_without_priority_text(), ("(C) incomplete task", None));
+}
+
+#[test]
+fn has_future_threshold_test() {
+    let today = NaiveDate::from_ymd_opt(2023, 5, 10).unwrap();
+    let data = [
+        ("past t:2023-05-09", false),
+        ("today t:2023-05-10", false),
+        ("future t:2023-05-11", true),
+        ("x 2023-05-01 completed future t:2023-06-01", true),
+        ("no threshold due:2023-06-01", false),
+    ];
+    for (s, exp) in data {
+        let t = Task::parse(s, today);
+        assert_eq!(t.has_future_threshold(today), exp, "{s}");
+    }
+}
+
+#[test]
+fn to_string_compact_test() {
+    let base = NaiveDate::from_ymd_opt(2020, 2, 2).unwrap();
+    let mut t = Task::parse("(A) 2020-01-01 call mother +family due:2020-02-10", base);
+    t.subject = " call  mother   +family  due:2020-02-10 ".to_string();
+    assert_eq!(t.to_string_compact(), "(A) 2020-01-01 call mother +family due:2020-02-10");
+    assert_eq!(t.subject, " call  mother   +family  due:2020-02-10 ");
+    assert_eq!(t.to_string(), "(A) 2020-01-01  call  mother   +family  due:2020-02-10 ");
+
+    let t = Task::parse("x 2020-01-02 call father", base);
+    assert_eq!(t.to_string_compact(), t.to_string());
+}
+
+#[test]
+fn resync_from_subject_test() {
+    let base = NaiveDate::from_ymd_opt(2020, 2, 2).unwrap();
+    let mut t = Task::parse("(A) 2020-01-01 call mother +family @phone #call due:2020-02-10 rec:1w", base);
+    t.subject = "visit father +home +family @car #visit t:3d spent:10".to_string();
+    t.resync_from_subject(base);
+
+    let exp = Task::parse("(A) 2020-01-01 visit father +home +family @car #visit t:3d spent:10", base);
+    assert_eq!(t, exp);
+    assert_eq!(t.projects, vec!["home".to_string(), "family".to_string()]);
+    assert_eq!(t.contexts, vec!["car".to_string()]);
+    assert_eq!(t.hashtags, vec!["visit".to_string()]);
+    assert_eq!(t.due_date, None);
+    assert_eq!(t.recurrence, None);
+    assert_eq!(t.threshold_date, NaiveDate::from_ymd_opt(2020, 2, 5));
+    assert_eq!(t.subject, "visit father +home +family @car #visit t:2020-02-05 spent:10");
+    assert_eq!(t.tags.len(), 2);
+    assert_eq!(t.priority, 0);
+}
+
+#[test]
+fn list_count_test() {
+    let base = NaiveDate::from_ymd_opt(2020, 2, 2).unwrap();
+    let data = [
+        ("call mother", 0, 0),
+        ("call mother +family @phone", 1, 1),
+        ("fix car +car +garage +money @garage", 3, 1),
+        ("+a +b @c @d @e email + @ x", 2, 3),
+    ];
+    for (s, projects, contexts) in data {
+        let t = Task::parse(s, base);
+        assert_eq!(t.project_count(), projects, "{s}");
+        assert_eq!(t.context_count(), contexts, "{s}");
+    }
+}
+
+#[test]
+fn reconcile_priority_tag_test() {
+    let base = NaiveDate::from_ymd_opt(2020, 2, 2).unwrap();
+    let line = "x 2020-02-02 2020-01-01 testb pri:B";
+
+    let mut t = Task::parse(line, base);
+    assert!(t.reconcile_priority_tag(true));
+    assert_eq!(t.priority, 1);
+    assert_eq!(t.to_string(), "x (B) 2020-02-02 2020-01-01 testb pri:B");
+    t.uncomplete(CompletionMode::PriorityToTag);
+    assert_eq!(t.to_string(), "(B) 2020-01-01 testb");
+
+    let mut t = Task::parse(line, base);
+    assert!(t.reconcile_priority_tag(false));
+    assert_eq!(t.priority, 1);
+    assert!(!t.tags.contains_key("pri"));
+    assert_eq!(t.to_string(), "x (B) 2020-02-02 2020-01-01 testb");
+    assert!(!t.reconcile_priority_tag(false));
+    t.uncomplete(CompletionMode::PriorityToTag);
+    assert_eq!(t.to_string(), "(B) 2020-01-01 testb");
+
+    // nothing to reconcile
+    for s in ["testb pri:B", "x 2020-02-02 testc pri:high", "x (A) 2020-02-02 testd pri:B"] {
+        let mut t = Task::parse(s, base);
+        assert!(!t.reconcile_priority_tag(false), "{s}");
+        assert_eq!(t.to_string(), s);
+    }
+}
+
+#[test]
+fn equal_ignoring_test() {
+    let base = NaiveDate::from_ymd_opt(2020, 2, 2).unwrap();
+    let running = Task::parse("(A) fix car +car spent:120 tmr:1580000000 due:2020-02-10", base);
+    let stopped = Task::parse("(A) fix car +car spent:300 tmr:off due:2020-02-10", base);
+    let clean = Task::parse("(A) fix car +car due:2020-02-10", base);
+
+    assert_ne!(running, stopped);
+    assert!(!running.equal_ignoring(&stopped, &[]));
+    assert!(running.equal_ignoring(&stopped, &[IgnoredField::Timer]));
+    assert!(running.equal_ignoring(&clean, &[IgnoredField::Timer]));
+
+    // other differences are still detected
+    let other_due = Task::parse("(A) fix car +car tmr:off due:2020-02-11", base);
+    assert!(!running.equal_ignoring(&other_due, &[IgnoredField::Timer]));
+    let other_pri = Task::parse("(B) fix car +car due:2020-02-10", base);
+    assert!(!clean.equal_ignoring(&other_pri, &[IgnoredField::Timer]));
+    assert!(clean.equal_ignoring(&other_pri, &[IgnoredField::Priority]));
+
+    let done = Task::parse("x 2020-02-01 2020-01-01 fix car modified:2020-02-01", base);
+    let pending = Task::parse("2020-01-01 fix car modified:2020-01-15", base);
+    let ignored = [IgnoredField::Finished, IgnoredField::FinishDate];
+    assert!(!done.equal_ignoring(&pending, &ignored));
+    let ignored = [IgnoredField::Finished, IgnoredField::FinishDate, IgnoredField::Tag("modified".to_string())];
+    assert!(done.equal_ignoring(&pending, &ignored));
+    assert!(!done.equal_ignoring(&pending, &[IgnoredField::CreateDate, IgnoredField::Tag("modified".to_string())]));
+}
+
+#[test]
+fn priority_char_test() {
+    let base = NaiveDate::from_ymd_opt(2020, 2, 2).unwrap();
+    let mut t = Task::parse("(B) call mother", base);
+    assert_eq!(t.priority_char(), Some('B'));
+
+    for c in ['A', 'M', 'Z'] {
+        assert!(t.set_priority_char(Some(c)).is_ok());
+        assert_eq!(t.priority_char(), Some(c));
+    }
+    assert_eq!(t.to_string(), "(Z) call mother");
+
+    for c in ['a', '1', ' ', 'Я'] {
+        assert!(t.set_priority_char(Some(c)).is_err(), "{c}");
+        assert_eq!(t.priority_char(), Some('Z'));
+    }
+
+    assert!(t.set_priority_char(None).is_ok());
+    assert_eq!(t.priority_char(), None);
+    assert_eq!(t.priority, todo_lib::todotxt::NO_PRIORITY);
+    assert_eq!(t.to_string(), "call mother");
+
+    let t = Task::parse("call father", base);
+    assert_eq!(t.priority_char(), None);
+}
+
+#[test]
+fn strip_completion_test() {
+    struct Test {
+        i: &'static str,
+        m: CompletionMode,
+        o: &'static str,
+    }
+    let data: Vec<Test> = vec![
+        Test {
+            i: "x (B) 2020-02-02 2020-01-01 testa tmr:123 spent:10m",
+            m: CompletionMode::JustMark,
+            o: "(B) 2020-01-01 testa",
+        },
+        Test { i: "x 2020-02-02 2020-01-01 testb", m: CompletionMode::RemovePriority, o: "2020-01-01 testb" },
+        Test { i: "x 2020-02-02 2020-01-01 (C) testc", m: CompletionMode::MovePriority, o: "(C) 2020-01-01 testc" },
+        Test {
+            i: "x 2020-02-02 2020-01-01 testd pri:D spent:1h",
+            m: CompletionMode::PriorityToTag,
+            o: "(D) 2020-01-01 testd",
+        },
+        Test {
+            i: "x (A) 2020-02-02 2020-01-01 teste pri:D",
+            m: CompletionMode::PriorityToTag,
+            o: "(A) 2020-01-01 teste",
+        },
+        Test { i: "x testf pri:high", m: CompletionMode::PriorityToTag, o: "testf pri:high" },
+        Test { i: "x (A) testg pri:high", m: CompletionMode::PriorityToTag, o: "(A) testg pri:high" },
+    ];
+    let base = NaiveDate::from_ymd_opt(2020, 2, 2).unwrap();
+    for d in data.iter() {
+        let mut t = Task::parse(d.i, base);
+        assert!(t.strip_completion(d.m), "{}", d.i);
+        assert_eq!(t.to_string(), d.o, "{}", d.i);
+        assert!(!t.finished);
+        assert_eq!(t.finish_date, None);
+        assert!(!t.tags.contains_key("tmr") && !t.tags.contains_key("spent"), "{}", d.i);
+        assert!(!t.strip_completion(d.m), "{}", d.i);
+    }
+}
+
+#[test]
+fn plain_subject_test() {
+    let base = NaiveDate::from_ymd_opt(2020, 2, 2).unwrap();
+    let data = [
+        ("(A) 2020-01-01 call +family mom @phone due:2020-02-10 #urgent", "call mom", 2),
+        ("+proj @ctx", "", 0),
+        ("x 2020-01-02 fix car t:2020-01-01", "fix car", 2),
+        ("buy 2 + 2 @ items", "buy 2 + 2 @ items", 6),
+        ("", "", 0),
+    ];
+    for (s, plain, count) in data {
+        let t = Task::parse(s, base);
+        assert_eq!(t.plain_subject(), plain, "{s}");
+        assert_eq!(t.word_count(), count, "{s}");
+    }
+}
+
+#[test]
+fn due_threshold_gap_test() {
+    let base = NaiveDate::from_ymd_opt(2023, 5, 10).unwrap();
+    let data = [
+        ("both due:2023-06-01 t:2023-05-11", Some(21)),
+        ("same due:2023-06-01 t:2023-06-01", Some(0)),
+        ("inverted due:2023-05-01 t:2023-05-11", Some(-10)),
+        ("due only due:2023-06-01", None),
+        ("threshold only t:2023-06-01", None),
+        ("no dates", None),
+    ];
+    for (s, gap) in data {
+        let t = Task::parse(s, base);
+        assert_eq!(t.due_threshold_gap_days(), gap, "{s}");
+    }
+}
@@ -0,0 +1,39 @@
+use chrono::{DateTime, Duration, Utc};
+use todo_lib::{timer, todotxt};
+
+fn at(secs: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(secs, 0).unwrap()
+}
+
+#[test]
+fn timer_fixed_interval() {
+    let now = chrono::NaiveDate::from_ymd_opt(2023, 8, 20).unwrap();
+    let start = 1_692_500_000;
+    let mut t = todotxt::Task::parse("write report spent:100", now);
+
+    assert!(timer::start_timer_at(&mut t, at(start)));
+    assert!(timer::is_timer_on(&t));
+    assert_eq!(t.tags.get("tmr").map(|s| s.as_str()), Some("1692500000"));
+    assert!(!timer::start_timer_at(&mut t, at(start + 10)));
+
+    assert_eq!(timer::spent_time_at(&t, at(start + 1800)), Duration::seconds(1900));
+
+    assert!(timer::stop_timer_at(&mut t, at(start + 3600)));
+    assert!(!timer::is_timer_on(&t));
+    assert_eq!(t.subject, "write report spent:3700 tmr:off");
+    assert_eq!(timer::spent_time_at(&t, at(start + 7200)), Duration::seconds(3700));
+    assert!(!timer::stop_timer_at(&mut t, at(start + 7200)));
+}
+
+#[test]
+fn timer_clock_skew() {
+    let now = chrono::NaiveDate::from_ymd_opt(2023, 8, 20).unwrap();
+    let start = 1_692_500_000;
+    let mut t = todotxt::Task::parse("write report spent:100", now);
+    assert!(timer::start_timer_at(&mut t, at(start)));
+
+    // the clock went back: the elapsed time is not negative
+    assert_eq!(timer::spent_time_at(&t, at(start - 600)), Duration::seconds(100));
+    assert!(timer::stop_timer_at(&mut t, at(start - 600)));
+    assert_eq!(timer::spent_time(&t), Duration::seconds(100));
+}
@@ -179,7 +179,7 @@ fn tags() {
         let mp = extract_tags(d.i);
         assert_eq!(mp.len(), d.tag_n.len(), "{} - {:?}", d.i, mp);
         for (key, val) in d.tag_n.iter().zip(d.tag_v.iter()) {
-            let found = mp.get(&key.to_string());
+            let found = mp.get(*key);
             let vv = val.to_string();
             assert_eq!(Some(&vv), found, "{}", d.i);
         }
@@ -216,3 +216,210 @@ fn recurrents() {
         }
     }
 }
+
+#[test]
+fn recurrence_from_days() {
+    let tests: Vec<(u32, Option<&str>)> = vec![
+        (0, None),
+        (1, Some("rec:1d")),
+        (7, Some("rec:1w")),
+        (14, Some("rec:2w")),
+        (10, Some("rec:10d")),
+        (30, Some("rec:30d")),
+        (31, Some("rec:31d")),
+        (365, None),
+        (364, Some("rec:52w")),
+        (255, Some("rec:255d")),
+        (7 * 256, None),
+    ];
+    for (days, exp) in tests {
+        let r = Recurrence::from_days(days).map(|r| r.to_string());
+        assert_eq!(r.as_deref(), exp, "{days}");
+    }
+
+    let r = Recurrence::from_period(Period::Month, 3, true);
+    assert_eq!(r.to_string(), "rec:+3m");
+    assert_eq!(r, "+3m".parse::<Recurrence>().unwrap());
+}
+
+#[test]
+fn recurrence_describe() {
+    let tests: Vec<(&str, &str)> = vec![
+        ("1d", "every day"),
+        ("2d", "every 2 days"),
+        ("1w", "every week"),
+        ("2w", "every 2 weeks"),
+        ("1m", "every month"),
+        ("+1m", "every month (strict)"),
+        ("6m", "every 6 months"),
+        ("1y", "every year"),
+        ("+3y", "every 3 years (strict)"),
+        ("1b", "every business day"),
+        ("3b", "every 3 business days"),
+        ("rec:+2b", "every 2 business days (strict)"),
+    ];
+    for (rec, desc) in tests {
+        let r: Recurrence = rec.parse().unwrap();
+        assert_eq!(r.describe(), desc, "{rec}");
+    }
+}
+
+#[test]
+fn recurrence_business_month() {
+    let r: Recurrence = "1bm".parse().unwrap();
+    assert_eq!(r, Recurrence { period: Period::BusinessMonth, count: 1, strict: false });
+    assert_eq!(r.to_string(), "rec:1bm");
+    let r2: Recurrence = "rec:+2bm".parse().unwrap();
+    assert_eq!(r2, Recurrence { period: Period::BusinessMonth, count: 2, strict: true });
+    assert_eq!(r2.to_string(), "rec:+2bm");
+    assert!("bm".parse::<Recurrence>().is_err());
+
+    let d = |y: i32, m: u32, day: u32| NaiveDate::from_ymd_opt(y, m, day).unwrap();
+    let tests: Vec<(&Recurrence, NaiveDate, NaiveDate)> = vec![
+        // 2024-01-31 is Wednesday, 2024-02-29 (leap year) is Thursday
+        (&r, d(2024, 1, 31), d(2024, 2, 29)),
+        (&r, d(2024, 1, 15), d(2024, 2, 29)),
+        // 2024-03-31 is Sunday
+        (&r, d(2024, 2, 29), d(2024, 3, 29)),
+        // 2024-04-30 is Tuesday
+        (&r, d(2024, 3, 29), d(2024, 4, 30)),
+        // 2024-08-31 is Saturday
+        (&r, d(2024, 7, 31), d(2024, 8, 30)),
+        // 2025-02-28 is Friday
+        (&r, d(2025, 1, 31), d(2025, 2, 28)),
+        // year change: 2025-01-31 is Friday
+        (&r, d(2024, 12, 31), d(2025, 1, 31)),
+        // 2024-03-31 is Sunday, 2024-05-31 is Friday
+        (&r2, d(2024, 1, 31), d(2024, 3, 29)),
+        (&r2, d(2024, 3, 29), d(2024, 5, 31)),
+        (&r2, d(2024, 11, 29), d(2025, 1, 31)),
+    ];
+    for (rec, base, next) in tests {
+        assert_eq!(rec.next_date(base), next, "{rec} from {base}");
+    }
+    assert_eq!(r.describe(), "every month on the last business day");
+    assert_eq!(r2.describe(), "every 2 months on the last business day (strict)");
+}
+
+#[test]
+fn recurrence_weekday() {
+    // 2024-11-13 is Wednesday
+    let base = NaiveDate::from_ymd_opt(2024, 11, 13).unwrap();
+    let d = |day: u32| NaiveDate::from_ymd_opt(2024, 11, day).unwrap();
+    let tests: Vec<(&str, chrono::Weekday, NaiveDate)> = vec![
+        ("1w@mon", chrono::Weekday::Mon, d(18)),
+        ("1w@tue", chrono::Weekday::Tue, d(19)),
+        ("1w@wed", chrono::Weekday::Wed, d(20)),
+        ("1w@thu", chrono::Weekday::Thu, d(14)),
+        ("1w@fri", chrono::Weekday::Fri, d(15)),
+        ("1w@sat", chrono::Weekday::Sat, d(16)),
+        ("1w@sun", chrono::Weekday::Sun, d(17)),
+    ];
+    for (s, wd, next) in tests {
+        let r: Recurrence = s.parse().unwrap();
+        assert_eq!(r, Recurrence { period: Period::WeekDay(wd), count: 1, strict: false }, "{s}");
+        assert_eq!(r.to_string(), format!("rec:{s}"));
+        assert_eq!(r.next_date(base), next, "{s}");
+    }
+
+    let r: Recurrence = "rec:+2w@MON".parse().unwrap();
+    assert_eq!(r.to_string(), "rec:+2w@mon");
+    assert_eq!(r.next_date(base), d(25));
+    assert_eq!(r.next_date(d(18)), NaiveDate::from_ymd_opt(2024, 12, 2).unwrap());
+    assert_eq!(r.describe(), "every 2 weeks on Monday (strict)");
+
+    assert!("1m@mon".parse::<Recurrence>().is_err());
+    assert!("1w@monday".parse::<Recurrence>().is_err());
+    assert!("1w@xyz".parse::<Recurrence>().is_err());
+    assert!("1w@".parse::<Recurrence>().is_err());
+}
+
+#[test]
+fn recurrence_prev_date() {
+    let d = |y: i32, m: u32, day: u32| NaiveDate::from_ymd_opt(y, m, day).unwrap();
+    // next_date and prev_date are symmetric for dates that are not clamped
+    let recs = ["1d", "10d", "+1w", "3w", "1w@mon", "2w@fri", "1m", "+5m", "14m", "1y", "+2y", "1b", "3b", "7b"];
+    let dates = [d(2024, 1, 15), d(2024, 2, 29), d(2023, 12, 31), d(2024, 11, 13), d(2024, 6, 30)];
+    for r in recs {
+        let rec: Recurrence = r.parse().unwrap();
+        for dt in dates {
+            let next = rec.next_date(dt);
+            let base_ok = match rec.period {
+                Period::BusinessDay => dt.format("%a").to_string() != "Sat" && dt.format("%a").to_string() != "Sun",
+                Period::WeekDay(wd) => chrono::Datelike::weekday(&dt) == wd,
+                Period::Month | Period::Year => chrono::Datelike::day(&dt) <= 28,
+                _ => true,
+            };
+            if base_ok {
+                assert_eq!(rec.prev_date(next), dt, "{r}: {dt} -> {next}");
+            }
+        }
+    }
+
+    // month-end clamping
+    let tests: Vec<(&str, NaiveDate, NaiveDate)> = vec![
+        ("1m", d(2024, 3, 31), d(2024, 2, 29)),
+        ("1m", d(2023, 3, 31), d(2023, 2, 28)),
+        ("1m", d(2024, 1, 31), d(2023, 12, 31)),
+        ("1m", d(2024, 2, 29), d(2024, 1, 31)),
+        ("1m", d(2024, 5, 30), d(2024, 4, 30)),
+        ("3m", d(2024, 5, 31), d(2024, 2, 29)),
+        ("1y", d(2025, 2, 28), d(2024, 2, 29)),
+        ("1y", d(2024, 2, 29), d(2023, 2, 28)),
+        ("1b", d(2024, 11, 18), d(2024, 11, 15)),
+        ("1bm", d(2024, 4, 30), d(2024, 3, 29)),
+        ("2bm", d(2024, 1, 31), d(2023, 11, 30)),
+        ("1w@mon", d(2024, 11, 13), d(2024, 11, 11)),
+        ("1w@wed", d(2024, 11, 13), d(2024, 11, 6)),
+    ];
+    for (r, base, prev) in tests {
+        let rec: Recurrence = r.parse().unwrap();
+        assert_eq!(rec.prev_date(base), prev, "{r} from {base}");
+    }
+}
+
+#[test]
+fn replace_word_spacing() {
+    let data = [
+        ("due:1 a b", "due:1", "due:22", "due:22 a b"),
+        ("a due:1 b", "due:1", "", "a b"),
+        ("a  due:1  b", "due:1", "", "a b"),
+        ("a  due:1  b", "due:1", "due:2", "a  due:2  b"),
+        ("a b due:1", "due:1", "", "a b"),
+        ("due:1 due:1", "due:1", "", ""),
+        ("a due:1 due:1 b", "due:1", "x", "a x x b"),
+        ("a due:10 b", "due:1", "", "a due:10 b"),
+    ];
+    for (s, old, new, res) in data {
+        let mut st = s.to_string();
+        replace_word(&mut st, old, new);
+        assert_eq!(st, res, "{s:?}: {old} -> {new}");
+    }
+}
+
+#[test]
+fn replace_word_unicode() {
+    let data = [
+        ("buy beans +café @home", "+café", "+coffee", "buy beans +coffee @home"),
+        ("+café buy beans", "+café", "+咖啡馆", "+咖啡馆 buy beans"),
+        ("buy beans +咖啡馆", "+咖啡馆", "+ñ", "buy beans +ñ"),
+        ("+ñandú ver +café", "+café", "", "+ñandú ver"),
+        ("+ñandú ver +café", "+ñandú", "", "ver +café"),
+        ("über +café", "+caf", "+x", "über +café"),
+    ];
+    for (s, old, new, res) in data {
+        let mut st = s.to_string();
+        replace_word(&mut st, old, new);
+        assert_eq!(st, res, "{s:?}: {old} -> {new}");
+    }
+
+    let base = NaiveDate::from_ymd_opt(2020, 3, 15).unwrap();
+    let mut t = Task::parse("buy beans +café @кафе due:2020-04-01", base);
+    t.replace_project("café", "咖啡馆");
+    t.replace_context("кафе", "ñ");
+    assert_eq!(t.subject, "buy beans +咖啡馆 @ñ due:2020-04-01");
+    assert_eq!(t.projects, vec!["咖啡馆".to_string()]);
+    assert_eq!(t.contexts, vec!["ñ".to_string()]);
+    t.replace_project("咖啡馆", "");
+    assert_eq!(t.subject, "buy beans @ñ due:2020-04-01");
+}
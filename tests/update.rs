@@ -1,6 +1,7 @@
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use todo_lib::{
-    todo,
+    terr, tfilter, todo,
     todotxt::{self, CompletionConfig},
 };
 
@@ -62,6 +63,38 @@ fn add() {
     assert_eq!(n, orig_len);
 }
 
+#[test]
+fn add_with_defaults() {
+    let mut t: todo::TaskVec = Vec::new();
+    let mut c: todo::Conf = todo::Conf::default();
+    c.default_projects = vec!["home".to_string()];
+    c.default_contexts = vec!["errands".to_string()];
+    c.default_priority = 2;
+
+    c.subject = Some("buy milk".to_owned());
+    let n = todo::add(&mut t, &c);
+    assert_eq!(t[n].subject, "buy milk +home @errands");
+    assert_eq!(t[n].projects, vec!["home".to_string()]);
+    assert_eq!(t[n].contexts, vec!["errands".to_string()]);
+    assert_eq!(t[n].priority, 2);
+
+    c.subject = Some("(A) buy milk +shop @city".to_owned());
+    let n = todo::add(&mut t, &c);
+    assert_eq!(t[n].subject, "buy milk +shop @city");
+    assert_eq!(t[n].priority, 0);
+
+    c.subject = Some("buy milk @city".to_owned());
+    let n = todo::add(&mut t, &c);
+    assert_eq!(t[n].subject, "buy milk @city +home");
+    assert_eq!(t[n].contexts, vec!["city".to_string()]);
+
+    c = todo::Conf::default();
+    c.subject = Some("buy milk".to_owned());
+    let n = todo::add(&mut t, &c);
+    assert_eq!(t[n].subject, "buy milk");
+    assert_eq!(t[n].priority, todotxt::NO_PRIORITY);
+}
+
 #[test]
 fn done_test() {
     let mut t: Vec<todotxt::Task> = init_tasks();
@@ -89,13 +122,13 @@ fn done_test() {
     assert_eq!(changed, vec![true, false, true, true, false]);
     assert!(!t[2].finished);
     assert!(t[3].due_date == old_date);
-    for i in 0..5 {
-        assert!(i == 2 || t[i].finished);
-        assert!(i == 2 || t[i].finish_date.is_some())
+    for (i, task) in t.iter().enumerate().take(5) {
+        assert!(i == 2 || task.finished);
+        assert!(i == 2 || task.finish_date.is_some())
     }
     assert_eq!(t.len(), orig_len + must_change);
-    for idx in orig_len..orig_len + must_change {
-        assert!(!t[idx].finished);
+    for task in &t[orig_len..] {
+        assert!(!task.finished);
     }
 }
 
@@ -107,7 +140,7 @@ fn undone() {
     let changed = todo::undone(&mut t, Some(&ids), todotxt::CompletionMode::JustMark);
     assert_eq!(changed, vec![false, false, false]);
     assert!(t[1].finished);
-    assert!(!t[0].finished && !t[3].finished && !t[3].finished);
+    assert!(!t[0].finished && !t[3].finished);
 
     let ids: todo::IDVec = vec![0, 1, 3, 4, 10];
     let changed = todo::undone(&mut t, Some(&ids), todotxt::CompletionMode::JustMark);
@@ -116,6 +149,132 @@ fn undone() {
     assert!(!t[0].finished && !t[3].finished && !t[4].finished);
 }
 
+#[test]
+fn done_range_test() {
+    let completion_config = || CompletionConfig {
+        completion_mode: todotxt::CompletionMode::JustMark,
+        completion_date_mode: todotxt::CompletionDateMode::AlwaysSet,
+    };
+    let mut expected = init_tasks();
+    let exp_changed = todo::done(&mut expected, Some(&vec![0, 1, 2]), completion_config());
+
+    let mut t = init_tasks();
+    let changed = todo::done_range(&mut t, &tfilter::ItemRange::Range(0, 2), completion_config());
+    assert_eq!(changed, exp_changed);
+    assert_eq!(changed, vec![true, false, true]);
+    assert_eq!(t, expected);
+
+    // recurrent todo spawns a new one; IDs beyond the list are skipped
+    let mut t = init_tasks();
+    let changed = todo::done_range(&mut t, &tfilter::ItemRange::RangeExclusive(3, 10), completion_config());
+    assert_eq!(changed, vec![true, true, true]);
+    assert_eq!(t.len(), 7);
+    assert!(!t[6].finished);
+
+    let changed =
+        todo::undone_range(&mut t, &tfilter::ItemRange::List(vec![1, 4, 20]), todotxt::CompletionMode::JustMark);
+    assert_eq!(changed, vec![true, true]);
+    assert!(!t[1].finished && !t[4].finished && t[3].finished);
+}
+
+#[test]
+fn rec_remaining_test() {
+    let now = chrono::Local::now().date_naive();
+    let completion_config = || CompletionConfig {
+        completion_mode: todotxt::CompletionMode::JustMark,
+        completion_date_mode: todotxt::CompletionDateMode::AlwaysSet,
+    };
+    let mut t: todo::TaskVec = vec![
+        todotxt::Task::parse("water plants due:2020-01-01 rec:+1w*2", now),
+        todotxt::Task::parse("pay rent due:2020-01-01 rec:+1m", now),
+    ];
+    assert_eq!(t[0].rec_remaining(), Some(2));
+    assert_eq!(t[0].recurrence, Some("+1w".parse().unwrap()));
+    assert_eq!(t[1].rec_remaining(), None);
+
+    let changed = todo::done(&mut t, Some(&vec![0, 1]), completion_config());
+    assert_eq!(changed, vec![true, true]);
+    assert_eq!(t.len(), 4);
+    assert!(t[2].subject.ends_with(" rec:+1w*1"), "{}", t[2].subject);
+    assert_eq!(t[2].rec_remaining(), Some(1));
+    assert_eq!(t[3].rec_remaining(), None);
+
+    todo::done(&mut t, Some(&vec![2]), completion_config());
+    assert_eq!(t.len(), 5);
+    assert!(t[4].subject.ends_with(" rec:+1w*0"), "{}", t[4].subject);
+    assert_eq!(t[4].rec_remaining(), Some(0));
+
+    // no occurrences left
+    todo::done(&mut t, Some(&vec![4]), completion_config());
+    assert_eq!(t.len(), 5);
+    assert!(t[4].finished);
+
+    // `until:` stops the recurrence before the count is over
+    let mut t: todo::TaskVec =
+        vec![todotxt::Task::parse("water plants due:2020-01-01 rec:+1w*5 until:2020-01-05", now)];
+    todo::done(&mut t, None, completion_config());
+    assert_eq!(t.len(), 1);
+
+    let mut task = todotxt::Task::parse("water plants due:2020-01-01 rec:1w", now);
+    assert!(task.set_rec_remaining(3));
+    assert_eq!(task.subject, "water plants due:2020-01-01 rec:1w*3");
+    assert_eq!(task.rec_remaining(), Some(3));
+    let mut task = todotxt::Task::parse("water plants due:2020-01-01", now);
+    assert!(!task.set_rec_remaining(3));
+    assert!(todotxt::Task::parse("bad rec:1w*x", now).recurrence.is_none());
+
+    // the count limits the occurrences calculated from `until:`
+    let today = chrono::NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+    let task = todotxt::Task::parse("water plants due:2020-01-01 rec:+1w*2 until:2030-01-01", today);
+    assert_eq!(task.rec_remaining(), Some(2));
+    assert_eq!(task.remaining_occurrences(today), Some(2));
+    let task = todotxt::Task::parse("water plants due:2020-01-01 rec:+1w*9 until:2020-01-20", today);
+    assert_eq!(task.remaining_occurrences(today), Some(2));
+    // a todo without due and threshold dates never recurs
+    let task = todotxt::Task::parse("water plants rec:+1w*4", today);
+    assert_eq!(task.remaining_occurrences(today), None);
+
+    // editing the recurrence keeps the count
+    let mut t: todo::TaskVec = vec![todotxt::Task::parse("water plants due:2020-01-01 rec:+1w*3", now)];
+    let mut c = todo::Conf::default();
+    c.recurrence = todo::RecurrencyTagChange { action: todo::Action::Set, value: Some("2d".parse().unwrap()) };
+    assert_eq!(todo::edit(&mut t, None, &c), vec![true]);
+    assert_eq!(t[0].subject, "water plants due:2020-01-01 rec:2d*3");
+    assert_eq!(t[0].rec_remaining(), Some(3));
+}
+
+#[test]
+fn collect_recurrent_parents_test() {
+    let now = chrono::Local::now().date_naive();
+    let mut t: todo::TaskVec = vec![
+        todotxt::Task::parse("call mother", now),
+        todotxt::Task::parse("water plants due:2020-01-01 rec:1w", now),
+        todotxt::Task::parse("pay rent due:2020-01-01 rec:1m", now),
+    ];
+    let mut c = todo::Conf::default();
+
+    // the flag is not set: no parents are returned
+    let mut t2 = t.clone();
+    let (changed, parents) = todo::done_with_conf(&mut t2, Some(&vec![1]), &c);
+    assert_eq!(changed, vec![true]);
+    assert!(parents.is_empty());
+    assert_eq!(t2.len(), 4);
+
+    c.collect_recurrent_parents = true;
+    let (changed, parents) = todo::done_with_conf(&mut t, Some(&vec![2, 0, 1]), &c);
+    assert_eq!(changed, vec![true, true, true]);
+    assert_eq!(parents, vec![2, 1]);
+    assert_eq!(t.len(), 5);
+    assert!(t[1].finished && t[2].finished);
+
+    // the spawned todos stay active after parents are removed
+    todo::remove(&mut t, Some(&parents));
+    assert_eq!(t.len(), 3);
+    assert!(t[0].finished);
+    assert!(!t[1].finished && t[1].subject.starts_with("pay rent"));
+    assert!(!t[2].finished && t[2].subject.starts_with("water plants"));
+}
+
 #[test]
 fn remove() {
     let mut t = init_tasks();
@@ -145,9 +304,10 @@ fn recs() {
     let changed = todo::edit(&mut t, Some(&ids), &c);
     assert_eq!(changed, vec![false, false, false, true, false, false]);
 
+    // the old project name is matched case-insensitively
     c.projects = todo::ListTagChange { action: todo::Action::Replace, value: vec!["Family+People".to_string()] };
     let changed = todo::edit(&mut t, Some(&ids), &c);
-    assert_eq!(changed, vec![true, false, false, false, false, false]);
+    assert_eq!(changed, vec![true, true, false, false, false, true]);
 
     c.recurrence = todo::RecurrencyTagChange { action: todo::Action::Delete, value: None };
     let changed = todo::edit(&mut t, Some(&ids), &c);
@@ -240,9 +400,9 @@ fn tag_update_test() {
         c.tags = todo::TagValuesChange { action: tags_act, value: Some(hm) };
         let changed = todo::edit(&mut t, None, &c);
         if test.changes {
-            assert!(changed.len() > 0 && changed[0]);
+            assert!(!changed.is_empty() && changed[0]);
         } else {
-            assert!(changed.len() == 0 || !changed[0]);
+            assert!(changed.is_empty() || !changed[0]);
         }
         assert_eq!(test.res, &t[0].subject, "\n{}. {} != {}", idx, t[0].subject, test.res);
     }
@@ -313,8 +473,1042 @@ fn hashtags_test() {
         }
         c.hashtags = todo::ListTagChange { value: hvec, action: test.act };
         let changed = todo::edit(&mut t, None, &c);
-        assert!(changed.len() > 0, "{}. {}", idx, t[0].subject);
+        assert!(!changed.is_empty(), "{}. {}", idx, t[0].subject);
         assert_eq!(changed[0], test.changed, "{}. {}", idx, t[0].subject);
         assert_eq!(test.res, &t[0].subject, "\n{}. {} != {}", idx, t[0].subject, test.res);
     }
 }
+
+#[test]
+fn due_on_test() {
+    let base = chrono::NaiveDate::from_ymd_opt(2023, 5, 1).unwrap();
+    let t: todo::TaskVec = vec![
+        todotxt::Task::parse("pay rent due:2023-05-08", base),
+        todotxt::Task::parse("x 2023-05-01 done already due:2023-05-08", base),
+        todotxt::Task::parse("weekly meeting due:2023-05-01 rec:1w", base),
+        todotxt::Task::parse("monthly report due:2023-04-30 rec:1m", base),
+        todotxt::Task::parse("no due date rec:1d", base),
+        todotxt::Task::parse("future weekly due:2023-05-15 rec:1w", base),
+        todotxt::Task::parse("two weeks due:2023-05-01 rec:2w", base),
+    ];
+
+    let d = chrono::NaiveDate::from_ymd_opt(2023, 5, 8).unwrap();
+    assert_eq!(todo::due_on(&t, d), vec![0, 2]);
+
+    let d = chrono::NaiveDate::from_ymd_opt(2023, 5, 15).unwrap();
+    assert_eq!(todo::due_on(&t, d), vec![2, 5, 6]);
+
+    let d = chrono::NaiveDate::from_ymd_opt(2023, 6, 30).unwrap();
+    assert_eq!(todo::due_on(&t, d), vec![3]);
+
+    let d = chrono::NaiveDate::from_ymd_opt(2023, 5, 1).unwrap();
+    assert_eq!(todo::due_on(&t, d), vec![2, 6]);
+
+    // beyond the horizon
+    let d = chrono::NaiveDate::from_ymd_opt(2025, 5, 5).unwrap();
+    assert_eq!(todo::due_on(&t, d), Vec::<usize>::new());
+}
+
+#[test]
+fn warning_handler_test() {
+    let warnings: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let collected = Arc::clone(&warnings);
+    terr::set_warning_handler(move |msg| collected.lock().unwrap().push(msg.to_string()));
+
+    let mut t = init_tasks();
+    let mut c = todo::Conf::default();
+    c.due = todo::DateTagChange { action: todo::Action::Set, value: todo::NewDateValue::Expr("due*2".to_string()) };
+    let changed = todo::edit(&mut t, Some(&vec![2]), &c);
+    terr::reset_warning_handler();
+
+    assert_eq!(changed, vec![false]);
+    let warnings = warnings.lock().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("[due*2]"), "{}", warnings[0]);
+}
+
+#[test]
+fn save_sorted_tags_test() {
+    let now = chrono::NaiveDate::from_ymd_opt(2023, 8, 20).unwrap();
+    let t = todotxt::Task::parse("(A) 2023-08-01 call +family id:3 @home due:2023-09-01 area:home", now);
+    assert_eq!(t.to_string_with_tags_sorted(), "(A) 2023-08-01 call +family id:3 @home area:home due:2023-09-01");
+
+    let orig = "call mother +family @parents\n\
+                2023-08-01 pay rent area:home due:2023-09-01\n\
+                x 2023-08-02 2023-08-01 fix car +car id:5 @repair spent:30 tmr:off\n";
+    let filename = std::env::temp_dir().join(format!("todo_lib_sorted_{}.txt", std::process::id()));
+    std::fs::write(&filename, orig).unwrap();
+
+    let mut tasks = todo::load(&filename).unwrap();
+    tasks[1].update_tag_with_value("due", "2023-10-01");
+    tasks[1].update_tag_with_value("cost", "100");
+    let mut c = todo::Conf::default();
+    c.tag_order = todotxt::TagOrder::Alphabetical;
+    todo::save_with_conf(&tasks, &filename, &c).unwrap();
+    let saved = std::fs::read_to_string(&filename).unwrap();
+
+    let orig_lines: Vec<&str> = orig.lines().collect();
+    let saved_lines: Vec<&str> = saved.lines().collect();
+    assert_eq!(saved_lines.len(), 3);
+    assert_eq!(saved_lines[0], orig_lines[0]);
+    assert_eq!(saved_lines[1], "2023-08-01 pay rent area:home cost:100 due:2023-10-01");
+    assert_eq!(saved_lines[2], orig_lines[2]);
+
+    // saving again does not change anything
+    let tasks = todo::load(&filename).unwrap();
+    todo::save_with_conf(&tasks, &filename, &c).unwrap();
+    assert_eq!(std::fs::read_to_string(&filename).unwrap(), saved);
+    let _ = std::fs::remove_file(&filename);
+}
+
+#[test]
+fn save_original_tag_order_test() {
+    let orig = "call mother +family @parents due:2023-09-01 id:1\n\
+                2023-08-01 pay rent  due:2023-09-01 area:home\n\
+                x 2023-08-02 2023-08-01 fix car +car tmr:off id:5 @repair spent:30\n\
+                (B) book tickets t:2023-08-25 due:2023-08-30 cost:200 @phone\n";
+    let filename = std::env::temp_dir().join(format!("todo_lib_tag_order_{}.txt", std::process::id()));
+    std::fs::write(&filename, orig).unwrap();
+
+    let mut tasks = todo::load(&filename).unwrap();
+    tasks[1].update_tag_with_value("due", "2023-10-01");
+    tasks[1].update_tag_with_value("cost", "100");
+    let c = todo::Conf::default();
+    assert_eq!(c.tag_order, todotxt::TagOrder::Original);
+    todo::save_with_conf(&tasks, &filename, &c).unwrap();
+    let saved = std::fs::read_to_string(&filename).unwrap();
+
+    let orig_lines: Vec<&str> = orig.lines().collect();
+    let saved_lines: Vec<&str> = saved.lines().collect();
+    assert_eq!(saved_lines.len(), orig_lines.len());
+    for (idx, (o, s)) in orig_lines.iter().zip(saved_lines.iter()).enumerate() {
+        if idx != 1 {
+            assert_eq!(o.as_bytes(), s.as_bytes(), "line {idx}");
+        }
+    }
+    assert_eq!(saved_lines[1], "2023-08-01 pay rent due:2023-10-01 area:home cost:100");
+
+    let _ = std::fs::remove_file(&filename);
+}
+
+#[test]
+fn save_completion_date_first_test() {
+    let content = "x (B) 2023-08-20 2023-08-01 pay rent\nx (C) call mother\n(A) fix car\n";
+    let filename = std::env::temp_dir().join(format!("todo_lib_date_first_{}.txt", std::process::id()));
+    std::fs::write(&filename, content).unwrap();
+
+    let tasks = todo::load(&filename).unwrap();
+    todo::save(&tasks, &filename).unwrap();
+    assert_eq!(std::fs::read_to_string(&filename).unwrap(), content);
+
+    let mut c = todo::Conf::default();
+    c.completion_date_first = true;
+    todo::save_with_conf(&tasks, &filename, &c).unwrap();
+    let saved = std::fs::read_to_string(&filename).unwrap();
+    let _ = std::fs::remove_file(&filename);
+    assert_eq!(saved, "x 2023-08-20 2023-08-01 (B) pay rent\nx (C) call mother\n(A) fix car\n");
+}
+
+#[test]
+fn save_crlf_test() {
+    let filename = std::env::temp_dir().join(format!("todo_lib_crlf_{}.txt", std::process::id()));
+    let now = chrono::Local::now().date_naive();
+    let tasks: todo::TaskVec =
+        vec![todotxt::Task::parse("call mother +family", now), todotxt::Task::parse("(A) fix car", now)];
+
+    todo::save(&tasks, &filename).unwrap();
+    assert_eq!(std::fs::read(&filename).unwrap(), b"call mother +family\n(A) fix car\n");
+
+    let mut c = todo::Conf::default();
+    c.line_ending = todo::LineEnding::CrLf;
+    todo::save_with_conf(&tasks, &filename, &c).unwrap();
+    let saved = std::fs::read(&filename).unwrap();
+    assert_eq!(saved, b"call mother +family\r\n(A) fix car\r\n");
+
+    // CRLF files load back without `\r` in subjects
+    let loaded = todo::load(&filename).unwrap();
+    let _ = std::fs::remove_file(&filename);
+    assert_eq!(loaded, tasks);
+}
+
+#[test]
+fn default_archive_path_test() {
+    use std::path::{Path, PathBuf};
+    let tests: Vec<(&str, &str)> = vec![
+        ("todo.txt", "done.txt"),
+        ("/home/user/todo.txt", "/home/user/done.txt"),
+        ("/home/user/work.txt", "/home/user/done.txt"),
+        ("notes/work.md", "notes/done.md"),
+        ("/home/user/todo", "/home/user/done"),
+    ];
+    for (src, dst) in tests {
+        assert_eq!(todo::default_archive_path(Path::new(src)), PathBuf::from(dst), "{src}");
+    }
+
+    let dir = std::env::temp_dir().join(format!("todo_lib_archive_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let todo_path = dir.join("work.txt");
+    let tasks = init_tasks();
+    todo::archive_to_default(&tasks[..1], &todo_path).unwrap();
+    todo::archive_to_default(&tasks[1..2], &todo_path).unwrap();
+    let content = std::fs::read_to_string(dir.join("done.txt")).unwrap();
+    assert_eq!(content, format!("{}\n{}\n", tasks[0], tasks[1]));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn agenda_test() {
+    let today = chrono::NaiveDate::from_ymd_opt(2023, 8, 20).unwrap();
+    let lines = [
+        "call mother",                           // 0: no due
+        "(B) pay rent due:2023-08-15",           // 1: overdue
+        "(A) fix car due:2023-08-19",            // 2: overdue
+        "buy milk due:2023-08-20",               // 3: today
+        "(C) book tickets due:2023-08-22",       // 4: soon
+        "(A) dentist due:2023-08-23",            // 5: soon
+        "vacation due:2023-08-24",               // 6: later
+        "x 2023-08-18 pay taxes due:2023-08-18", // 7: done
+        "(A) read book",                         // 8: no due
+    ];
+    let tasks: todo::TaskVec = lines.iter().map(|l| todotxt::Task::parse(l, today)).collect();
+    assert_eq!(todo::due_status(&tasks[4], today, 3), todo::DueStatus::Soon);
+    assert_eq!(todo::due_status(&tasks[6], today, 3), todo::DueStatus::Later);
+    assert_eq!(todo::due_status(&tasks[6], today, 4), todo::DueStatus::Soon);
+
+    let agenda = todo::agenda(&tasks, today, 3);
+    assert_eq!(agenda.overdue, vec![2, 1]);
+    assert_eq!(agenda.today, vec![3]);
+    assert_eq!(agenda.soon, vec![5, 4]);
+    assert_eq!(agenda.later, vec![6]);
+    assert_eq!(agenda.no_due, vec![8, 0]);
+    assert_eq!(agenda.done, vec![7]);
+}
+
+#[test]
+fn load_with_report_test() {
+    let content = "call mother +family\n\
+                   pay rent due:2023-31-01\n\
+                   (A) 2023-08-01 fix car due:2023-09-01 rec:1m\n\
+                   water plants rec:often\n";
+    let filename = std::env::temp_dir().join(format!("todo_lib_report_{}.txt", std::process::id()));
+    std::fs::write(&filename, content).unwrap();
+    let (tasks, diags) = todo::load_with_report(&filename).unwrap();
+    let _ = std::fs::remove_file(&filename);
+
+    assert_eq!(tasks.len(), 4);
+    assert_eq!(tasks[1].subject, "pay rent due:2023-31-01");
+    assert_eq!(diags.len(), 2);
+    assert_eq!(diags[0].line, 1);
+    assert!(diags[0].message.contains("2023-31-01"), "{}", diags[0].message);
+    assert_eq!(diags[1].line, 3);
+    assert!(diags[1].message.contains("often"), "{}", diags[1].message);
+}
+
+#[test]
+fn load_with_options_test() {
+    let content = "# home\n\
+                   call mother +family\n\
+                   \x20 # work\n\
+                   (A) fix car @garage\n\
+                   #tagged todo\n\
+                   ; end\n";
+    let filename = std::env::temp_dir().join(format!("todo_lib_comments_{}.txt", std::process::id()));
+    std::fs::write(&filename, content).unwrap();
+
+    // default: every line is a todo
+    let (tasks, comments) = todo::load_with_options(&filename, &todo::LoadOptions::default()).unwrap();
+    assert_eq!(tasks.len(), 6);
+    assert!(comments.is_empty());
+
+    let opts = todo::LoadOptions { comment_prefix: Some("#".to_string()), ..Default::default() };
+    let (tasks, comments) = todo::load_with_options(&filename, &opts).unwrap();
+    assert_eq!(tasks.len(), 3);
+    assert_eq!(tasks[0].subject, "call mother +family");
+    assert_eq!(tasks[1].subject, "fix car @garage");
+    assert_eq!(tasks[2].subject, "; end");
+    assert_eq!(
+        comments,
+        vec![
+            todo::CommentLine { before: 0, text: "# home".to_string() },
+            todo::CommentLine { before: 1, text: "  # work".to_string() },
+            todo::CommentLine { before: 2, text: "#tagged todo".to_string() },
+        ]
+    );
+
+    let opts = todo::LoadOptions { comment_prefix: Some(";".to_string()), ..Default::default() };
+    let (tasks, comments) = todo::load_with_options(&filename, &opts).unwrap();
+    assert_eq!(tasks.len(), 5);
+    assert_eq!(comments, vec![todo::CommentLine { before: 5, text: "; end".to_string() }]);
+
+    // comments are written back to their places
+    let opts = todo::LoadOptions { comment_prefix: Some("#".to_string()), ..Default::default() };
+    let (tasks, comments) = todo::load_with_options(&filename, &opts).unwrap();
+    todo::save_with_comments(&tasks, &comments, &filename, &todo::Conf::default()).unwrap();
+    let saved = std::fs::read_to_string(&filename).unwrap();
+    let _ = std::fs::remove_file(&filename);
+    assert_eq!(saved, content);
+}
+
+#[test]
+fn load_with_base_test() {
+    let filename = std::env::temp_dir().join(format!("todo_lib_base_{}.txt", std::process::id()));
+    std::fs::write(&filename, "call mother due:2d\npay rent t:1w\n").unwrap();
+    let base = chrono::NaiveDate::from_ymd_opt(2020, 2, 27).unwrap();
+    let tasks = todo::load_with_base(&filename, base).unwrap();
+    let (opt_tasks, _) = todo::load_with_options_and_base(&filename, &todo::LoadOptions::default(), base).unwrap();
+    let (rep_tasks, _) = todo::load_with_report_and_base(&filename, base).unwrap();
+    let iter_tasks: Vec<todotxt::Task> = todo::load_iter_with_base(&filename, base).map(Result::unwrap).collect();
+    let _ = std::fs::remove_file(&filename);
+
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[0].due_date, chrono::NaiveDate::from_ymd_opt(2020, 2, 29));
+    assert_eq!(tasks[0].subject, "call mother due:2020-02-29");
+    assert_eq!(tasks[1].threshold_date, chrono::NaiveDate::from_ymd_opt(2020, 3, 5));
+    assert_eq!(opt_tasks, tasks);
+    assert_eq!(rep_tasks, tasks);
+    assert_eq!(iter_tasks, tasks);
+}
+
+#[test]
+fn load_iter_test() {
+    let filename = std::env::temp_dir().join(format!("todo_lib_iter_{}.txt", std::process::id()));
+    let mut content = String::new();
+    for i in 0..20_000 {
+        if i % 4 == 0 {
+            content.push_str(&format!("x 2023-08-20 2023-08-01 task {i} +done\n"));
+        } else {
+            content.push_str(&format!("(B) task {i} +active due:2023-09-01\n"));
+        }
+    }
+    std::fs::write(&filename, content).unwrap();
+    let mut total = 0;
+    let mut finished = 0;
+    for t in todo::load_iter(&filename) {
+        let t = t.unwrap();
+        total += 1;
+        if t.finished {
+            finished += 1;
+        }
+    }
+    let _ = std::fs::remove_file(&filename);
+    assert_eq!(total, 20_000);
+    assert_eq!(finished, 5_000);
+
+    let missing = std::env::temp_dir().join(format!("todo_lib_iter_missing_{}.txt", std::process::id()));
+    assert_eq!(todo::load_iter(&missing).count(), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn save_keeps_permissions_test() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let filename = std::env::temp_dir().join(format!("todo_lib_perm_{}.txt", std::process::id()));
+    std::fs::write(&filename, "call mother\n").unwrap();
+    std::fs::set_permissions(&filename, std::fs::Permissions::from_mode(0o600)).unwrap();
+    let mtime = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000);
+    std::fs::File::options().write(true).open(&filename).unwrap().set_modified(mtime).unwrap();
+
+    let tasks = init_tasks();
+    todo::save(&tasks, &filename).unwrap();
+    let meta = std::fs::metadata(&filename).unwrap();
+    assert_eq!(meta.permissions().mode() & 0o777, 0o600);
+    assert_ne!(meta.modified().unwrap(), mtime);
+
+    let mut c = todo::Conf::default();
+    c.preserve_mtime = true;
+    std::fs::File::options().write(true).open(&filename).unwrap().set_modified(mtime).unwrap();
+    todo::save_with_conf(&tasks, &filename, &c).unwrap();
+    let meta = std::fs::metadata(&filename).unwrap();
+    assert_eq!(meta.permissions().mode() & 0o777, 0o600);
+    assert_eq!(meta.modified().unwrap(), mtime);
+    let _ = std::fs::remove_file(&filename);
+}
+
+#[cfg(unix)]
+#[test]
+fn save_keeps_owner_test() {
+    use std::os::unix::fs::MetadataExt;
+
+    let filename = std::env::temp_dir().join(format!("todo_lib_owner_{}.txt", std::process::id()));
+    std::fs::write(&filename, "call mother\n").unwrap();
+    // changing the owner needs privileges: nothing to check without them
+    if std::os::unix::fs::chown(&filename, Some(1), Some(1)).is_err() {
+        let _ = std::fs::remove_file(&filename);
+        return;
+    }
+
+    todo::save(&init_tasks(), &filename).unwrap();
+    let meta = std::fs::metadata(&filename).unwrap();
+    assert_eq!((meta.uid(), meta.gid()), (1, 1));
+    let _ = std::fs::remove_file(&filename);
+}
+
+#[test]
+fn archive_completed_test() {
+    let now = chrono::Local::now().date_naive();
+    let mut tasks: todo::TaskVec = vec![
+        todotxt::Task::parse("call mother", now),
+        todotxt::Task::parse("x 2023-08-20 pay taxes", now),
+        todotxt::Task::parse("pay rent due:2023-09-01 rec:1m", now),
+    ];
+    let changed = todo::done(&mut tasks, Some(&vec![2]), CompletionConfig::default());
+    assert_eq!(changed, vec![true]);
+    assert_eq!(tasks.len(), 4);
+
+    let filename = std::env::temp_dir().join(format!("todo_lib_done_{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&filename);
+    let ids = todo::archive_completed(&mut tasks, &filename).unwrap();
+    let content = std::fs::read_to_string(&filename).unwrap();
+    let _ = std::fs::remove_file(&filename);
+
+    assert_eq!(ids, vec![1, 2]);
+    assert_eq!(content.lines().count(), 2);
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[0].subject, "call mother");
+    assert!(!tasks[1].finished);
+    assert!(tasks[1].recurrence.is_some());
+    assert!(tasks[1].due_date > chrono::NaiveDate::from_ymd_opt(2023, 9, 1));
+
+    // nothing to archive
+    let ids = todo::archive_completed(&mut tasks, &filename).unwrap();
+    assert!(ids.is_empty());
+    assert!(!filename.exists());
+}
+
+#[test]
+fn archive_dedup_test() {
+    let now = chrono::Local::now().date_naive();
+    let tasks: todo::TaskVec = vec![
+        todotxt::Task::parse("x 2023-08-20 pay taxes", now),
+        todotxt::Task::parse("x 2023-08-21 call mother", now),
+    ];
+    let filename = std::env::temp_dir().join(format!("todo_lib_dedup_{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&filename);
+
+    let ids = todo::archive_dedup(&tasks, &filename).unwrap();
+    assert_eq!(ids, vec![0, 1]);
+    // archiving the same todos again changes nothing
+    let ids = todo::archive_dedup(&tasks, &filename).unwrap();
+    assert!(ids.is_empty());
+
+    // comparison is case-sensitive; duplicates inside the list are written once
+    let more: todo::TaskVec = vec![
+        todotxt::Task::parse("x 2023-08-20 Pay taxes", now),
+        todotxt::Task::parse("x 2023-08-22 water plants", now),
+        todotxt::Task::parse("x 2023-08-22 water plants", now),
+        todotxt::Task::parse("x 2023-08-20 pay taxes", now),
+    ];
+    let ids = todo::archive_dedup(&more, &filename).unwrap();
+    assert_eq!(ids, vec![0, 1]);
+
+    let content = std::fs::read_to_string(&filename).unwrap();
+    let _ = std::fs::remove_file(&filename);
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "x 2023-08-20 pay taxes",
+            "x 2023-08-21 call mother",
+            "x 2023-08-20 Pay taxes",
+            "x 2023-08-22 water plants"
+        ]
+    );
+}
+
+#[test]
+fn date_span_test() {
+    let now = chrono::NaiveDate::from_ymd_opt(2023, 8, 20).unwrap();
+    let mut tasks: todo::TaskVec =
+        vec![todotxt::Task::parse("call mother", now), todotxt::Task::parse("pay rent +home", now)];
+    assert_eq!(todo::date_span(&tasks), None);
+    assert_eq!(todo::date_span(&[]), None);
+
+    tasks.push(todotxt::Task::parse("fix car due:2023-09-01", now));
+    let d = chrono::NaiveDate::from_ymd_opt(2023, 9, 1).unwrap();
+    assert_eq!(todo::date_span(&tasks), Some((d, d)));
+
+    tasks.push(todotxt::Task::parse("x 2023-08-10 2023-07-05 pay taxes", now));
+    tasks.push(todotxt::Task::parse("2023-08-01 buy tickets due:2023-10-01 t:2023-12-24", now));
+    let first = chrono::NaiveDate::from_ymd_opt(2023, 7, 5).unwrap();
+    let last = chrono::NaiveDate::from_ymd_opt(2023, 12, 24).unwrap();
+    assert_eq!(todo::date_span(&tasks), Some((first, last)));
+}
+
+#[test]
+fn carry_forward_test() {
+    let now = chrono::NaiveDate::from_ymd_opt(2023, 8, 20).unwrap();
+    let from = chrono::NaiveDate::from_ymd_opt(2023, 8, 19).unwrap();
+    let mut tasks: todo::TaskVec = vec![
+        todotxt::Task::parse("call mother due:2023-08-19", now),
+        todotxt::Task::parse("pay rent due:2023-08-18", now),
+        todotxt::Task::parse("x 2023-08-19 pay taxes due:2023-08-19", now),
+        todotxt::Task::parse("buy milk", now),
+        todotxt::Task::parse("fix car due:2023-08-19 +car", now),
+    ];
+    let changed = todo::carry_forward(&mut tasks, from, now);
+    assert_eq!(changed, vec![true, false, false, false, true]);
+    assert_eq!(tasks[0].due_date, Some(now));
+    assert_eq!(tasks[0].subject, "call mother due:2023-08-20");
+    assert_eq!(tasks[1].due_date, chrono::NaiveDate::from_ymd_opt(2023, 8, 18));
+    assert_eq!(tasks[2].due_date, Some(from));
+    assert_eq!(tasks[3].due_date, None);
+    assert_eq!(tasks[4].subject, "fix car due:2023-08-20 +car");
+
+    let changed = todo::carry_forward(&mut tasks, from, now);
+    assert_eq!(changed, vec![false; 5]);
+}
+
+#[test]
+fn done_preview_test() {
+    let today = chrono::NaiveDate::from_ymd_opt(2023, 8, 20).unwrap();
+    let mut tasks: todo::TaskVec = vec![
+        todotxt::Task::parse("call mother", today),
+        todotxt::Task::parse("pay rent due:2023-08-20 rec:+1m until:2023-12-31", today),
+        todotxt::Task::parse("water plants due:2023-08-20 rec:+1w until:2023-08-25", today),
+        todotxt::Task::parse("x 2023-08-19 pay taxes due:2023-08-19 rec:1y", today),
+        todotxt::Task::parse("walk dog due:2023-08-20 rec:1d", today),
+    ];
+    let preview = todo::done_preview(&tasks, None, today);
+    let spawn = |d: Option<chrono::NaiveDate>| todo::DonePreview { will_spawn: true, next_due: d };
+    let none = todo::DonePreview { will_spawn: false, next_due: None };
+    assert_eq!(
+        preview,
+        vec![
+            none.clone(),
+            spawn(chrono::NaiveDate::from_ymd_opt(2023, 9, 20)),
+            none.clone(),
+            none.clone(),
+            spawn(chrono::NaiveDate::from_ymd_opt(2023, 8, 21)),
+        ]
+    );
+    let preview = todo::done_preview(&tasks, Some(&vec![2, 10]), today);
+    assert_eq!(preview, vec![none.clone(), none.clone()]);
+
+    // the list is not changed by preview
+    assert_eq!(tasks.len(), 5);
+
+    // done agrees with preview
+    let now = chrono::Local::now().date_naive();
+    let due = todotxt::format_date(now);
+    let until = todotxt::format_date(now + chrono::Duration::days(3));
+    tasks = vec![
+        todotxt::Task::parse(&format!("pay rent due:{due} rec:+1d until:{until}"), now),
+        todotxt::Task::parse(&format!("water plants due:{due} rec:+1w until:{until}"), now),
+    ];
+    let preview = todo::done_preview(&tasks, None, now);
+    assert_eq!(preview, vec![spawn(Some(now + chrono::Duration::days(1))), none]);
+    todo::done(&mut tasks, None, CompletionConfig::default());
+    assert_eq!(tasks.len(), 3);
+    assert_eq!(tasks[2].due_date, Some(now + chrono::Duration::days(1)));
+}
+
+#[test]
+fn edit_with_report_test() {
+    let now = chrono::Local::now().date_naive();
+    let mut tasks: todo::TaskVec = vec![
+        todotxt::Task::parse("pay rent due:2020-10-09", now),
+        todotxt::Task::parse("call mother", now),
+        todotxt::Task::parse("fix car due:2020-10-01 t:2020-09-20", now),
+    ];
+    let mut c = todo::Conf::default();
+    c.due = todo::DateTagChange { action: todo::Action::Set, value: todo::NewDateValue::Expr("due+1d".to_string()) };
+    c.priority = todo::PriorityTagChange { action: todo::Action::Set, value: 1 };
+    let ids = vec![0, 1, 2];
+    let (changed, errors) = todo::edit_with_report(&mut tasks, Some(&ids), &c);
+    assert_eq!(changed, vec![true, true, true]);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, 1);
+    assert!(errors[0].1.contains("due+1d"), "{}", errors[0].1);
+    assert_eq!(tasks[0].due_date, chrono::NaiveDate::from_ymd_opt(2020, 10, 10));
+    assert_eq!(tasks[1].due_date, None);
+    assert_eq!(tasks[1].priority, 1);
+    assert_eq!(tasks[2].due_date, chrono::NaiveDate::from_ymd_opt(2020, 10, 2));
+
+    c.due = todo::DateTagChange { action: todo::Action::Set, value: todo::NewDateValue::Expr("t+1x".to_string()) };
+    let ids = vec![2];
+    let (changed, errors) = todo::edit_with_report(&mut tasks, Some(&ids), &c);
+    assert_eq!(changed, vec![false]);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, 2);
+}
+
+#[test]
+fn edit_detailed_test() {
+    let now = chrono::Local::now().date_naive();
+    let mut tasks: todo::TaskVec = vec![
+        todotxt::Task::parse("(A) pay rent +home due:2020-10-09", now),
+        todotxt::Task::parse("call mother due:2020-10-10", now),
+    ];
+    let mut c = todo::Conf::default();
+    c.due = todo::DateTagChange {
+        action: todo::Action::Set,
+        value: todo::NewDateValue::Date(chrono::NaiveDate::from_ymd_opt(2020, 10, 10).unwrap()),
+    };
+    let changed = todo::edit_detailed(&mut tasks, None, &c);
+    let due_only = todo::ChangedFields { due: true, ..Default::default() };
+    assert_eq!(changed, vec![due_only, todo::ChangedFields::default()]);
+    assert!(changed[0].any());
+    assert!(!changed[1].any());
+
+    c.priority = todo::PriorityTagChange { action: todo::Action::Set, value: 1 };
+    c.projects = todo::ListTagChange { action: todo::Action::Set, value: vec!["family".to_string()] };
+    let changed = todo::edit_detailed(&mut tasks, Some(&vec![1]), &c);
+    assert_eq!(changed, vec![todo::ChangedFields { priority: true, projects: true, ..Default::default() }]);
+
+    let mut c = todo::Conf::default();
+    c.subject = Some("call father".to_string());
+    let changed = todo::edit_detailed(&mut tasks, Some(&vec![1]), &c);
+    assert_eq!(changed, vec![todo::ChangedFields { subject: true, ..Default::default() }]);
+}
+
+#[test]
+fn priority_step_test() {
+    let now = chrono::Local::now().date_naive();
+    let mut tasks: todo::TaskVec =
+        ["(D) a", "(B) b", "c", "(Y) d", "(A) e", "(Z) f"].iter().map(|s| todotxt::Task::parse(s, now)).collect();
+    let pri = |tasks: &todo::TaskVec| tasks.iter().map(|t| t.priority_char()).collect::<Vec<_>>();
+    let mut c = todo::Conf::default();
+
+    c.priority = todo::PriorityTagChange { action: todo::Action::IncreaseBy(2), ..Default::default() };
+    let changed = todo::edit(&mut tasks, None, &c);
+    assert_eq!(changed, vec![true, true, true, true, false, true]);
+    assert_eq!(pri(&tasks), vec![Some('B'), Some('A'), Some('Y'), Some('W'), Some('A'), Some('X')]);
+
+    c.priority = todo::PriorityTagChange { action: todo::Action::IncreaseBy(5), ..Default::default() };
+    todo::edit(&mut tasks, None, &c);
+    assert_eq!(pri(&tasks), vec![Some('A'), Some('A'), Some('T'), Some('R'), Some('A'), Some('S')]);
+
+    c.priority = todo::PriorityTagChange { action: todo::Action::DecreaseBy(5), ..Default::default() };
+    todo::edit(&mut tasks, None, &c);
+    assert_eq!(pri(&tasks), vec![Some('F'), Some('F'), Some('Y'), Some('W'), Some('F'), Some('X')]);
+
+    c.priority = todo::PriorityTagChange { action: todo::Action::DecreaseBy(2), ..Default::default() };
+    let changed = todo::edit(&mut tasks, None, &c);
+    assert_eq!(changed, vec![true; 6]);
+    assert_eq!(pri(&tasks), vec![Some('H'), Some('H'), None, Some('Y'), Some('H'), Some('Z')]);
+
+    // no priority is not decreased; a step 0 works as 1
+    c.priority = todo::PriorityTagChange { action: todo::Action::DecreaseBy(0), ..Default::default() };
+    let changed = todo::edit(&mut tasks, Some(&vec![2, 5]), &c);
+    assert_eq!(changed, vec![false, true]);
+    assert_eq!(pri(&tasks), vec![Some('H'), Some('H'), None, Some('Y'), Some('H'), None]);
+    c.priority = todo::PriorityTagChange { action: todo::Action::IncreaseBy(0), ..Default::default() };
+    todo::edit(&mut tasks, Some(&vec![2]), &c);
+    assert_eq!(tasks[2].priority_char(), Some('Z'));
+}
+
+#[test]
+fn prepend_lists_test() {
+    let now = chrono::Local::now().date_naive();
+    let tasks: todo::TaskVec = vec![
+        todotxt::Task::parse("(B) 2023-08-01 fix brakes @garage due:2023-09-01", now),
+        todotxt::Task::parse("buy tires +car", now),
+    ];
+    let mut c = todo::Conf::default();
+    c.projects = todo::ListTagChange { action: todo::Action::Set, value: vec!["car".to_string()] };
+    c.contexts = todo::ListTagChange { action: todo::Action::Set, value: vec!["shop".to_string()] };
+
+    // default: append to the end
+    let mut t = tasks.clone();
+    let changed = todo::edit(&mut t, None, &c);
+    assert_eq!(changed, vec![true, true]);
+    assert_eq!(t[0].subject, "fix brakes @garage due:2023-09-01 +car @shop");
+
+    c.prepend_lists = true;
+    let mut t = tasks.clone();
+    let changed = todo::edit(&mut t, None, &c);
+    assert_eq!(changed, vec![true, true]);
+    assert_eq!(t[0].subject, "@shop +car fix brakes @garage due:2023-09-01");
+    assert_eq!(t[0].projects, vec!["car".to_string()]);
+    assert_eq!(t[0].contexts, vec!["shop".to_string(), "garage".to_string()]);
+    assert_eq!(t[0].to_string(), "(B) 2023-08-01 @shop +car fix brakes @garage due:2023-09-01");
+    assert_eq!(t[1].subject, "@shop buy tires +car");
+}
+
+#[test]
+fn ignore_case_dedupe_test() {
+    let now = chrono::Local::now().date_naive();
+    let mut tasks: todo::TaskVec = vec![todotxt::Task::parse("fix brakes +Car @Garage", now)];
+    let mut c = todo::Conf::default();
+    c.projects = todo::ListTagChange { action: todo::Action::Set, value: vec!["car".to_string()] };
+    c.contexts = todo::ListTagChange { action: todo::Action::Set, value: vec!["garage".to_string()] };
+
+    // default: different case means a different project
+    let mut t = tasks.clone();
+    let changed = todo::edit(&mut t, None, &c);
+    assert_eq!(changed, vec![true]);
+    assert_eq!(t[0].subject, "fix brakes +Car @Garage +car @garage");
+
+    c.ignore_case_dedupe = true;
+    let changed = todo::edit(&mut tasks, None, &c);
+    assert_eq!(changed, vec![false]);
+    assert_eq!(tasks[0].subject, "fix brakes +Car @Garage");
+    assert_eq!(tasks[0].projects, vec!["Car".to_string()]);
+
+    // replacing with an existing project removes the old one
+    tasks[0] = todotxt::Task::parse("fix brakes +Car +auto", now);
+    c.contexts = todo::ListTagChange::default();
+    c.projects = todo::ListTagChange { action: todo::Action::Replace, value: vec!["auto+car".to_string()] };
+    let changed = todo::edit(&mut tasks, None, &c);
+    assert_eq!(changed, vec![true]);
+    assert_eq!(tasks[0].subject, "fix brakes +Car");
+    assert_eq!(tasks[0].projects, vec!["Car".to_string()]);
+
+    // changing the case of a project is still possible
+    c.projects = todo::ListTagChange { action: todo::Action::Replace, value: vec!["Car+car".to_string()] };
+    todo::edit(&mut tasks, None, &c);
+    assert_eq!(tasks[0].subject, "fix brakes +car");
+}
+
+#[test]
+fn tag_update_spacing_test() {
+    struct Test {
+        subj: &'static str,
+        tag: &'static str,
+        value: &'static str,
+        res: &'static str,
+    }
+    let data: Vec<Test> = vec![
+        // first, middle, and last word; values of different length
+        Test {
+            subj: "item:ball take to who:me game",
+            tag: "item",
+            value: "puck-and-stick",
+            res: "item:puck-and-stick take to who:me game",
+        },
+        Test { subj: "item:ball take to who:me game", tag: "who", value: "x", res: "item:ball take to who:x game" },
+        Test { subj: "take to game who:me", tag: "who", value: "everybody", res: "take to game who:everybody" },
+        Test { subj: "who:me", tag: "who", value: "you", res: "who:you" },
+        // removing
+        Test { subj: "item:ball take to who:me game", tag: "item", value: "", res: "take to who:me game" },
+        Test { subj: "item:ball take to who:me game", tag: "who", value: "", res: "item:ball take to game" },
+        Test { subj: "take to game who:me", tag: "who", value: "", res: "take to game" },
+        Test { subj: "who:me", tag: "who", value: "", res: "" },
+        // extra spaces around the tag
+        Test { subj: "take  who:me  game", tag: "who", value: "", res: "take game" },
+        Test { subj: "take  who:me  game", tag: "who", value: "you", res: "take who:you game" },
+        // the same tag repeated
+        Test { subj: "take who:me who:me game", tag: "who", value: "you", res: "take who:you who:you game" },
+        Test { subj: "take who:me who:me game", tag: "who", value: "", res: "take game" },
+    ];
+    let now = chrono::Local::now().date_naive();
+    for test in data.iter() {
+        let mut t = todotxt::Task::parse(test.subj, now);
+        assert!(t.update_tag_with_value(test.tag, test.value), "{}", test.subj);
+        assert_eq!(t.subject, test.res, "{} -> {}:{}", test.subj, test.tag, test.value);
+    }
+
+    // due date in the middle of a subject
+    let mut t = vec![todotxt::Task::parse("pay due:2020-1-2 rent", now)];
+    let mut c = todo::Conf::default();
+    c.due = todo::DateTagChange {
+        action: todo::Action::Set,
+        value: todo::NewDateValue::Date(chrono::NaiveDate::from_ymd_opt(2020, 11, 12).unwrap()),
+    };
+    todo::edit(&mut t, None, &c);
+    assert_eq!(t[0].subject, "pay due:2020-11-12 rent");
+    c.due = todo::DateTagChange { action: todo::Action::Delete, value: todo::NewDateValue::None };
+    todo::edit(&mut t, None, &c);
+    assert_eq!(t[0].subject, "pay rent");
+}
+
+#[test]
+fn dry_run_test() {
+    let now = chrono::Local::now().date_naive();
+    let due = todotxt::format_date(now);
+    let next_due = todotxt::format_date(now.succ_opt().unwrap());
+    let tasks: todo::TaskVec = vec![
+        todotxt::Task::parse("call mother", now),
+        todotxt::Task::parse(&format!("walk dog due:{due} rec:1d"), now),
+    ];
+    let orig = tasks.clone();
+
+    let ids = vec![1];
+    let (changed, lines) = todo::dry_run(&tasks, |t| todo::done(t, Some(&ids), CompletionConfig::default()));
+    assert_eq!(changed, vec![true]);
+    assert_eq!(
+        lines,
+        vec![(1, format!("x walk dog due:{due} rec:1d")), (2, format!("walk dog due:{next_due} rec:1d"))]
+    );
+    assert_eq!(tasks, orig);
+
+    let (changed, lines) = todo::dry_run(&tasks, |t| todo::undone(t, None, todotxt::CompletionMode::JustMark));
+    assert_eq!(changed, vec![false, false]);
+    assert!(lines.is_empty());
+
+    let mut c = todo::Conf::default();
+    c.priority = todo::PriorityTagChange { action: todo::Action::Set, value: 0 };
+    let (changed, lines) = todo::dry_run(&tasks, |t| todo::edit(t, Some(&vec![0]), &c));
+    assert_eq!(changed, vec![true]);
+    assert_eq!(lines, vec![(0, "(A) call mother".to_string())]);
+    assert_eq!(tasks, orig);
+}
+
+#[test]
+fn add_with_base_test() {
+    let base = chrono::NaiveDate::from_ymd_opt(2019, 3, 10).unwrap();
+    let mut t: todo::TaskVec = Vec::new();
+    let mut c = todo::Conf::default();
+    c.auto_create_date = true;
+    c.subject = Some("pay rent due:2d".to_owned());
+    let n = todo::add_with_base(&mut t, &c, base);
+    assert_eq!(t[n].create_date, Some(base));
+    assert_eq!(t[n].due_date, chrono::NaiveDate::from_ymd_opt(2019, 3, 12));
+    assert_eq!(t[n].to_string(), "2019-03-10 pay rent due:2019-03-12");
+
+    c.subject = Some("2018-01-01 call mother".to_owned());
+    let n = todo::add_with_base(&mut t, &c, base);
+    assert_eq!(t[n].create_date, chrono::NaiveDate::from_ymd_opt(2018, 1, 1));
+}
+
+#[test]
+fn add_many_test() {
+    let mut t = init_tasks();
+    let orig_len = t.len();
+    let mut c = todo::Conf::default();
+    c.auto_create_date = true;
+    let subjects = vec!["call mother".to_string(), "  ".to_string(), "2018-01-01 fix car".to_string()];
+    let ids = todo::add_many(&mut t, &subjects, &c);
+    assert_eq!(ids, vec![orig_len, todo::INVALID_ID, orig_len + 1]);
+    assert_eq!(t.len(), orig_len + 2);
+    assert_eq!(t[orig_len].subject, "call mother");
+    assert_eq!(t[orig_len].create_date, Some(chrono::Local::now().date_naive()));
+    assert_eq!(t[orig_len + 1].create_date, chrono::NaiveDate::from_ymd_opt(2018, 1, 1));
+
+    let ids = todo::add_many(&mut t, &[], &c);
+    assert!(ids.is_empty());
+}
+
+#[test]
+fn merge_test() {
+    let base = init_tasks();
+    let now = chrono::Local::now().date_naive();
+    let other: todo::TaskVec = vec![
+        base[1].clone(),
+        todotxt::Task::parse("buy milk @store", now),
+        base[3].clone(),
+        todotxt::Task::parse("buy milk @store", now),
+        todotxt::Task::parse("Buy milk @store", now),
+        todotxt::Task::parse("fix car tmr:1600000000", now),
+        todotxt::Task::parse("fix car tmr:1600000100", now),
+    ];
+    let merged = todo::merge(&base, &other);
+    assert_eq!(merged.len(), base.len() + 4);
+    assert_eq!(&merged[..base.len()], &base[..]);
+    let added: Vec<String> = merged[base.len()..].iter().map(|t| t.to_string()).collect();
+    assert_eq!(added, vec!["buy milk @store", "Buy milk @store", "fix car tmr:1600000000", "fix car tmr:1600000100"]);
+
+    assert_eq!(todo::merge(&base, &base).len(), base.len());
+    assert_eq!(todo::merge(&[], &other).len(), 6);
+}
+
+#[test]
+fn rename_ignore_case_test() {
+    let now = chrono::Local::now().date_naive();
+    let mut tasks: todo::TaskVec = vec![
+        todotxt::Task::parse("fix brakes +Car @Garage", now),
+        todotxt::Task::parse("wash +CAR +auto", now),
+        todotxt::Task::parse("wash +car +Car", now),
+    ];
+    let mut c = todo::Conf::default();
+    c.projects = todo::ListTagChange { action: todo::Action::Replace, value: vec!["Car+car".to_string()] };
+    c.contexts = todo::ListTagChange { action: todo::Action::Replace, value: vec!["garage@Garage2".to_string()] };
+    let changed = todo::edit(&mut tasks, None, &c);
+    assert_eq!(changed, vec![true, true, true]);
+    assert_eq!(tasks[0].subject, "fix brakes +car @Garage2");
+    assert_eq!(tasks[0].projects, vec!["car".to_string()]);
+    assert_eq!(tasks[0].contexts, vec!["Garage2".to_string()]);
+    assert_eq!(tasks[1].subject, "wash +car +auto");
+    assert_eq!(tasks[1].projects, vec!["auto".to_string(), "car".to_string()]);
+    assert_eq!(tasks[2].subject, "wash +car");
+    assert_eq!(tasks[2].projects, vec!["car".to_string()]);
+
+    // renaming to an existing project does not duplicate it
+    c.contexts = todo::ListTagChange::default();
+    c.projects = todo::ListTagChange { action: todo::Action::Replace, value: vec!["AUTO+car".to_string()] };
+    let changed = todo::edit(&mut tasks, Some(&vec![1]), &c);
+    assert_eq!(changed, vec![true]);
+    assert_eq!(tasks[1].subject, "wash +car");
+    assert_eq!(tasks[1].projects, vec!["car".to_string()]);
+
+    // nothing to rename
+    let changed = todo::edit(&mut tasks, Some(&vec![0]), &c);
+    assert_eq!(changed, vec![false]);
+    assert_eq!(tasks[0].projects, vec!["car".to_string()]);
+}
+
+#[test]
+fn validate_date_order_test() {
+    let base = chrono::NaiveDate::from_ymd_opt(2023, 5, 10).unwrap();
+    let d = |m, d| chrono::NaiveDate::from_ymd_opt(2023, m, d).unwrap();
+    let set = |dt| todo::DateTagChange { action: todo::Action::Set, value: todo::NewDateValue::Date(dt) };
+    let orig = "pay rent t:2023-06-01 due:2023-06-10";
+    let mut c = todo::Conf::default();
+    c.validate_date_order = true;
+
+    c.due = set(d(5, 20));
+    let mut tasks = vec![todotxt::Task::parse(orig, base)];
+    assert_eq!(todo::edit(&mut tasks, None, &c), vec![false]);
+    assert_eq!(tasks[0].to_string(), orig);
+
+    c.due = todo::DateTagChange::default();
+    c.thr = set(d(6, 15));
+    assert_eq!(todo::edit(&mut tasks, None, &c), vec![false]);
+    assert_eq!(tasks[0].to_string(), orig);
+
+    // both dates are checked after applying both changes
+    c.due = set(d(5, 20));
+    c.thr = set(d(5, 15));
+    assert_eq!(todo::edit(&mut tasks, None, &c), vec![true]);
+    assert_eq!(tasks[0].due_date, Some(d(5, 20)));
+    assert_eq!(tasks[0].threshold_date, Some(d(5, 15)));
+
+    // either both changes are applied or none
+    c.due = set(d(5, 25));
+    c.thr = set(d(5, 30));
+    assert_eq!(todo::edit(&mut tasks, None, &c), vec![false]);
+    assert_eq!(tasks[0].due_date, Some(d(5, 20)));
+    assert_eq!(tasks[0].threshold_date, Some(d(5, 15)));
+
+    c.validate_date_order = false;
+    assert_eq!(todo::edit(&mut tasks, None, &c), vec![true]);
+    assert_eq!(tasks[0].due_date, Some(d(5, 25)));
+    assert_eq!(tasks[0].threshold_date, Some(d(5, 30)));
+}
+
+#[test]
+fn calendar_range_test() {
+    // 2020-07-15 is Wednesday
+    let today = chrono::NaiveDate::from_ymd_opt(2020, 7, 15).unwrap();
+    let d = |day| chrono::NaiveDate::from_ymd_opt(2020, 7, day).unwrap();
+    let rng = todo_lib::human_date::CalendarRange::parse("2w").unwrap();
+    let mut c = todo::Conf::default();
+    assert_eq!(todo::calendar_range(today, &rng, &c), (d(13), d(26)));
+    c.week_start = todo_lib::human_date::WeekStart::Sunday;
+    assert_eq!(todo::calendar_range(today, &rng, &c), (d(12), d(25)));
+
+    // the old boolean flag still works
+    assert_eq!(todo_lib::human_date::calendar_first_day(today, &rng, true), d(12));
+    assert_eq!(todo_lib::human_date::calendar_last_day(today, &rng, false), d(26));
+}
+
+#[test]
+fn add_date_phrases_test() {
+    // 2023-08-16 is Wednesday
+    let base = chrono::NaiveDate::from_ymd_opt(2023, 8, 16).unwrap();
+    let data: Vec<(&str, &str)> = vec![
+        ("call mom due:tomorrow", "call mom due:2023-08-17"),
+        ("call mom due:next friday @home", "call mom due:2023-08-18 @home"),
+        ("pay rent due:End   Of  Month +home", "pay rent due:2023-08-31 +home"),
+        ("book tickets t:in 2 weeks due:in 3 weeks", "book tickets t:2023-08-30 due:2023-09-06"),
+        ("fix car due:2d", "fix car due:2023-08-18"),
+        ("fix car due:2023-09-01 in 2 days", "fix car due:2023-09-01 in 2 days"),
+        ("read book due:someday", "read book due:someday"),
+        ("read book due:next time", "read book due:next time"),
+    ];
+    for (subj, res) in data {
+        let mut tasks: todo::TaskVec = Vec::new();
+        let mut c = todo::Conf::default();
+        c.subject = Some(subj.to_string());
+        let id = todo::add_with_base(&mut tasks, &c, base);
+        assert_eq!(tasks[id].to_string(), res, "{subj}");
+    }
+
+    // subjects without phrases are added as is
+    for subj in ["call  mom\tdue:someday  later", "fix car due:2023-09-01  +car", "due: t:soon-ish  plan"] {
+        let mut tasks: todo::TaskVec = Vec::new();
+        let mut c = todo::Conf::default();
+        c.subject = Some(subj.to_string());
+        let id = todo::add_with_base(&mut tasks, &c, base);
+        assert_eq!(tasks[id], todotxt::Task::parse(subj, base), "{subj}");
+    }
+
+    // only the phrase is replaced
+    let mut tasks: todo::TaskVec = Vec::new();
+    let mut c = todo::Conf::default();
+    c.subject = Some("call bob due:next friday about the car".to_string());
+    let id = todo::add_with_base(&mut tasks, &c, base);
+    assert_eq!(tasks[id].subject, "call bob due:2023-08-18 about the car");
+
+    let mut tasks: todo::TaskVec = Vec::new();
+    let mut c = todo::Conf::default();
+    c.subject = Some("plan trip due:end of week".to_string());
+    c.week_start = todo_lib::human_date::WeekStart::Sunday;
+    let id = todo::add_with_base(&mut tasks, &c, base);
+    assert_eq!(tasks[id].due_date, chrono::NaiveDate::from_ymd_opt(2023, 8, 19));
+}
+
+#[test]
+fn load_restore_priority_test() {
+    let content = "x testb pri:B\nx 2020-02-02 testc pri:high\n(C) testd pri:A\n";
+    let filename = std::env::temp_dir().join(format!("todo_lib_restore_pri_{}.txt", std::process::id()));
+    std::fs::write(&filename, content).unwrap();
+
+    // disabled by default: the file is saved as is
+    let (tasks, _) = todo::load_with_options(&filename, &todo::LoadOptions::default()).unwrap();
+    assert_eq!(tasks[0].priority, todotxt::NO_PRIORITY);
+    todo::save(&tasks, &filename).unwrap();
+    assert_eq!(std::fs::read_to_string(&filename).unwrap(), content);
+
+    let mut opts = todo::LoadOptions::default();
+    opts.parse.restore_priority = true;
+    let (mut tasks, _) = todo::load_with_options(&filename, &opts).unwrap();
+    let _ = std::fs::remove_file(&filename);
+    assert_eq!(tasks[0].priority, 1);
+    assert_eq!(tasks[0].to_string(), "x (B) testb pri:B");
+    assert_eq!(tasks[1].priority, todotxt::NO_PRIORITY);
+    assert_eq!(tasks[2].priority, 2);
+    tasks[0].uncomplete(todotxt::CompletionMode::PriorityToTag);
+    assert_eq!(tasks[0].to_string(), "(B) testb");
+}
+
+#[test]
+fn edit_touch_modified_test() {
+    let now = chrono::Local::now().date_naive();
+    let today = todotxt::format_date(now);
+    let mut tasks: todo::TaskVec = vec![
+        todotxt::Task::parse("call mother +family modified:2020-01-01", now),
+        todotxt::Task::parse("fix car +car", now),
+        todotxt::Task::parse("pay rent +home", now),
+    ];
+    let mut c = todo::Conf::default();
+    c.projects = todo::ListTagChange { action: todo::Action::Set, value: vec!["family".to_string()] };
+
+    // disabled by default
+    let changed = todo::edit(&mut tasks, Some(&vec![1]), &c);
+    assert_eq!(changed, vec![true]);
+    assert_eq!(tasks[1].modified_date(), None);
+
+    c.touch_modified = true;
+    c.projects.value = vec!["home".to_string()];
+    let changed = todo::edit(&mut tasks, Some(&vec![0, 2]), &c);
+    assert_eq!(changed, vec![true, false]);
+    assert_eq!(tasks[0].to_string(), format!("call mother +family modified:{today} +home"));
+    assert_eq!(tasks[2].modified_date(), None);
+
+    let filter = tfilter::Conf { modified_since: Some(now), ..Default::default() };
+    assert_eq!(tfilter::filter(&tasks, &filter), vec![0]);
+
+    // new subject
+    c = todo::Conf::default();
+    c.touch_modified = true;
+    c.subject = Some("fix bike +bike".to_string());
+    todo::edit(&mut tasks, Some(&vec![1]), &c);
+    assert_eq!(tasks[1].to_string(), format!("fix bike +bike modified:{today}"));
+}
+
+#[test]
+fn load_strip_invalid_rec_test() {
+    let content = "call mother rec: +family\npay rent rec:1m due:2023-09-01\nfix car rec:xyz\n";
+    let filename = std::env::temp_dir().join(format!("todo_lib_strip_rec_{}.txt", std::process::id()));
+    std::fs::write(&filename, content).unwrap();
+
+    let mut opts = todo::LoadOptions::default();
+    opts.parse.strip_invalid_rec = true;
+    let (tasks, _) = todo::load_with_options(&filename, &opts).unwrap();
+    todo::save(&tasks, &filename).unwrap();
+    let saved = std::fs::read_to_string(&filename).unwrap();
+    let _ = std::fs::remove_file(&filename);
+    assert_eq!(saved, "call mother +family\npay rent rec:1m due:2023-09-01\nfix car\n");
+}